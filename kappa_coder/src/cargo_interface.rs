@@ -1,4 +1,175 @@
 use std::process::Command;
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Clone, Serialize)]
+pub struct BuildDiagnostic {
+    pub level: String,
+    pub message: String,
+    pub code: Option<String>,
+    pub file: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub rendered: Option<String>,
+    pub object: Option<String>,
+    pub section: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct BuildArtifact {
+    pub package_id: String,
+    pub kinds: Vec<String>,
+    pub filenames: Vec<String>,
+}
+
+impl BuildArtifact {
+    // Path packages are reported as `path+file://<dir>#<version>`, or `<name> <version> (path+file://<dir>)` by older cargo.
+    pub fn package_path(&self) -> Option<String> {
+        let (_, package_path) = self.package_id.split_once("path+file://")?;
+        package_path.split(|c| c == '#' || c == ')').next().map(|p| p.to_string())
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct BuildReport {
+    pub success: bool,
+    pub diagnostics: Vec<BuildDiagnostic>,
+    pub artifacts: Vec<BuildArtifact>,
+}
+
+impl BuildReport {
+    pub fn error_count(&self) -> usize {
+        self.diagnostics.iter().filter(|d| d.level == "error").count()
+    }
+
+    fn from_cargo_output(crate_path: &String, success: bool, stdout: &[u8], stderr: &[u8]) -> Self {
+        let mut diagnostics: Vec<BuildDiagnostic> = Vec::new();
+        let mut artifacts: Vec<BuildArtifact> = Vec::new();
+        for line in String::from_utf8_lossy(stdout).lines() {
+            let message: Value = match serde_json::from_str(line) {
+                Ok(message) => message,
+                Err(_) => continue,
+            };
+            if message["reason"] == "compiler-artifact" {
+                let strings = |value: &Value| value.as_array()
+                    .map(|a| a.iter().filter_map(|v| v.as_str()).map(|v| v.to_string()).collect())
+                    .unwrap_or_default();
+                artifacts.push(BuildArtifact {
+                    package_id: message["package_id"].as_str().unwrap_or_default().to_string(),
+                    kinds: strings(&message["target"]["kind"]),
+                    filenames: strings(&message["filenames"]),
+                });
+                continue;
+            }
+            if message["reason"] != "compiler-message" {
+                continue;
+            }
+            if let Some(diagnostic) = BuildDiagnostic::from_compiler_message(crate_path, &message["message"]) {
+                diagnostics.push(diagnostic);
+            }
+        }
+        let mut report = BuildReport { success, diagnostics, artifacts };
+        if !success && report.error_count() == 0 {
+            // Failures that happen before rustc runs (manifest errors, missing dependencies) are only on stderr.
+            report.diagnostics.push(BuildDiagnostic {
+                level: "error".to_string(),
+                message: String::from_utf8_lossy(stderr).trim().to_string(),
+                code: None,
+                file: None,
+                line: None,
+                column: None,
+                rendered: None,
+                object: None,
+                section: None,
+            });
+        }
+        report
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct TestOutcome {
+    pub name: String,
+    pub status: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct TestReport {
+    pub success: bool,
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub tests: Vec<TestOutcome>,
+    pub diagnostics: Vec<BuildDiagnostic>,
+}
+
+impl TestReport {
+    fn from_cargo_output(crate_path: &String, success: bool, stdout: &[u8], stderr: &[u8]) -> Self {
+        let build_report = BuildReport::from_cargo_output(crate_path, true, stdout, stderr);
+        let build_errors = build_report.error_count();
+        let mut tests: Vec<TestOutcome> = Vec::new();
+        // libtest prints one "test <name> ... <status>" line per test case.
+        for line in String::from_utf8_lossy(stdout).lines() {
+            let line = match line.strip_prefix("test ") {
+                Some(line) => line,
+                None => continue,
+            };
+            if let Some((name, status)) = line.rsplit_once(" ... ") {
+                tests.push(TestOutcome {
+                    name: name.trim().to_string(),
+                    status: status.trim().to_string(),
+                });
+            }
+        }
+        let mut report = TestReport {
+            success,
+            passed: tests.iter().filter(|t| t.status == "ok").count(),
+            failed: tests.iter().filter(|t| t.status == "FAILED").count(),
+            ignored: tests.iter().filter(|t| t.status.starts_with("ignored")).count(),
+            tests,
+            diagnostics: build_report.diagnostics,
+        };
+        if !success && report.failed == 0 && build_errors == 0 {
+            report.diagnostics.push(BuildDiagnostic {
+                level: "error".to_string(),
+                message: String::from_utf8_lossy(stderr).trim().to_string(),
+                code: None,
+                file: None,
+                line: None,
+                column: None,
+                rendered: None,
+                object: None,
+                section: None,
+            });
+        }
+        report
+    }
+}
+
+impl BuildDiagnostic {
+    fn from_compiler_message(crate_path: &String, message: &Value) -> Option<Self> {
+        let level = message["level"].as_str()?;
+        if level != "error" && level != "warning" {
+            return None;
+        }
+        let primary_span = message["spans"]
+            .as_array()
+            .and_then(|spans| spans.iter().find(|span| span["is_primary"] == true));
+        Some(BuildDiagnostic {
+            level: level.to_string(),
+            message: message["message"].as_str().unwrap_or_default().to_string(),
+            code: message["code"]["code"].as_str().map(|code| code.to_string()),
+            file: primary_span
+                .and_then(|span| span["file_name"].as_str())
+                .map(|file_name| format!("{}/{}", crate_path, file_name)),
+            line: primary_span.and_then(|span| span["line_start"].as_u64()).map(|line| line as usize),
+            column: primary_span.and_then(|span| span["column_start"].as_u64()).map(|column| column as usize),
+            rendered: message["rendered"].as_str().map(|rendered| rendered.to_string()),
+            object: None,
+            section: None,
+        })
+    }
+}
 
 pub struct CargoInterface {
     pub cargo_path: String,
@@ -6,6 +177,16 @@ pub struct CargoInterface {
 }
 
 impl CargoInterface {
+    // Crates of the kappa library every generated project depends on, with their current location.
+    pub fn library_dependencies(&self) -> Vec<(String, String)> {
+        vec![
+            ("processor_engine".to_string(), format!("{}/processor_engine", self.library_path)),
+            ("stream_proc_macro".to_string(), format!("{}/processor_engine/src/stream_proc_macro", self.library_path)),
+            ("data_model".to_string(), format!("{}/data_model", self.library_path)),
+            ("utils".to_string(), format!("{}/utils", self.library_path)),
+        ]
+    }
+
     pub fn cargo_add_commands(&self, path: String) -> Result<(), String> {
         let curr_dir = std::env::current_dir().unwrap();
         println!("Setting current dir to {}", path);
@@ -13,48 +194,89 @@ impl CargoInterface {
             return Err(format!("Something went wrong in crate creation"));
         }
         println!("Library path: {}", self.library_path);
-        Command::new(&self.cargo_path).arg("add").arg("num-traits").status().expect("Failed to create the project");
-        Command::new(&self.cargo_path).arg("add").arg("serde_json").status().expect("Failed to create the project");
-        Command::new(&self.cargo_path).arg("add").arg("serde").arg("--features").arg("derive").status().expect("Failed to create the project");
-        Command::new(&self.cargo_path).arg("add").arg("processor_engine").arg("--path").arg(format!("{}/processor_engine", self.library_path)).status().expect("Failed to create the project");
-        Command::new(&self.cargo_path).arg("add").arg("stream_proc_macro").arg("--path").arg(format!("{}/processor_engine/src/stream_proc_macro", self.library_path)).status().expect("Failed to create the project");
-        Command::new(&self.cargo_path).arg("add").arg("data_model").arg("--path").arg(format!("{}/data_model", self.library_path)).status().expect("Failed to create the project");
-        Command::new(&self.cargo_path).arg("add").arg("utils").arg("--path").arg(format!("{}/utils", self.library_path)).status().expect("Failed to create the project");
-        Command::new("mkdir").arg("-p").arg(format!("{}/.project", path)).status().expect("Failed to create the .project folder");
+        let mut added = self.run_cargo_command(vec!["add", "num-traits"])
+            .and_then(|_| self.run_cargo_command(vec!["add", "serde_json"]))
+            .and_then(|_| self.run_cargo_command(vec!["add", "serde", "--features", "derive"]));
+        for (dependency, dependency_path) in self.library_dependencies() {
+            added = added.and_then(|_| self.run_cargo_command(vec!["add", dependency.as_str(), "--path", dependency_path.as_str()]));
+        }
+        let added = added.and_then(|_| {
+            std::fs::create_dir_all(format!("{}/.project", path)).map_err(|e| format!("Failed to create the .project folder: {}", e))
+        });
         let res = std::env::set_current_dir(curr_dir);
         match res {
             Ok(_) => (),
             Err(e) => return Err(format!("Failed to set back the current directory: {}", e)),
         }
+        added
+    }
+
+    // Runs cargo in the current directory, failing when it cannot be started or exits with an error.
+    fn run_cargo_command(&self, args: Vec<&str>) -> Result<(), String> {
+        let output = Command::new(&self.cargo_path).args(&args).output()
+            .map_err(|e| format!("Failed to run {}: {}", self.cargo_path, e))?;
+        if !output.status.success() {
+            return Err(format!("cargo {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+        }
         Ok(())
     }
 
     pub fn cargo_new_library(&self, path: String) -> Result<(), String> {
-        Command::new(&self.cargo_path).arg("new").arg("--lib").arg(&path).status().expect("Failed to create the project");
-        Ok(())
+        self.run_cargo_command(vec!["new", "--lib", path.as_str()])
     }
     pub fn cargo_new_application(&self, path: String) -> Result<(), String> {
-        Command::new(&self.cargo_path).arg("new").arg(&path).status().expect("Failed to create the project");
-        Ok(())
+        self.run_cargo_command(vec!["new", path.as_str()])
     }
 
-    pub fn cargo_build(&self, path: String, build_type: String) -> Result<(), String> {
+    fn run_cargo(&self, path: &String, args: Vec<String>) -> Result<std::process::Output, String> {
         let curr_dir = std::env::current_dir().unwrap();
         println!("Setting current dir to {}", path);
-        if std::env::set_current_dir(&path).is_err() {
+        if std::env::set_current_dir(path).is_err() {
             return Err(format!("Something went wrong in crate creation"));
         }
-        if build_type == "debug" {
-            Command::new(&self.cargo_path).arg("build").status().expect("Failed to build the project");
-        } else {
-            Command::new(&self.cargo_path).arg("build").arg(format!("--{}", build_type)).status().expect("Failed to build the project");
-        }
+        let output = Command::new(&self.cargo_path).args(args).output();
         let res = std::env::set_current_dir(curr_dir);
         match res {
             Ok(_) => (),
             Err(e) => return Err(format!("Failed to set back the current directory: {}", e)),
         }
-        Ok(())
+        output.map_err(|e| format!("Failed to run cargo in {}: {}", path, e))
+    }
+
+    pub fn cargo_build(&self, path: String, build_type: String) -> Result<BuildReport, String> {
+        let mut args: Vec<String> = vec!["build".to_string(), "--message-format=json".to_string()];
+        if build_type != "debug" {
+            args.push(format!("--{}", build_type));
+        }
+        let output = self.run_cargo(&path, args)?;
+        Ok(BuildReport::from_cargo_output(&path, output.status.success(), &output.stdout, &output.stderr))
+    }
+
+    pub fn cargo_check(&self, path: String) -> Result<BuildReport, String> {
+        let args: Vec<String> = vec!["check".to_string(), "--all-targets".to_string(), "--message-format=json".to_string()];
+        let output = self.run_cargo(&path, args)?;
+        Ok(BuildReport::from_cargo_output(&path, output.status.success(), &output.stdout, &output.stderr))
+    }
+
+    pub fn cargo_build_cdylib(&self, path: String, build_type: String) -> Result<BuildReport, String> {
+        let mut args: Vec<String> = vec![
+            "rustc".to_string(),
+            "--lib".to_string(),
+            "--crate-type".to_string(),
+            "cdylib".to_string(),
+            "--message-format=json".to_string(),
+        ];
+        if build_type != "debug" {
+            args.push(format!("--{}", build_type));
+        }
+        let output = self.run_cargo(&path, args)?;
+        Ok(BuildReport::from_cargo_output(&path, output.status.success(), &output.stdout, &output.stderr))
+    }
+
+    pub fn cargo_test(&self, path: String) -> Result<TestReport, String> {
+        let args: Vec<String> = vec!["test".to_string(), "--message-format=json".to_string()];
+        let output = self.run_cargo(&path, args)?;
+        Ok(TestReport::from_cargo_output(&path, output.status.success(), &output.stdout, &output.stderr))
     }
     pub fn delete_project(&self, path: String) -> Result<(), String> {
         match std::fs::remove_dir_all(path.clone()) {
@@ -62,4 +284,74 @@ impl CargoInterface {
             Err(e) => Err(format!("Error deleting project at {}: {}", path, e)),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lines printed by `cargo build --message-format=json` for a crate at /work/dsp.
+    const BUILD_OUTPUT: &str = r#"{"reason":"compiler-artifact","package_id":"path+file:///work/dsp#0.1.0","target":{"kind":["cdylib","rlib"],"name":"dsp"},"filenames":["/work/dsp/target/debug/libdsp.so","/work/dsp/target/debug/libdsp.rlib"],"fresh":false}
+{"reason":"compiler-message","package_id":"path+file:///work/dsp#0.1.0","message":{"rendered":"error[E0308]: mismatched types\n --> src/gain.rs:42:20\n","code":{"code":"E0308","explanation":null},"level":"error","message":"mismatched types","spans":[{"file_name":"src/lib.rs","line_start":3,"column_start":1,"is_primary":false},{"file_name":"src/gain.rs","line_start":42,"column_start":20,"is_primary":true}],"children":[]}}
+{"reason":"compiler-message","package_id":"path+file:///work/dsp#0.1.0","message":{"rendered":"warning: unused variable: `x`\n","code":{"code":"unused_variables","explanation":null},"level":"warning","message":"unused variable: `x`","spans":[{"file_name":"src/gain.rs","line_start":7,"column_start":13,"is_primary":true}],"children":[]}}
+{"reason":"compiler-message","package_id":"path+file:///work/dsp#0.1.0","message":{"rendered":"error: aborting due to 1 previous error\n","code":null,"level":"error","message":"aborting due to 1 previous error","spans":[],"children":[]}}
+{"reason":"compiler-message","package_id":"path+file:///work/dsp#0.1.0","message":{"rendered":"note: see issue\n","code":null,"level":"note","message":"see issue","spans":[],"children":[]}}
+{"reason":"build-finished","success":false}
+   Compiling dsp v0.1.0 (/work/dsp)"#;
+
+    #[test]
+    fn compiler_messages_become_diagnostics() {
+        let report = BuildReport::from_cargo_output(&"/work/dsp".to_string(), false, BUILD_OUTPUT.as_bytes(), b"error: could not compile `dsp`");
+        let located = report.diagnostics.iter()
+            .map(|d| (d.level.as_str(), d.code.as_deref(), d.file.as_deref(), d.line, d.column))
+            .collect::<Vec<_>>();
+        assert_eq!(located, vec![
+            ("error", Some("E0308"), Some("/work/dsp/src/gain.rs"), Some(42), Some(20)),
+            ("warning", Some("unused_variables"), Some("/work/dsp/src/gain.rs"), Some(7), Some(13)),
+            ("error", None, None, None, None),
+        ]);
+        assert_eq!(report.error_count(), 2);
+        assert!(report.diagnostics[0].rendered.as_deref().unwrap().starts_with("error[E0308]"));
+    }
+
+    #[test]
+    fn artifacts_name_their_package_folder() {
+        let report = BuildReport::from_cargo_output(&"/work/dsp".to_string(), true, BUILD_OUTPUT.as_bytes(), b"");
+        assert_eq!(report.artifacts.len(), 1);
+        assert_eq!(report.artifacts[0].kinds, vec!["cdylib", "rlib"]);
+        assert_eq!(report.artifacts[0].package_path().as_deref(), Some("/work/dsp"));
+        let older = BuildArtifact { package_id: "dsp 0.1.0 (path+file:///work/dsp)".to_string(), kinds: Vec::new(), filenames: Vec::new() };
+        assert_eq!(older.package_path().as_deref(), Some("/work/dsp"));
+    }
+
+    #[test]
+    fn failures_without_compiler_errors_report_stderr() {
+        let stderr = b"error: failed to parse manifest at `/work/dsp/Cargo.toml`\n";
+        let report = BuildReport::from_cargo_output(&"/work/dsp".to_string(), false, b"", stderr);
+        assert_eq!(report.error_count(), 1);
+        assert_eq!(report.diagnostics[0].message, "error: failed to parse manifest at `/work/dsp/Cargo.toml`");
+        assert!(report.diagnostics[0].file.is_none());
+        // A successful build has nothing to report from stderr.
+        assert!(BuildReport::from_cargo_output(&"/work/dsp".to_string(), true, b"", b"warning: unused manifest key").diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_lines_are_counted() {
+        let stdout = "running 3 tests\ntest tests::a ... ok\ntest tests::b ... FAILED\ntest tests::c ... ignored, slow\n\ntest result: FAILED. 1 passed; 1 failed; 1 ignored";
+        let report = TestReport::from_cargo_output(&"/work/dsp".to_string(), false, stdout.as_bytes(), b"");
+        assert_eq!((report.passed, report.failed, report.ignored), (1, 1, 1));
+        assert_eq!(report.tests[1].name, "tests::b");
+        assert!(report.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn crate_creation_reports_cargo_failures() {
+        let missing_cargo = CargoInterface { cargo_path: "/nonexistent/cargo".to_string(), library_path: "/nonexistent".to_string() };
+        let error = missing_cargo.cargo_new_library("/nonexistent/dsp".to_string()).unwrap_err();
+        assert!(error.starts_with("Failed to run /nonexistent/cargo"), "{}", error);
+        // `false` starts but exits with an error, as cargo does when the folder already exists.
+        let failing_cargo = CargoInterface { cargo_path: "false".to_string(), library_path: "/nonexistent".to_string() };
+        let error = failing_cargo.cargo_new_application("/nonexistent/app".to_string()).unwrap_err();
+        assert!(error.starts_with("cargo new /nonexistent/app failed"), "{}", error);
+    }
+}
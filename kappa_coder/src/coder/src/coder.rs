@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::io::Write;
 use rand::{Rng, rng, random_range};
+use serde::{Serialize, Deserialize};
 pub fn to_snake_case(s: &str) -> String {
     let mut result = String::new();
     let mut chars = s.chars().peekable();
@@ -22,9 +24,139 @@ pub fn to_snake_case(s: &str) -> String {
     result
 }
 
+// Renames an object name when it is `old_name` or one of its children.
+pub fn rename_prefix(name: &String, old_name: &String, new_name: &String) -> String {
+    if name == old_name {
+        new_name.clone()
+    } else if name.starts_with(&format!("{}.", old_name)) {
+        format!("{}{}", new_name, &name[old_name.len()..])
+    } else {
+        name.clone()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SectionSpan {
+    pub section: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+// Joins the generated sections with newlines, recording the 1-based line range each one occupies.
+pub fn join_sections(sections: Vec<(String, String)>) -> (String, Vec<SectionSpan>) {
+    let mut spans: Vec<SectionSpan> = Vec::new();
+    let mut code_lines: Vec<String> = Vec::new();
+    let mut next_line: usize = 1;
+    for (section, code) in sections.into_iter() {
+        let line_count = code.split('\n').count();
+        spans.push(SectionSpan {
+            section,
+            start_line: next_line,
+            end_line: next_line + line_count - 1,
+        });
+        next_line += line_count;
+        code_lines.push(code);
+    }
+    (code_lines.join("\n"), spans)
+}
+
+pub const REGION_BEGIN: &str = "// kappa:begin";
+pub const REGION_END: &str = "// kappa:end";
+
+// Wraps user code between markers, so that edits made to the generated file can be read back.
+pub fn protected_region(indent: &str, part: String, code: Option<&String>) -> String {
+    let mut code_lines: Vec<String> = Vec::new();
+    code_lines.push(format!("{}{} {}", indent, REGION_BEGIN, part));
+    if let Some(code) = code.filter(|code| !code.is_empty()) {
+        code_lines.push(code.clone());
+    }
+    code_lines.push(format!("{}{}", indent, REGION_END));
+    code_lines.join("\n")
+}
+
+// Returns the name and content of every protected region found in a generated file.
+pub fn read_regions(content: &String) -> Result<Vec<(String, String)>, String> {
+    let mut regions: Vec<(String, String)> = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for (index, line) in content.split('\n').enumerate() {
+        let marker = line.trim();
+        if let Some(part) = marker.strip_prefix(REGION_BEGIN) {
+            if let Some((open_part, _)) = &current {
+                return Err(format!("line {}: region {} starts before region {} ends.", index + 1, part.trim(), open_part));
+            }
+            current = Some((part.trim().to_string(), Vec::new()));
+        } else if marker == REGION_END {
+            match current.take() {
+                Some((part, lines)) => regions.push((part, lines.join("\n"))),
+                None => return Err(format!("line {}: {} without a matching begin marker.", index + 1, REGION_END)),
+            }
+        } else if let Some((_, lines)) = current.as_mut() {
+            lines.push(line);
+        }
+    }
+    if let Some((part, _)) = current {
+        return Err(format!("region {} is never closed.", part));
+    }
+    Ok(regions)
+}
+
+// Regions of a generated file whose content differs from the stored user code.
+pub fn edited_regions<P>(file_path: &String, parts: Vec<P>, user_codes: &HashMap<P, String>) -> Result<Vec<(P, String)>, String>
+where
+    P: std::fmt::Debug + Eq + std::hash::Hash,
+{
+    let mut edited: Vec<(P, String)> = Vec::new();
+    if !std::path::Path::new(file_path).exists() {
+        return Ok(edited);
+    }
+    let content = std::fs::read_to_string(file_path).map_err(|e| format!("Error reading {}: {}", file_path, e))?;
+    let mut regions = read_regions(&content).map_err(|e| format!("{}: {}", file_path, e))?;
+    for part in parts.into_iter() {
+        let part_name = format!("{:?}", part);
+        let position = match regions.iter().position(|(region, _)| *region == part_name) {
+            Some(position) => position,
+            None => continue,
+        };
+        let (_, code) = regions.remove(position);
+        if user_codes.get(&part).map_or("", |c| c.as_str()) != code {
+            edited.push((part, code));
+        }
+    }
+    if let Some((region, _)) = regions.first() {
+        return Err(format!("{}: unknown region {}.", file_path, region));
+    }
+    Ok(edited)
+}
+
+// Regions whose user code names a port or settable by its quoted name, as the block accessors do.
+pub fn regions_quoting<P>(user_codes: &HashMap<P, String>, name: &String) -> Vec<String>
+where
+    P: std::fmt::Debug,
+{
+    let quoted = format!("\"{}\"", name);
+    let mut regions = user_codes.iter()
+        .filter(|(_, code)| code.contains(&quoted))
+        .map(|(part, _)| format!("{:?}", part))
+        .collect::<Vec<String>>();
+    regions.sort();
+    regions
+}
+
 pub trait Coder: Send + Sync + std::any::Any {
     fn generate(&mut self) -> Result<(), String>;
 
+    // Content generate() would write to the file, without touching the disk.
+    fn render(&mut self) -> Result<String, String>;
+
+    // Points the coder to the crate found at a new location.
+    fn relocate(&mut self, path: String);
+
+    // Reads the protected regions edited in the generated file back into the model.
+    // Returns the names of the sections that changed.
+    fn sync_from_file(&mut self) -> Result<Vec<String>, String> {
+        Ok(Vec::new())
+    }
+
     fn get_tmp_file(&self) -> String {
         const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
         let mut rng = rng();
@@ -57,7 +189,93 @@ pub trait Coder: Send + Sync + std::any::Any {
     }
     fn get_path(&self) -> String;
 
+    fn get_file_path(&self) -> String;
+
+    fn get_save_path(&self) -> String;
+
+    fn get_sections(&self) -> &Vec<SectionSpan>;
+
+    fn find_section(&self, line: usize) -> Option<String> {
+        self.get_sections()
+            .iter()
+            .find(|span| span.start_line <= line && line <= span.end_line)
+            .map(|span| span.section.clone())
+    }
+
     fn as_any(&self) -> &dyn std::any::Any;
 
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum Part {
+        Head,
+        Body,
+    }
+
+    fn generated(head: Option<&String>, body: Option<&String>) -> String {
+        [
+            "fn main() {".to_string(),
+            protected_region("", format!("{:?}", Part::Head), head),
+            protected_region("    ", format!("{:?}", Part::Body), body),
+            "}".to_string(),
+        ].join("\n")
+    }
+
+    fn write_generated(name: &str, content: &String) -> String {
+        let path = std::env::temp_dir().join(format!("kappa_regions_{}_{}.rs", name, std::process::id()));
+        std::fs::write(&path, content).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn unchanged_regions_round_trip() {
+        let mut user_codes: HashMap<Part, String> = HashMap::new();
+        user_codes.insert(Part::Body, "    let x = 1;\n\n    println!(\"{}\", x);".to_string());
+        let content = generated(user_codes.get(&Part::Head), user_codes.get(&Part::Body));
+        assert_eq!(read_regions(&content).unwrap(), vec![
+            ("Head".to_string(), "".to_string()),
+            ("Body".to_string(), user_codes[&Part::Body].clone()),
+        ]);
+        let file_path = write_generated("unchanged", &content);
+        assert!(edited_regions(&file_path, vec![Part::Head, Part::Body], &user_codes).unwrap().is_empty());
+        let _ = std::fs::remove_file(&file_path);
+    }
+
+    #[test]
+    fn edited_region_is_read_back() {
+        let user_codes: HashMap<Part, String> = HashMap::new();
+        let edited = "    run();".to_string();
+        let file_path = write_generated("edited", &generated(None, Some(&edited)));
+        assert_eq!(edited_regions(&file_path, vec![Part::Head, Part::Body], &user_codes).unwrap(), vec![(Part::Body, edited)]);
+        let _ = std::fs::remove_file(&file_path);
+    }
+
+    #[test]
+    fn nested_region_is_refused() {
+        let content = format!("{} Head\n{} Body\n{}\n{}", REGION_BEGIN, REGION_BEGIN, REGION_END, REGION_END);
+        let error = read_regions(&content).unwrap_err();
+        assert_eq!(error, "line 2: region Body starts before region Head ends.");
+    }
+
+    #[test]
+    fn unclosed_region_is_refused() {
+        let content = format!("{} Head\nlet x = 1;", REGION_BEGIN);
+        assert_eq!(read_regions(&content).unwrap_err(), "region Head is never closed.");
+        let content = format!("let x = 1;\n{}", REGION_END);
+        assert_eq!(read_regions(&content).unwrap_err(), format!("line 2: {} without a matching begin marker.", REGION_END));
+    }
+
+    #[test]
+    fn unknown_region_is_refused() {
+        let content = format!("{}\n{} Footer\n{}", generated(None, None), REGION_BEGIN, REGION_END);
+        let file_path = write_generated("unknown", &content);
+        let error = edited_regions(&file_path, vec![Part::Head, Part::Body], &HashMap::new()).unwrap_err();
+        assert_eq!(error, format!("{}: unknown region Footer.", file_path));
+        let _ = std::fs::remove_file(&file_path);
+    }
+}
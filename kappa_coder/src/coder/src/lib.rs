@@ -1,4 +1,6 @@
 pub mod coder;
 pub mod lib_coder;
 pub mod main_coder;
-pub mod processor_coder;
\ No newline at end of file
+pub mod manifest_coder;
+pub mod processor_coder;
+pub mod schema;
\ No newline at end of file
@@ -1,8 +1,52 @@
 use rand::{Rng, rng, random_range};
 use data_model::modules::{ModuleStruct, Version};
+use semver::VersionReq;
 use serde::{Serialize, Deserialize};
-use crate::coder::{Coder, to_snake_case};
+use crate::coder::{Coder, SectionSpan, join_sections, to_snake_case};
+use crate::schema;
 
+fn is_module_identifier(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn check_text(field: &str, text: &String) -> Result<(), String> {
+    if text.contains('\0') {
+        return Err(format!("Module {} cannot contain NUL characters.", field));
+    }
+    Ok(())
+}
+
+// Content of a b"..." literal holding the UTF-8 bytes of the text.
+fn byte_string_content(text: &String) -> String {
+    let mut content = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'"' => content.push_str("\\\""),
+            b'\\' => content.push_str("\\\\"),
+            0x20..=0x7e => content.push(byte as char),
+            _ => content.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    content
+}
+
+// A dependency is the name of the module it needs, optionally followed by `@` and a semver requirement.
+pub fn split_module_dependency(dependency: &String) -> Result<(String, Option<VersionReq>), String> {
+    let (name, requirement) = match dependency.split_once('@') {
+        Some((name, requirement)) => (name.trim(), Some(requirement.trim())),
+        None => (dependency.trim(), None),
+    };
+    if !is_module_identifier(name) {
+        return Err(format!("Invalid module dependency {}: expected <name>[@<version requirement>].", dependency));
+    }
+    let requirement = match requirement {
+        Some(requirement) => Some(VersionReq::parse(requirement).map_err(|e| format!("Invalid version requirement in {}: {}", dependency, e))?),
+        None => None,
+    };
+    Ok((name.to_string(), requirement))
+}
+
+#[derive(Debug)]
 enum LibCoderParts {
     ModulesSection,
     ModuleStructSection,
@@ -18,14 +62,22 @@ pub struct LibCoder {
     crate_path: String,
     file_path: String,
     tmp_path: String,
+    #[serde(default)]
+    sections: Vec<SectionSpan>,
 }
 
 impl LibCoder {
     pub fn new(path: String) -> Self {
+        // Modules are registered by name in the library manager, so it defaults to the crate name.
+        let crate_name = std::path::Path::new(&path)
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or("")
+            .to_string();
         LibCoder {
             modules: Vec::new(),
             module_structs: ModuleStruct {
-                name: String::new(),
+                name: crate_name,
                 description: String::new(),
                 authors: String::new(),
                 release_date: String::new(),
@@ -36,21 +88,15 @@ impl LibCoder {
             crate_path: path.clone(),
             file_path: format!("{}/src/lib.rs", path.clone()),
             tmp_path: "".to_string(),
+            sections: Vec::new(),
         }
     }
     pub fn save(&self) -> Result<(), String> {
-        let json_string = serde_json::to_string(self).map_err(|e| format!("Error serializing LibCoder: {}", e))?;
-        std::fs::write(format!("{}/.project/lib_coder.json", self.crate_path), json_string).map_err(|e| format!("Error writing LibCoder file: {}", e))?;
-        Ok(())
+        schema::write_versioned(&self.get_save_path(), schema::LIB_CODER, self)
     }
 
     pub fn load(path: String) -> Result<Self, String> {
-        let json_data = std::fs::read_to_string(path).map_err(|e| format!("Error reading LibCoder file: {}", e))?;
-        let json_data = json_data.as_str();
-        match serde_json::from_str(json_data) {
-            Ok(coder) => Ok(coder),
-            Err(e) => Err(format!("Error deserializing LibCoder: {}", e)),
-        }
+        schema::read_versioned(&path, schema::LIB_CODER)
     }
     
     pub fn add_module(&mut self, module_name: String) {
@@ -63,9 +109,94 @@ impl LibCoder {
         self.modules.retain(|m| m != object_name);
     }
 
+    pub fn rename_module(&mut self, old_name: &String, new_name: &String) {
+        for module in self.modules.iter_mut().filter(|m| *m == old_name) {
+            *module = new_name.clone();
+        }
+    }
+
     pub fn get_modules(&self) -> &Vec<String> {
         &self.modules
     }
+
+    pub fn get_module_struct(&self) -> &ModuleStruct {
+        &self.module_structs
+    }
+    pub fn set_module_name(&mut self, name: String) -> Result<(), String> {
+        if !is_module_identifier(&name) {
+            return Err(format!("Invalid module name {}: use ASCII letters, digits, '_' and '-'.", name));
+        }
+        self.module_structs.name = name;
+        Ok(())
+    }
+    pub fn set_module_description(&mut self, description: String) -> Result<(), String> {
+        check_text("description", &description)?;
+        self.module_structs.description = description;
+        Ok(())
+    }
+    pub fn set_module_authors(&mut self, authors: String) -> Result<(), String> {
+        check_text("authors", &authors)?;
+        self.module_structs.authors = authors;
+        Ok(())
+    }
+    pub fn set_module_release_date(&mut self, release_date: String) -> Result<(), String> {
+        let parts = release_date.split('-').collect::<Vec<&str>>();
+        let valid = parts.len() == 3
+            && [4, 2, 2].iter().zip(parts.iter()).all(|(len, part)| part.len() == *len && part.chars().all(|c| c.is_ascii_digit()))
+            && (1..=12).contains(&parts[1].parse::<u32>().unwrap_or(0))
+            && (1..=31).contains(&parts[2].parse::<u32>().unwrap_or(0));
+        if !valid {
+            return Err(format!("Invalid release date {}, expected YYYY-MM-DD.", release_date));
+        }
+        self.module_structs.release_date = release_date;
+        Ok(())
+    }
+    // ModuleStruct only holds major.minor.build, so pre-release and build metadata are refused.
+    pub fn set_module_version(&mut self, version: String) -> Result<(), String> {
+        let parsed = semver::Version::parse(&version).map_err(|e| format!("Invalid version {}: {}", version, e))?;
+        if !parsed.pre.is_empty() || !parsed.build.is_empty() {
+            return Err(format!("Invalid version {}: pre-release and build metadata are not supported.", version));
+        }
+        let component = |value: u64| u32::try_from(value).map_err(|_| format!("Invalid version {}: component {} is too large.", version, value));
+        self.module_structs.version = Version {
+            major: component(parsed.major)?,
+            minor: component(parsed.minor)?,
+            build: component(parsed.patch)?,
+        };
+        Ok(())
+    }
+    pub fn set_module_dependencies(&mut self, dependencies: Vec<String>) -> Result<(), String> {
+        let mut names: Vec<String> = Vec::new();
+        let mut normalized: Vec<String> = Vec::new();
+        for dependency in dependencies.iter() {
+            let (name, requirement) = split_module_dependency(dependency)?;
+            if name == self.module_structs.name {
+                return Err(format!("Module {} cannot depend on itself.", name));
+            }
+            if names.contains(&name) {
+                return Err(format!("Module dependency {} is listed twice.", name));
+            }
+            normalized.push(match requirement {
+                Some(requirement) => format!("{}@{}", name, requirement),
+                None => name.clone(),
+            });
+            names.push(name);
+        }
+        self.module_structs.dependencies = normalized;
+        Ok(())
+    }
+    pub fn set_module_provides(&mut self, provides: Vec<String>) -> Result<(), String> {
+        for (index, provide) in provides.iter().enumerate() {
+            if !is_module_identifier(provide) {
+                return Err(format!("Invalid provided name {}: use ASCII letters, digits, '_' and '-'.", provide));
+            }
+            if provides[..index].contains(provide) {
+                return Err(format!("Provided name {} is listed twice.", provide));
+            }
+        }
+        self.module_structs.provides = provides;
+        Ok(())
+    }
     fn generate_module_section(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
         for module in self.modules.iter() {
@@ -73,41 +204,50 @@ impl LibCoder {
         }
         code_lines.join("\n")
     }
+    fn generate_string_array(static_name: &str, values: &Vec<String>) -> Vec<String> {
+        let mut code_lines: Vec<String> = Vec::new();
+        if values.is_empty() {
+            return code_lines;
+        }
+        code_lines.push(format!("static {}: FfiStringArray<{}> = FfiStringArray([", static_name, values.len()));
+        for value in values.iter() {
+            code_lines.push(format!("    b\"{}\\0\".as_ptr() as *const c_char,", byte_string_content(value)));
+        }
+        code_lines.push(format!("]);"));
+        code_lines
+    }
     fn generate_module_struct_section(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
         code_lines.push(format!("use std::ffi::c_char;"));
         code_lines.push(format!("use data_model::modules::{{Version,ModuleStructFFI}};"));
         code_lines.push(format!("use processor_engine::stream_processor::StreamProcessor;"));
         code_lines.push(format!("use processor_engine::ffi::{{TraitObjectRepr, export_stream_processor, get_error_return}};"));
+        // Raw pointers are not Sync: the string tables are wrapped so that they can live in statics.
+        if !self.module_structs.dependencies.is_empty() || !self.module_structs.provides.is_empty() {
+            code_lines.push(format!("struct FfiStringArray<const N: usize>([*const c_char; N]);"));
+            code_lines.push(format!("unsafe impl<const N: usize> Sync for FfiStringArray<N> {{}}"));
+        }
+        code_lines.extend(LibCoder::generate_string_array("MODULE_DEPENDENCIES", &self.module_structs.dependencies));
+        code_lines.extend(LibCoder::generate_string_array("MODULE_PROVIDES", &self.module_structs.provides));
         code_lines.push(format!("#[unsafe(no_mangle)]"));
         code_lines.push(format!("pub static MODULE: ModuleStructFFI  = ModuleStructFFI {{"));
-        code_lines.push(format!("    name: b\"{}\\0\".as_ptr() as *const c_char,", self.module_structs.name));
-        code_lines.push(format!("    description: b\"{}\\0\".as_ptr() as *const c_char,", self.module_structs.description));
-        code_lines.push(format!("    authors: b\"{}\\0\".as_ptr() as *const c_char,", self.module_structs.authors));
-        code_lines.push(format!("    release_date: b\"{}\\0\".as_ptr() as *const c_char,", self.module_structs.release_date));
+        code_lines.push(format!("    name: b\"{}\\0\".as_ptr() as *const c_char,", byte_string_content(&self.module_structs.name)));
+        code_lines.push(format!("    description: b\"{}\\0\".as_ptr() as *const c_char,", byte_string_content(&self.module_structs.description)));
+        code_lines.push(format!("    authors: b\"{}\\0\".as_ptr() as *const c_char,", byte_string_content(&self.module_structs.authors)));
+        code_lines.push(format!("    release_date: b\"{}\\0\".as_ptr() as *const c_char,", byte_string_content(&self.module_structs.release_date)));
         code_lines.push(format!("    version: Version{{ major: {},minor: {},build: {}}},", self.module_structs.version.major, self.module_structs.version.minor, self.module_structs.version.build));
         if self.module_structs.dependencies.is_empty() {
             code_lines.push(format!("    dependencies: std::ptr::null(),"));
-            code_lines.push(format!("    dependency_number: 0,"));
         } else {
-            code_lines.push(format!("    dependencies: ["),);
-            for dependency in self.module_structs.dependencies.iter() {
-                code_lines.push(format!("        b\"{}\\0\".as_ptr() as *const c_char,", dependency));
-            }
-            code_lines.push(format!("    ],"));
-            code_lines.push(format!("    dependency_number: {},", self.module_structs.dependencies.len()));
+            code_lines.push(format!("    dependencies: MODULE_DEPENDENCIES.0.as_ptr(),"));
         }
+        code_lines.push(format!("    dependency_number: {},", self.module_structs.dependencies.len()));
         if self.module_structs.provides.is_empty() {
             code_lines.push(format!("    provides: std::ptr::null(),"));
-            code_lines.push(format!("    provides_lengths: 0,"));
         } else {
-             code_lines.push(format!("    provides: ["));
-            for provide in self.module_structs.provides.iter() {
-                code_lines.push(format!("        b\"{}\\0\".as_ptr() as *const c_char,", provide));
-            }
-            code_lines.push(format!("    ],"));
-            code_lines.push(format!("    provides_lengths: {},", self.module_structs.provides.len()));
+            code_lines.push(format!("    provides: MODULE_PROVIDES.0.as_ptr(),"));
         }
+        code_lines.push(format!("    provides_lengths: {},", self.module_structs.provides.len()));
         code_lines.push(format!("}};"));
         code_lines.join("\n")
     }
@@ -154,23 +294,46 @@ impl LibCoder {
 impl Coder for LibCoder {
     fn generate(&mut self) -> Result<(), String> {
         let code_file = self.get_tmp_file();
-        let mut code_lines: Vec<String> = Vec::new();
-        code_lines.push(self.generate_module_section());
-        code_lines.push(self.generate_module_struct_section());
-        code_lines.push(self.generate_start_get_module_section());
-        code_lines.push(self.generate_body_get_module_section());
-        code_lines.push(self.generate_end_get_module_section());
-        let full_code = code_lines.join("\n");
+        let full_code = self.render()?;
         self.file_write(code_file.clone(), full_code)?;
         std::fs::rename(&code_file.clone(), &self.file_path).map_err(|e| format!("Error renaming temp file to {}: {}", self.file_path, e))?;
         self.save()?;
         Ok(())
     }
 
+    fn render(&mut self) -> Result<String, String> {
+        let (full_code, sections) = join_sections(vec![
+            (format!("{:?}", LibCoderParts::ModulesSection), self.generate_module_section()),
+            (format!("{:?}", LibCoderParts::ModuleStructSection), self.generate_module_struct_section()),
+            (format!("{:?}", LibCoderParts::StartGetModule), self.generate_start_get_module_section()),
+            (format!("{:?}", LibCoderParts::BodyGetModule), self.generate_body_get_module_section()),
+            (format!("{:?}", LibCoderParts::EndGetModule), self.generate_end_get_module_section()),
+        ]);
+        self.sections = sections;
+        Ok(full_code)
+    }
+
+    fn relocate(&mut self, path: String) {
+        self.file_path = format!("{}/src/lib.rs", path);
+        self.crate_path = path;
+    }
+
     fn get_path(&self) -> String {
         self.crate_path.clone()
     }
 
+    fn get_file_path(&self) -> String {
+        self.file_path.clone()
+    }
+
+    fn get_save_path(&self) -> String {
+        format!("{}/.project/lib_coder.json", self.crate_path)
+    }
+
+    fn get_sections(&self) -> &Vec<SectionSpan> {
+        &self.sections
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {self}
 
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {self}
@@ -1,9 +1,11 @@
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
-use crate::coder::Coder;
+use crate::coder::{Coder, SectionSpan, edited_regions, join_sections, protected_region, regions_quoting, rename_prefix, to_snake_case};
+use crate::processor_coder::typed_literal;
+use crate::schema;
 
 #[repr(u8)]
-#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 pub enum MainCoderParts {
     HeadMain,
     UsedDefinedCode,
@@ -51,6 +53,8 @@ pub struct Connections {
     pub from_output: String,
     pub to_processor: String,
     pub to_input: String,
+    #[serde(default)]
+    pub data_type: String,
 }
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Settings {
@@ -58,6 +62,8 @@ pub struct Settings {
     pub settable_type: String,
     pub settable_name: String,
     pub value: String,
+    #[serde(default)]
+    pub data_type: String,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -66,6 +72,37 @@ pub struct TaskProcessor {
     pub stream_processors: Vec<String>,
 }
 
+// Single place to update if the engine moves within processor_engine.
+const PROCESSOR_ENGINE_PATH: &str = "processor_engine::engine::ProcessorEngine";
+const TASK_MANAGER_PATH: &str = "processor_engine::task_monitor::TaskManager";
+
+// Variable holding the stream processor `<application>.<task>.<processor>` in main().
+fn variable_name(proc_name: &String) -> String {
+    let local_name = proc_name.splitn(2, '.').nth(1).unwrap_or(proc_name.as_str());
+    let mut variable: String = to_snake_case(local_name)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if variable.chars().next().map_or(true, |c| c.is_ascii_digit()) {
+        variable.insert(0, '_');
+    }
+    variable
+}
+
+fn local_name(object_name: &String) -> String {
+    object_name.rsplit('.').next().unwrap_or_default().to_string()
+}
+
+// `<crate>.<Block>` types are blocks of a library crate, other types are used as written.
+fn type_import(proc_type: &String) -> Option<String> {
+    let (crate_name, block_name) = proc_type.rsplit_once('.')?;
+    Some(format!("use {}::{}::{};", crate_name.replace('-', "_"), to_snake_case(block_name), block_name))
+}
+
+fn type_parameter(data_type: &String) -> String {
+    if data_type.is_empty() { "_".to_string() } else { data_type.clone() }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct MainCoder {
     task_proc: HashMap<String, TaskProcessor>,
@@ -75,6 +112,8 @@ pub struct MainCoder {
     user_codes: HashMap<MainCoderParts, String>,
     crate_path: String,
     file_path: String,
+    #[serde(default)]
+    sections: Vec<SectionSpan>,
 }
 impl MainCoder {
     pub fn new(path: String) -> Self {
@@ -86,21 +125,27 @@ impl MainCoder {
             user_codes: HashMap::new(),
             crate_path: path.clone(),
             file_path: format!("{}/src/main.rs", path.clone()),
+            sections: Vec::new(),
         }
     }
     pub fn save(&self) -> Result<(), String> {
-        let json_string = serde_json::to_string(self).map_err(|e| format!("Error serializing LibCoder: {}", e))?;
-        std::fs::write(format!("{}/.project/main_coder.json", self.crate_path), json_string).map_err(|e| format!("Error writing LibCoder file: {}", e))?;
-        Ok(())
+        schema::write_versioned(&self.get_save_path(), schema::MAIN_CODER, self)
     }
 
     pub fn load(path: String) -> Result<Self, String> {
-        let json_data = std::fs::read_to_string(path).map_err(|e| format!("Error reading LibCoder file: {}", e))?;
-        let json_data = json_data.as_str();
-        match serde_json::from_str(json_data) {
-            Ok(coder) => Ok(coder),
-            Err(e) => Err(format!("Error deserializing LibCoder: {}", e)),
-        }
+        schema::read_versioned(&path, schema::MAIN_CODER)
+    }
+    pub fn get_task_processors(&self) -> &HashMap<String, TaskProcessor> {
+        &self.task_proc
+    }
+    pub fn get_stream_processors(&self) -> &HashMap<String, String> {
+        &self.stream_proc
+    }
+    pub fn get_connections(&self) -> &Vec<Connections> {
+        &self.connections
+    }
+    pub fn get_settings(&self) -> &Vec<Settings> {
+        &self.settings
     }
     pub fn add_task_processor(&mut self, task_name: String) {
         self.task_proc.insert(task_name.clone(), TaskProcessor {
@@ -108,29 +153,134 @@ impl MainCoder {
             stream_processors: Vec::new(),
         });
     }
-    pub fn add_stream_processor(&mut self, proc_name: String, proc_type: String) {
-        let split_name: Vec<&str> = proc_name.split(".").collect();
-        let task_name = split_name[0].to_string();
-        let stream_proc_name = split_name[1].to_string();
-        let task_proc = self.task_proc.get_mut(&task_name).unwrap();
-        task_proc.stream_processors.push(stream_proc_name);
+    pub fn add_stream_processor(&mut self, proc_name: String, proc_type: String) -> Result<(), String> {
+        let (task_name, stream_proc_name) = proc_name.rsplit_once('.')
+            .ok_or_else(|| format!("Stream processor name {} must be in the format <>.<>.<>.", proc_name))?;
+        let task_proc = self.task_proc.get_mut(task_name).ok_or_else(|| format!("Task {} not found.", task_name))?;
+        task_proc.stream_processors.push(stream_proc_name.to_string());
+        self.stream_proc.insert(proc_name, proc_type);
+        Ok(())
     }
-    pub fn add_connection(&mut self, from_proc: String, from_output: String, to_proc: String, to_input: String) {
+    pub fn add_connection(&mut self, from_proc: String, from_output: String, to_proc: String, to_input: String, data_type: String) {
         self.connections.push(Connections {
             from_processor: from_proc,
             from_output,
             to_processor: to_proc,
             to_input,
+            data_type,
         });
     }
-    pub fn add_setting_value(&mut self, proc_name: String, settable_type: String, settable_name: String, value: String) {
+    pub fn remove_connection(&mut self, from_output: &String, to_input: &String) -> bool {
+        let count = self.connections.len();
+        self.connections.retain(|c| c.from_output != *from_output || c.to_input != *to_input);
+        self.connections.len() != count
+    }
+    pub fn add_setting_value(&mut self, proc_name: String, settable_type: String, settable_name: String, value: String, data_type: String) {
+        // Setting the same parameter again replaces the previous value.
+        self.settings.retain(|s| s.processor_name != proc_name || s.settable_name != settable_name);
         self.settings.push(Settings {
             processor_name: proc_name,
             settable_type,
             settable_name,
             value,
+            data_type,
         });
     }
+    pub fn remove_setting(&mut self, settable_type: &String, settable_name: &String) -> bool {
+        let count = self.settings.len();
+        self.settings.retain(|s| s.settable_type != *settable_type || s.settable_name != *settable_name);
+        self.settings.len() != count
+    }
+    pub fn rename_object(&mut self, old_name: &String, new_name: &String) {
+        let task_proc = std::mem::take(&mut self.task_proc);
+        for (task_name, mut task) in task_proc.into_iter() {
+            task.name = rename_prefix(&task.name, old_name, new_name);
+            self.task_proc.insert(rename_prefix(&task_name, old_name, new_name), task);
+        }
+        let stream_proc = std::mem::take(&mut self.stream_proc);
+        for (proc_name, proc_type) in stream_proc.into_iter() {
+            self.stream_proc.insert(rename_prefix(&proc_name, old_name, new_name), proc_type);
+        }
+        // Stream processors are listed by local name in their task, a renamed one may change task.
+        let mut moved: Vec<(String, String)> = Vec::new();
+        for (task_name, task) in self.task_proc.iter_mut() {
+            for stream_proc_name in std::mem::take(&mut task.stream_processors).into_iter() {
+                let proc_name = rename_prefix(&format!("{}.{}", task_name, stream_proc_name), old_name, new_name);
+                match proc_name.rsplit_once('.') {
+                    Some((new_task, new_proc)) if new_task != task_name => moved.push((new_task.to_string(), new_proc.to_string())),
+                    Some((_, new_proc)) => task.stream_processors.push(new_proc.to_string()),
+                    None => task.stream_processors.push(stream_proc_name),
+                }
+            }
+        }
+        for (task_name, stream_proc_name) in moved.into_iter() {
+            if let Some(task) = self.task_proc.get_mut(&task_name) {
+                task.stream_processors.push(stream_proc_name);
+            }
+        }
+        for connection in self.connections.iter_mut() {
+            connection.from_processor = rename_prefix(&connection.from_processor, old_name, new_name);
+            connection.from_output = rename_prefix(&connection.from_output, old_name, new_name);
+            connection.to_processor = rename_prefix(&connection.to_processor, old_name, new_name);
+            connection.to_input = rename_prefix(&connection.to_input, old_name, new_name);
+        }
+        for setting in self.settings.iter_mut() {
+            setting.processor_name = rename_prefix(&setting.processor_name, old_name, new_name);
+            setting.settable_name = rename_prefix(&setting.settable_name, old_name, new_name);
+        }
+    }
+    pub fn rename_block_type(&mut self, old_type: &String, new_type: &String) -> bool {
+        let mut changed = false;
+        for proc_type in self.stream_proc.values_mut().filter(|t| *t == old_type) {
+            *proc_type = new_type.clone();
+            changed = true;
+        }
+        changed
+    }
+    // Follows the rename of a port or settable in every stream processor of the block type.
+    pub fn rename_block_member(&mut self, block_type: &String, category: &str, old_member: &String, new_member: &String) -> bool {
+        let stream_proc = &self.stream_proc;
+        let of_block = |proc_name: &String| stream_proc.get(proc_name) == Some(block_type);
+        let mut changed = false;
+        match category {
+            "input" | "output" => {
+                for connection in self.connections.iter_mut() {
+                    let (proc_name, port) = if category == "input" {
+                        (&connection.to_processor, &mut connection.to_input)
+                    } else {
+                        (&connection.from_processor, &mut connection.from_output)
+                    };
+                    if of_block(proc_name) && local_name(port) == *old_member {
+                        *port = format!("{}.{}", proc_name, new_member);
+                        changed = true;
+                    }
+                }
+            },
+            "parameter" | "static" => {
+                for setting in self.settings.iter_mut() {
+                    if setting.settable_type == category && of_block(&setting.processor_name) && local_name(&setting.settable_name) == *old_member {
+                        setting.settable_name = format!("{}.{}", setting.processor_name, new_member);
+                        changed = true;
+                    }
+                }
+            },
+            _ => {},
+        }
+        changed
+    }
+    // Drops the connections or settings that use a deleted port or settable of the block type.
+    pub fn remove_block_member(&mut self, block_type: &String, category: &str, member: &String) -> bool {
+        let stream_proc = &self.stream_proc;
+        let of_block = |proc_name: &String| stream_proc.get(proc_name) == Some(block_type);
+        let (connection_count, setting_count) = (self.connections.len(), self.settings.len());
+        match category {
+            "input" => self.connections.retain(|c| !of_block(&c.to_processor) || local_name(&c.to_input) != *member),
+            "output" => self.connections.retain(|c| !of_block(&c.from_processor) || local_name(&c.from_output) != *member),
+            "parameter" | "static" => self.settings.retain(|s| s.settable_type != category || !of_block(&s.processor_name) || local_name(&s.settable_name) != *member),
+            _ => {},
+        }
+        self.connections.len() != connection_count || self.settings.len() != setting_count
+    }
     pub fn add_code_section(&mut self, part: MainCoderParts, code: String) {
         self.user_codes.insert(part, code);
     }
@@ -144,39 +294,62 @@ impl MainCoder {
                 task_proc.stream_processors.retain(|sp| sp != split_name[2]);
             }
         }
+        // Processors that are gone cannot be referenced by main() anymore.
+        let prefix = format!("{}.", object_name);
+        let removed = |proc_name: &String| proc_name == object_name || proc_name.starts_with(&prefix);
+        self.stream_proc.retain(|proc_name, _| !removed(proc_name));
+        self.connections.retain(|c| !removed(&c.from_processor) && !removed(&c.to_processor));
+        self.settings.retain(|s| !removed(&s.processor_name));
+    }
+    pub fn regions_quoting(&self, name: &String) -> Vec<String> {
+        regions_quoting(&self.user_codes, name)
+    }
+    fn user_region(&self, indent: &str, part: MainCoderParts) -> String {
+        protected_region(indent, format!("{:?}", part), self.user_codes.get(&part))
+    }
+    fn sorted_stream_processors(&self) -> Vec<(&String, &String)> {
+        let mut stream_procs: Vec<(&String, &String)> = self.stream_proc.iter().collect();
+        stream_procs.sort();
+        stream_procs
     }
     fn create_file_head_block(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
-        code_lines.push("processor_engine::log;".to_string());
-        code_lines.push("processor_engine::logger::{LogLevel, Logger};".to_string());
-        code_lines.push("processor_engine::task_monitor::TaskManager;".to_string());
-        code_lines.push("stream_proc_macro::{StreamBlockMacro};".to_string());
-        code_lines.push("data_model::streaming_data::{StreamingError, StreamingState};".to_string());
-        code_lines.push("data_model::memory_manager::{DataTrait, StaticsTrait, State, Parameter, Statics};".to_string());
-        code_lines.push("processor_engine::stream_processor::{StreamBlock, StreamBlockDyn, StreamProcessor};".to_string());
-        code_lines.push("processor_engine::connectors::{ConnectorTrait, Input, Output};".to_string());
-        code_lines.push("processor_engine::logger::LogEntry;".to_string());
+        code_lines.push(format!("use {};", PROCESSOR_ENGINE_PATH));
+        code_lines.push(format!("use {};", TASK_MANAGER_PATH));
+        code_lines.push("use processor_engine::stream_processor::{StreamBlock, StreamBlockDyn, StreamProcessor};".to_string());
+        code_lines.push("use processor_engine::connectors::ConnectorTrait;".to_string());
+        code_lines.push("use data_model::streaming_data::{StreamingError, StreamingState};".to_string());
+        let mut type_imports = self.stream_proc.values().filter_map(|proc_type| type_import(proc_type)).collect::<Vec<String>>();
+        type_imports.sort();
+        type_imports.dedup();
+        code_lines.extend(type_imports);
+        code_lines.push(self.user_region("", MainCoderParts::HeadMain));
         code_lines.join("\n")
     }
 
     fn create_stream_processor_creation_block(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
-        code_lines.push("// Stream Processor Creation Section".to_string());
-        code_lines.push("let mut processor_engine = ProcessorEngine::get().lock().unwrap();".to_string());
-        for (proc_name, proc_type) in self.stream_proc.iter() {
-            code_lines.push(format!("let mut {} = {}::new(\"{}\");", proc_name, proc_type, proc_name));
-            code_lines.push(format!("processor_engine.register_processor(\"{}\", Box::new({})).unwrap();", proc_name, proc_name));
+        code_lines.push("fn main() {".to_string());
+        code_lines.push("    // Stream processor creation".to_string());
+        for (proc_name, proc_type) in self.sorted_stream_processors() {
+            code_lines.push(format!("    let mut {} = {}::new(\"{}\");", variable_name(proc_name), local_name(proc_type), proc_name));
         }
         code_lines.join("\n")
     }
 
     fn create_stream_processor_setup_block(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
+        code_lines.push("    // Stream processor settings".to_string());
         for setting in self.settings.iter() {
+            let variable = variable_name(&setting.processor_name);
+            let data_type = type_parameter(&setting.data_type);
+            let settable_name = local_name(&setting.settable_name);
+            // Values are kept as written, the literal is only needed in the generated code.
+            let value = typed_literal(&setting.data_type, &setting.value, None).unwrap_or(setting.value.clone());
             if setting.settable_type == "parameter" {
-                code_lines.push(format!("{}.set_parameter_value::<{}>(\"{}\", \"{}\").unwrap();", setting.processor_name, setting.settable_type, setting.settable_name, setting.value));
-            } else if setting.settable_type == "statics" {
-                code_lines.push(format!("{}.set_statics_value::<{}>(\"{}\", \"{}\").unwrap();", setting.processor_name, setting.settable_type, setting.settable_name, setting.value));
+                code_lines.push(format!("    {}.set_parameter_value::<{}>(\"{}\", {}).unwrap();", variable, data_type, settable_name, value));
+            } else if setting.settable_type == "static" {
+                code_lines.push(format!("    {}.set_statics_value::<{}>(\"{}\", {}).unwrap();", variable, data_type, settable_name, value));
             }
         }
         code_lines.join("\n")
@@ -184,59 +357,112 @@ impl MainCoder {
 
     fn create_stream_processor_connection_block(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
+        code_lines.push("    // Stream processor connections".to_string());
         for connection in self.connections.iter() {
-            code_lines.push(format!("let sender = {}.get_input::<_>(\"{}\").unwrap().sender;", connection.to_processor, connection.to_input));
-            code_lines.push(format!("{}.connect::<_>(\"{}\", sender).unwrap();", connection.from_processor, connection.from_output));
+            let data_type = type_parameter(&connection.data_type);
+            code_lines.push(format!("    let sender = {}.get_input::<{}>(\"{}\").unwrap().sender.clone();",
+                variable_name(&connection.to_processor), data_type, local_name(&connection.to_input)));
+            code_lines.push(format!("    {}.connect::<{}>(\"{}\", sender).unwrap();",
+                variable_name(&connection.from_processor), data_type, local_name(&connection.from_output)));
         }
         code_lines.join("\n")
     }
 
     fn create_stream_init_block(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
-        code_lines.push("processor_engine.init().unwrap();".to_string());
+        code_lines.push("    {".to_string());
+        code_lines.push("        let mut processor_engine = ProcessorEngine::get().lock().unwrap();".to_string());
+        for (proc_name, _) in self.sorted_stream_processors() {
+            code_lines.push(format!("        processor_engine.register_processor(\"{}\", Box::new({})).unwrap();", proc_name, variable_name(proc_name)));
+        }
+        code_lines.push("        processor_engine.init().unwrap();".to_string());
+        code_lines.push("    }".to_string());
         code_lines.join("\n")
     }
     fn create_stream_run_block(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
-        for (task_name, task_data) in self.task_proc.iter() {
-            code_lines.push(format!("let mut task_manager = TaskManager::get().lock().unwrap();"));
-            code_lines.push(format!("task_manager.spawn_task(\"{}\", | {{", task_name));
+        let mut tasks: Vec<(&String, &TaskProcessor)> = self.task_proc.iter().collect();
+        tasks.sort_by(|a, b| a.0.cmp(b.0));
+        code_lines.push("    {".to_string());
+        code_lines.push("        let mut task_manager = TaskManager::get().lock().unwrap();".to_string());
+        for (task_name, task_data) in tasks.into_iter() {
+            code_lines.push(format!("        task_manager.spawn_task(\"{}\", || {{", task_name));
+            code_lines.push("            let mut processor_engine = ProcessorEngine::get().lock().unwrap();".to_string());
             for stream_proc_name in task_data.stream_processors.iter() {
-                code_lines.push(format!("    processor_engine.process(\"{}\").unwrap();", stream_proc_name));
+                code_lines.push(format!("            processor_engine.process(\"{}.{}\").unwrap();", task_name, stream_proc_name));
             }
-            code_lines.push("});".to_string());
+            code_lines.push("        });".to_string());
         }
+        code_lines.push("    }".to_string());
+        code_lines.push("    // The tasks run until Enter is pressed.".to_string());
+        code_lines.push("    let mut line = String::new();".to_string());
+        code_lines.push("    std::io::stdin().read_line(&mut line).unwrap();".to_string());
         code_lines.join("\n")
     }
     fn create_stream_stop_block(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
-        code_lines.push("processor_engine.stop().unwrap();".to_string());
+        code_lines.push("    ProcessorEngine::get().lock().unwrap().stop().unwrap();".to_string());
+        code_lines.push("}".to_string());
         code_lines.join("\n")
     }
 }
 impl Coder for MainCoder {
     fn generate(&mut self) -> Result<(), String> {
         let code_file = self.get_tmp_file();
-        let mut code_lines: Vec<String> = Vec::new();
-        code_lines.push("// Auto-generated main.rs file".to_string());
-        code_lines.push(self.create_file_head_block());
-        code_lines.push("// User-defined code section".to_string());
-        code_lines.push(self.create_stream_processor_creation_block());
-        code_lines.push(self.create_stream_processor_setup_block());
-        code_lines.push(self.create_stream_processor_connection_block());
-        code_lines.push(self.create_stream_init_block());
-        code_lines.push(self.create_stream_run_block());
-        code_lines.push(self.create_stream_stop_block());
-        code_lines.join("\n");
-        let full_code = code_lines.join("\n");
+        let full_code = self.render()?;
         self.file_write(code_file.clone(), full_code)?;
         std::fs::rename(&code_file.clone(), &self.file_path).map_err(|e| format!("Error renaming temp file to {}: {}", self.file_path, e))?;
         self.save()?;
         Ok(())
     }
+    fn render(&mut self) -> Result<String, String> {
+        let (full_code, sections) = join_sections(vec![
+            ("Header".to_string(), "// Auto-generated main.rs file\n#![allow(unused_imports, unused_mut)]".to_string()),
+            (format!("{:?}", MainCoderParts::HeadMain), self.create_file_head_block()),
+            (format!("{:?}", MainCoderParts::UsedDefinedCode), self.user_region("", MainCoderParts::UsedDefinedCode)),
+            (format!("{:?}", MainCoderParts::StreamProcessorCreation), self.create_stream_processor_creation_block()),
+            (format!("{:?}", MainCoderParts::StreamProcessorSetup), self.create_stream_processor_setup_block()),
+            (format!("{:?}", MainCoderParts::StreamProcessorConnection), self.create_stream_processor_connection_block()),
+            (format!("{:?}", MainCoderParts::StreamProcessorUserCode), self.user_region("    ", MainCoderParts::StreamProcessorUserCode)),
+            (format!("{:?}", MainCoderParts::StreamInit), self.create_stream_init_block()),
+            (format!("{:?}", MainCoderParts::StreamRun), self.create_stream_run_block()),
+            (format!("{:?}", MainCoderParts::StreamStop), self.create_stream_stop_block()),
+        ]);
+        self.sections = sections;
+        Ok(full_code)
+    }
+    fn sync_from_file(&mut self) -> Result<Vec<String>, String> {
+        let parts = (0..=u8::MAX).filter_map(|i| MainCoderParts::try_from(i).ok()).collect();
+        let edited = edited_regions(&self.file_path, parts, &self.user_codes)?;
+        let mut updated: Vec<String> = Vec::new();
+        for (part, code) in edited.into_iter() {
+            updated.push(format!("{:?}", part));
+            self.user_codes.insert(part, code);
+        }
+        if !updated.is_empty() {
+            self.save()?;
+        }
+        Ok(updated)
+    }
+    fn relocate(&mut self, path: String) {
+        self.file_path = format!("{}/src/main.rs", path);
+        self.crate_path = path;
+    }
     fn get_path(&self) -> String {
         self.crate_path.clone()
     }
+
+    fn get_file_path(&self) -> String {
+        self.file_path.clone()
+    }
+
+    fn get_save_path(&self) -> String {
+        format!("{}/.project/main_coder.json", self.crate_path)
+    }
+
+    fn get_sections(&self) -> &Vec<SectionSpan> {
+        &self.sections
+    }
     
     fn as_any(&self) -> &dyn std::any::Any {self}
 
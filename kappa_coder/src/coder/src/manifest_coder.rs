@@ -0,0 +1,450 @@
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, Value, value};
+use crate::coder::{Coder, SectionSpan};
+use crate::schema;
+
+pub const CRATE_TYPES: [&str; 7] = ["bin", "lib", "rlib", "dylib", "cdylib", "staticlib", "proc-macro"];
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DependencySpec {
+    pub version: Option<String>,
+    pub path: Option<String>,
+    pub features: Vec<String>,
+    pub optional: bool,
+}
+
+impl DependencySpec {
+    fn from_item(item: &Item) -> Option<Self> {
+        if let Some(version) = item.as_str() {
+            return Some(DependencySpec {
+                version: Some(version.to_string()),
+                ..Default::default()
+            });
+        }
+        let table = item.as_table_like()?;
+        Some(DependencySpec {
+            version: table.get("version").and_then(|v| v.as_str()).map(|v| v.to_string()),
+            path: table.get("path").and_then(|v| v.as_str()).map(|v| v.to_string()),
+            features: table.get("features")
+                .and_then(|v| v.as_array())
+                .map(|a| a.iter().filter_map(|f| f.as_str()).map(|f| f.to_string()).collect())
+                .unwrap_or_default(),
+            optional: table.get("optional").and_then(|v| v.as_bool()).unwrap_or(false),
+        })
+    }
+
+    fn to_item(&self) -> Item {
+        if self.path.is_none() && self.features.is_empty() && !self.optional {
+            if let Some(version) = &self.version {
+                return value(version.clone());
+            }
+        }
+        let mut table = InlineTable::new();
+        if let Some(version) = &self.version {
+            table.insert("version", version.as_str().into());
+        }
+        if let Some(path) = &self.path {
+            table.insert("path", path.as_str().into());
+        }
+        if !self.features.is_empty() {
+            table.insert("features", Value::Array(string_array(&self.features)));
+        }
+        if self.optional {
+            table.insert("optional", true.into());
+        }
+        value(table)
+    }
+}
+
+fn string_array(values: &Vec<String>) -> Array {
+    let mut array = Array::new();
+    for v in values.iter() {
+        array.push(v.as_str());
+    }
+    array
+}
+
+fn retain_keys(table: &mut dyn toml_edit::TableLike, keep: impl Fn(&String) -> bool) {
+    let stale = table.iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !keep(key))
+        .collect::<Vec<String>>();
+    for key in stale.iter() {
+        table.remove(key);
+    }
+}
+
+// Profile settings are kept as TOML source text, so `3`, `true` and `"z"` keep their types.
+fn parse_setting(setting: &String) -> Value {
+    match setting.parse::<Value>() {
+        Ok(parsed) => parsed,
+        Err(_) => Value::from(setting.as_str()),
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ManifestCoder {
+    crate_types: Vec<String>,
+    profiles: BTreeMap<String, BTreeMap<String, String>>,
+    features: BTreeMap<String, Vec<String>>,
+    dependencies: BTreeMap<String, DependencySpec>,
+    crate_path: String,
+    file_path: String,
+    #[serde(default)]
+    sections: Vec<SectionSpan>,
+}
+
+impl ManifestCoder {
+    // Builds the model from the Cargo.toml currently on disk, so entries added by `cargo add` stay managed.
+    pub fn from_manifest(path: String) -> Result<Self, String> {
+        let file_path = format!("{}/Cargo.toml", path);
+        let doc = ManifestCoder::read_document(&file_path)?;
+        let crate_types = doc.get("lib")
+            .and_then(|lib| lib.get("crate-type"))
+            .and_then(|types| types.as_array())
+            .map(|a| a.iter().filter_map(|t| t.as_str()).map(|t| t.to_string()).collect())
+            .unwrap_or_default();
+        let mut profiles: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        if let Some(profile_table) = doc.get("profile").and_then(|p| p.as_table_like()) {
+            for (profile_name, profile) in profile_table.iter() {
+                let mut settings: BTreeMap<String, String> = BTreeMap::new();
+                if let Some(profile) = profile.as_table_like() {
+                    for (key, setting) in profile.iter() {
+                        if let Some(setting) = setting.as_value() {
+                            settings.insert(key.to_string(), setting.to_string().trim().to_string());
+                        }
+                    }
+                }
+                profiles.insert(profile_name.to_string(), settings);
+            }
+        }
+        let mut features: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        if let Some(feature_table) = doc.get("features").and_then(|f| f.as_table_like()) {
+            for (feature_name, enables) in feature_table.iter() {
+                let enables = enables.as_array()
+                    .map(|a| a.iter().filter_map(|e| e.as_str()).map(|e| e.to_string()).collect())
+                    .unwrap_or_default();
+                features.insert(feature_name.to_string(), enables);
+            }
+        }
+        let mut dependencies: BTreeMap<String, DependencySpec> = BTreeMap::new();
+        if let Some(dependency_table) = doc.get("dependencies").and_then(|d| d.as_table_like()) {
+            for (dependency_name, dependency) in dependency_table.iter() {
+                if let Some(spec) = DependencySpec::from_item(dependency) {
+                    dependencies.insert(dependency_name.to_string(), spec);
+                }
+            }
+        }
+        Ok(ManifestCoder {
+            crate_types,
+            profiles,
+            features,
+            dependencies,
+            crate_path: path,
+            file_path,
+            sections: Vec::new(),
+        })
+    }
+
+    fn read_document(file_path: &String) -> Result<DocumentMut, String> {
+        let manifest = std::fs::read_to_string(file_path).map_err(|e| format!("Error reading manifest {}: {}", file_path, e))?;
+        manifest.parse::<DocumentMut>().map_err(|e| format!("Error parsing manifest {}: {}", file_path, e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        schema::write_versioned(&self.get_save_path(), schema::MANIFEST, self)
+    }
+
+    pub fn load(path: String) -> Result<Self, String> {
+        schema::read_versioned(&path, schema::MANIFEST)
+    }
+
+    pub fn set_crate_types(&mut self, crate_types: Vec<String>) -> Result<(), String> {
+        for crate_type in crate_types.iter() {
+            if !CRATE_TYPES.contains(&crate_type.as_str()) {
+                return Err(format!("Unknown crate type {}, expected one of {}.", crate_type, CRATE_TYPES.join(", ")));
+            }
+        }
+        self.crate_types = crate_types;
+        Ok(())
+    }
+    pub fn set_profile_setting(&mut self, profile: String, key: String, setting: String) {
+        self.profiles.entry(profile).or_default().insert(key, setting);
+    }
+    pub fn remove_profile_setting(&mut self, profile: &String, key: Option<&String>) -> Result<(), String> {
+        match key {
+            Some(key) => {
+                let settings = self.profiles.get_mut(profile).ok_or_else(|| format!("Profile {} is not set.", profile))?;
+                settings.remove(key).ok_or_else(|| format!("Profile {} has no setting {}.", profile, key))?;
+                if settings.is_empty() {
+                    self.profiles.remove(profile);
+                }
+            },
+            None => {
+                self.profiles.remove(profile).ok_or_else(|| format!("Profile {} is not set.", profile))?;
+            },
+        }
+        Ok(())
+    }
+    pub fn set_feature(&mut self, feature: String, enables: Vec<String>) {
+        self.features.insert(feature, enables);
+    }
+    pub fn remove_feature(&mut self, feature: &String) -> Result<(), String> {
+        self.features.remove(feature).ok_or_else(|| format!("Feature {} is not defined.", feature))?;
+        Ok(())
+    }
+    pub fn set_dependency(&mut self, name: String, spec: DependencySpec) -> Result<(), String> {
+        if spec.version.is_none() && spec.path.is_none() {
+            return Err(format!("Dependency {} needs a version or a path.", name));
+        }
+        self.dependencies.insert(name, spec);
+        Ok(())
+    }
+    pub fn remove_dependency(&mut self, name: &String) -> Result<(), String> {
+        self.dependencies.remove(name).ok_or_else(|| format!("Dependency {} is not declared.", name))?;
+        Ok(())
+    }
+    pub fn get_dependencies(&self) -> &BTreeMap<String, DependencySpec> {
+        &self.dependencies
+    }
+
+    fn apply(&self, doc: &mut DocumentMut) {
+        if self.crate_types.is_empty() {
+            if let Some(lib) = doc.get_mut("lib").and_then(|l| l.as_table_like_mut()) {
+                lib.remove("crate-type");
+            }
+        } else {
+            if !doc.contains_key("lib") {
+                doc["lib"] = toml_edit::table();
+            }
+            doc["lib"]["crate-type"] = value(string_array(&self.crate_types));
+        }
+
+        // Existing tables are edited in place so their position, key order and comments survive.
+        if self.profiles.is_empty() {
+            doc.remove("profile");
+        } else {
+            if !doc.contains_key("profile") {
+                let mut profile_table = Table::new();
+                profile_table.set_implicit(true);
+                doc.insert("profile", Item::Table(profile_table));
+            }
+            if let Some(profile_table) = doc["profile"].as_table_like_mut() {
+                retain_keys(profile_table, |name| self.profiles.contains_key(name));
+                for (profile_name, settings) in self.profiles.iter() {
+                    if !profile_table.contains_key(profile_name) {
+                        profile_table.insert(profile_name, toml_edit::table());
+                    }
+                    if let Some(profile) = profile_table.get_mut(profile_name).and_then(|p| p.as_table_like_mut()) {
+                        retain_keys(profile, |key| settings.contains_key(key));
+                        for (key, setting) in settings.iter() {
+                            let current = profile.get(key).and_then(|s| s.as_value()).map(|s| s.to_string().trim().to_string());
+                            if current.as_ref() != Some(setting) {
+                                profile.insert(key, value(parse_setting(setting)));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        if self.features.is_empty() {
+            doc.remove("features");
+        } else {
+            if !doc.contains_key("features") {
+                doc["features"] = toml_edit::table();
+            }
+            if let Some(feature_table) = doc["features"].as_table_like_mut() {
+                retain_keys(feature_table, |name| self.features.contains_key(name));
+                for (feature_name, enables) in self.features.iter() {
+                    let current = feature_table.get(feature_name)
+                        .and_then(|e| e.as_array())
+                        .map(|a| a.iter().filter_map(|e| e.as_str()).map(|e| e.to_string()).collect::<Vec<String>>());
+                    if current.as_ref() != Some(enables) {
+                        feature_table.insert(feature_name, value(string_array(enables)));
+                    }
+                }
+            }
+        }
+
+        if !doc.contains_key("dependencies") {
+            doc["dependencies"] = toml_edit::table();
+        }
+        if let Some(dependency_table) = doc["dependencies"].as_table_like_mut() {
+            retain_keys(dependency_table, |name| self.dependencies.contains_key(name));
+            // Entries that already match are left untouched to keep their formatting and extra keys.
+            for (name, spec) in self.dependencies.iter() {
+                let current = dependency_table.get(name).and_then(|item| DependencySpec::from_item(item));
+                if current.as_ref() != Some(spec) {
+                    dependency_table.insert(name, spec.to_item());
+                }
+            }
+        }
+    }
+}
+
+impl Coder for ManifestCoder {
+    fn generate(&mut self) -> Result<(), String> {
+        let manifest = self.render()?;
+        let code_file = self.get_tmp_file();
+        self.file_write(code_file.clone(), manifest)?;
+        std::fs::rename(&code_file.clone(), &self.file_path).map_err(|e| format!("Error renaming temp file to {}: {}", self.file_path, e))?;
+        self.save()?;
+        Ok(())
+    }
+
+    fn render(&mut self) -> Result<String, String> {
+        let mut doc = ManifestCoder::read_document(&self.file_path)?;
+        self.apply(&mut doc);
+        Ok(doc.to_string())
+    }
+
+    fn relocate(&mut self, path: String) {
+        self.file_path = format!("{}/Cargo.toml", path);
+        self.crate_path = path;
+    }
+
+    fn get_path(&self) -> String {
+        self.crate_path.clone()
+    }
+
+    fn get_file_path(&self) -> String {
+        self.file_path.clone()
+    }
+
+    fn get_save_path(&self) -> String {
+        format!("{}/.project/manifest.json", self.crate_path)
+    }
+
+    fn get_sections(&self) -> &Vec<SectionSpan> {
+        &self.sections
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {self}
+
+    fn as_any_mut(&mut self) -> &mut dyn std::any::Any {self}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"[package]
+name = "dsp"
+version = "0.1.0"
+edition = "2021"
+
+# Loaded by the coder as a dynamic library.
+[lib]
+name = "dsp"
+crate-type = ["cdylib", "rlib"]
+
+[dependencies]
+num-traits = "0.2"
+serde = { version = "1.0", features = ["derive"] }
+kappa_lib = { path = "../kappa_lib", default-features = false }
+
+[features]
+simd = ["num-traits/std"]
+
+[profile.release]
+opt-level = 3
+lto = true
+"#;
+
+    fn manifest_crate(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("kappa_manifest_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(path.join(".project")).unwrap();
+        std::fs::write(path.join("Cargo.toml"), MANIFEST).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn unedited_manifest_renders_unchanged() {
+        let path = manifest_crate("unedited");
+        let mut manifest = ManifestCoder::from_manifest(path.clone()).unwrap();
+        assert_eq!(manifest.render().unwrap(), MANIFEST);
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn edits_change_only_their_entries() {
+        let path = manifest_crate("edits");
+        let mut manifest = ManifestCoder::from_manifest(path.clone()).unwrap();
+        manifest.set_crate_types(vec!["cdylib".to_string()]).unwrap();
+        manifest.set_profile_setting("release".to_string(), "opt-level".to_string(), "\"z\"".to_string());
+        manifest.set_profile_setting("dev".to_string(), "debug".to_string(), "false".to_string());
+        manifest.set_feature("trace".to_string(), vec![]);
+        manifest.remove_feature(&"simd".to_string()).unwrap();
+        manifest.set_dependency("serde".to_string(), DependencySpec {
+            version: Some("1.0".to_string()),
+            features: vec!["derive".to_string(), "rc".to_string()],
+            ..Default::default()
+        }).unwrap();
+        manifest.remove_dependency(&"num-traits".to_string()).unwrap();
+        manifest.generate().unwrap();
+        let rendered = std::fs::read_to_string(format!("{}/Cargo.toml", path)).unwrap();
+        assert_eq!(rendered, r#"[package]
+name = "dsp"
+version = "0.1.0"
+edition = "2021"
+
+# Loaded by the coder as a dynamic library.
+[lib]
+name = "dsp"
+crate-type = ["cdylib"]
+
+[dependencies]
+serde = { version = "1.0", features = ["derive", "rc"] }
+kappa_lib = { path = "../kappa_lib", default-features = false }
+
+[features]
+trace = []
+
+[profile.release]
+opt-level = "z"
+lto = true
+
+[profile.dev]
+debug = false
+"#);
+        // Both the edited file and the saved model read back into the same model.
+        for reread in [ManifestCoder::from_manifest(path.clone()).unwrap(), ManifestCoder::load(manifest.get_save_path()).unwrap()] {
+            assert_eq!(reread.crate_types, manifest.crate_types);
+            assert_eq!(reread.profiles, manifest.profiles);
+            assert_eq!(reread.features, manifest.features);
+            assert!(reread.dependencies == manifest.dependencies);
+        }
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn emptied_sections_are_removed() {
+        let path = manifest_crate("emptied");
+        let mut manifest = ManifestCoder::from_manifest(path.clone()).unwrap();
+        manifest.set_crate_types(vec![]).unwrap();
+        manifest.remove_profile_setting(&"release".to_string(), Some(&"lto".to_string())).unwrap();
+        manifest.remove_profile_setting(&"release".to_string(), Some(&"opt-level".to_string())).unwrap();
+        manifest.remove_feature(&"simd".to_string()).unwrap();
+        let rendered = manifest.render().unwrap();
+        assert!(!rendered.contains("crate-type"));
+        assert!(rendered.contains("[lib]\nname = \"dsp\""));
+        assert!(!rendered.contains("[profile"));
+        assert!(!rendered.contains("[features]"));
+        assert!(rendered.contains("kappa_lib = { path = \"../kappa_lib\", default-features = false }"));
+        let _ = std::fs::remove_dir_all(&path);
+    }
+
+    #[test]
+    fn invalid_edits_are_refused() {
+        let path = manifest_crate("invalid");
+        let mut manifest = ManifestCoder::from_manifest(path.clone()).unwrap();
+        assert!(manifest.set_crate_types(vec!["shared".to_string()]).unwrap_err().starts_with("Unknown crate type shared"));
+        assert_eq!(manifest.set_dependency("libm".to_string(), DependencySpec::default()).unwrap_err(), "Dependency libm needs a version or a path.");
+        assert_eq!(manifest.remove_profile_setting(&"bench".to_string(), None).unwrap_err(), "Profile bench is not set.");
+        assert_eq!(manifest.remove_dependency(&"libm".to_string()).unwrap_err(), "Dependency libm is not declared.");
+        assert_eq!(manifest.render().unwrap(), MANIFEST);
+        let _ = std::fs::remove_dir_all(&path);
+    }
+}
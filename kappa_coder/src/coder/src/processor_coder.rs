@@ -1,9 +1,10 @@
 use std::collections::HashMap;
 use std::path::Path;
 use serde::{Serialize, Deserialize};
-use crate::coder::{Coder, to_snake_case};
+use crate::coder::{Coder, SectionSpan, join_sections, edited_regions, protected_region, regions_quoting, to_snake_case};
+use crate::schema;
 
-#[derive(Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[repr(u8)]
 pub enum ModCoderParts {
     HeadMod,
@@ -19,6 +20,7 @@ pub enum ModCoderParts {
     RunBody,
     ProcessBody,
     StopBody,
+    UserTestCases,
 }
 
 impl TryFrom<u8> for ModCoderParts {
@@ -37,6 +39,7 @@ impl TryFrom<u8> for ModCoderParts {
             10 => Ok(ModCoderParts::RunBody),
             11 => Ok(ModCoderParts::ProcessBody),
             12 => Ok(ModCoderParts::StopBody),
+            13 => Ok(ModCoderParts::UserTestCases),
             _ => Err(()),
         }
     }
@@ -54,8 +57,8 @@ impl TryFrom<String> for ModCoderParts {
 }
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Limits {
-    min: String,
-    max: String,
+    pub min: String,
+    pub max: String,
 }
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Typed {
@@ -66,6 +69,80 @@ pub struct Typed {
     pub limits: Option<Limits>,
 }
 
+fn integer_literal(data_type: &str, value: &String) -> Option<Option<String>> {
+    let literal = match data_type {
+        "i8" => value.parse::<i8>().ok().map(|n| n.to_string()),
+        "i16" => value.parse::<i16>().ok().map(|n| n.to_string()),
+        "i32" => value.parse::<i32>().ok().map(|n| n.to_string()),
+        "i64" => value.parse::<i64>().ok().map(|n| n.to_string()),
+        "i128" => value.parse::<i128>().ok().map(|n| n.to_string()),
+        "isize" => value.parse::<isize>().ok().map(|n| n.to_string()),
+        "u8" => value.parse::<u8>().ok().map(|n| n.to_string()),
+        "u16" => value.parse::<u16>().ok().map(|n| n.to_string()),
+        "u32" => value.parse::<u32>().ok().map(|n| n.to_string()),
+        "u64" => value.parse::<u64>().ok().map(|n| n.to_string()),
+        "u128" => value.parse::<u128>().ok().map(|n| n.to_string()),
+        "usize" => value.parse::<usize>().ok().map(|n| n.to_string()),
+        _ => return None,
+    };
+    Some(literal)
+}
+
+// Checks a value against a declared data type and its limits, and returns it as a Rust literal of that type.
+// Values of types other than the primitive ones are used as written.
+pub fn typed_literal(data_type: &String, value: &String, limits: Option<&Limits>) -> Result<String, String> {
+    let literal = match data_type.as_str() {
+        "bool" => value.parse::<bool>().map(|b| b.to_string()).map_err(|_| format!("{} is not a bool.", value))?,
+        "char" => {
+            let mut chars = value.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => format!("{:?}", c),
+                _ => return Err(format!("{} is not a char.", value)),
+            }
+        },
+        "String" => format!("{:?}.to_string()", value),
+        "&str" | "&'static str" => format!("{:?}", value),
+        "f32" | "f64" => {
+            let number = value.parse::<f64>().ok()
+                .filter(|n| n.is_finite() && (data_type == "f64" || n.abs() <= f32::MAX as f64))
+                .ok_or_else(|| format!("{} is not a valid {}.", value, data_type))?;
+            format!("{:?}", number)
+        },
+        _ => match integer_literal(data_type, value) {
+            Some(literal) => literal.ok_or_else(|| format!("{} is not a valid {}.", value, data_type))?,
+            None => return Ok(value.clone()),
+        },
+    };
+    if let (Some(limits), Ok(number)) = (limits, value.parse::<f64>()) {
+        if let Ok(min) = limits.min.parse::<f64>() {
+            if number < min {
+                return Err(format!("{} is below the minimum {}.", value, limits.min));
+            }
+        }
+        if let Ok(max) = limits.max.parse::<f64>() {
+            if number > max {
+                return Err(format!("{} is above the maximum {}.", value, limits.max));
+            }
+        }
+    }
+    Ok(literal)
+}
+
+// Settable values are checked when they are set, a value that is not a literal of its type is used as written.
+fn settable_literal(data_type: &String, value: &String) -> String {
+    typed_literal(data_type, value, None).unwrap_or(value.clone())
+}
+
+// Literal of a value just outside a numeric limit, if the data type can hold it.
+fn outside_limit(data_type: &String, limit: &String, below: bool) -> Option<String> {
+    let step: i128 = if below { -1 } else { 1 };
+    let value = match limit.parse::<i128>() {
+        Ok(number) => number.checked_add(step)?.to_string(),
+        Err(_) => (limit.parse::<f64>().ok()? + step as f64).to_string(),
+    };
+    typed_literal(data_type, &value, None).ok()
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ProcessorCoder {
     processor_name: String,
@@ -78,10 +155,12 @@ pub struct ProcessorCoder {
     crate_path: String,
     file_path: String,
     tmp_path: String,
+    #[serde(default)]
+    sections: Vec<SectionSpan>,
 }
 
 impl ProcessorCoder {
-    pub fn new(path: String, processor_name: String) -> Self {
+    pub fn default_user_codes() -> HashMap<ModCoderParts, String> {
         let mut user_codes: HashMap<ModCoderParts, String> = HashMap::new();
         user_codes.insert(ModCoderParts::InitBody,    "        
         if self.check_state(StreamingState::Running) {
@@ -111,6 +190,11 @@ impl ProcessorCoder {
         user_codes.insert(ModCoderParts::StopBody,    "
         self.set_state(StreamingState::Stopped);
         Ok(())".to_string());
+        user_codes
+    }
+
+    pub fn new(path: String, processor_name: String) -> Self {
+        let user_codes = ProcessorCoder::default_user_codes();
         let processor_file_name = to_snake_case(&processor_name);
         ProcessorCoder {
             processor_name,
@@ -119,27 +203,39 @@ impl ProcessorCoder {
             states: HashMap::new(),
             statics: HashMap::new(),
             parameters: HashMap::new(),
-            user_codes: HashMap::new(),
+            user_codes,
             crate_path: path.clone(),
             file_path: format!("{}/src/{}.rs", path.clone(), processor_file_name.clone()),
             tmp_path: "".to_string(),
+            sections: Vec::new(),
         }
     }
     pub fn save(&self) -> Result<(), String> {
-        let json_string = serde_json::to_string(self).map_err(|e| format!("Error serializing LibCoder: {}", e))?;
-        std::fs::write(format!("{}/.project/{}.json", self.crate_path, self.processor_name), json_string).map_err(|e| format!("Error writing LibCoder file: {}", e))?;
-        Ok(())
+        schema::write_versioned(&self.get_save_path(), schema::PROCESSOR_CODER, self)
     }
 
     pub fn load(path: String) -> Result<Self, String> {
-        let json_data = std::fs::read_to_string(path).map_err(|e| format!("Error reading LibCoder file: {}", e))?;
-        let json_data = json_data.as_str();
-        match serde_json::from_str(json_data) {
-            Ok(coder) => Ok(coder),
-            Err(e) => Err(format!("Error deserializing LibCoder: {}", e)),
-        }
+        schema::read_versioned(&path, schema::PROCESSOR_CODER)
     }
     
+    pub fn get_processor_name(&self) -> &String {
+        &self.processor_name
+    }
+    pub fn get_inputs(&self) -> &HashMap<String, String> {
+        &self.inputs
+    }
+    pub fn get_outputs(&self) -> &HashMap<String, String> {
+        &self.outputs
+    }
+    pub fn get_states(&self) -> &HashMap<String, Typed> {
+        &self.states
+    }
+    pub fn get_statics(&self) -> &HashMap<String, Typed> {
+        &self.statics
+    }
+    pub fn get_parameters(&self) -> &HashMap<String, Typed> {
+        &self.parameters
+    }
     pub fn add_code_section(&mut self, part: ModCoderParts, code: String) {
         self.user_codes.insert(part, code);
     }
@@ -189,6 +285,33 @@ impl ProcessorCoder {
         self.parameters.retain(|k, _| k != object_name);
 
     }
+    // The struct name also names the generated file and the saved model.
+    pub fn set_processor_name(&mut self, processor_name: String) {
+        self.file_path = format!("{}/src/{}.rs", self.crate_path, to_snake_case(&processor_name));
+        self.processor_name = processor_name;
+    }
+    pub fn rename_member(&mut self, old_name: &String, new_name: &String) -> Result<(), String> {
+        for ports in [&mut self.inputs, &mut self.outputs] {
+            if let Some(data_type) = ports.remove(old_name) {
+                ports.insert(new_name.clone(), data_type);
+                return Ok(());
+            }
+        }
+        for settables in [&mut self.states, &mut self.statics, &mut self.parameters] {
+            if let Some(mut settable) = settables.remove(old_name) {
+                settable.name = new_name.clone();
+                settables.insert(new_name.clone(), settable);
+                return Ok(());
+            }
+        }
+        Err(format!("Block {} has no member {}.", self.processor_name, old_name))
+    }
+    pub fn regions_quoting(&self, name: &String) -> Vec<String> {
+        regions_quoting(&self.user_codes, name)
+    }
+    fn user_region(&self, indent: &str, part: ModCoderParts) -> String {
+        protected_region(indent, format!("{:?}", part), self.user_codes.get(&part))
+    }
     fn generate_head_mod(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
         code_lines.push(format!("use std::collections::HashMap;"));
@@ -205,10 +328,7 @@ impl ProcessorCoder {
         code_lines.join("\n")
     }
     fn generate_user_defined_code(&self) -> String {
-        if let Some(code) = self.user_codes.get(&ModCoderParts::UsedDefinedCode) {
-            return code.clone();
-        }
-        "".to_string()
+        self.user_region("", ModCoderParts::UsedDefinedCode)
     }
     fn generate_head_struct(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
@@ -226,9 +346,7 @@ impl ProcessorCoder {
     }
     fn generate_user_defined_struct(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
-        if let Some(code) = self.user_codes.get(&ModCoderParts::UserDefinedStruct) {
-            code_lines.push(code.clone());
-        }
+        code_lines.push(self.user_region("    ", ModCoderParts::UserDefinedStruct));
         code_lines.push(format!("}}"));
         code_lines.join("\n")
     }
@@ -249,9 +367,7 @@ impl ProcessorCoder {
     }
     fn generate_user_defined_builder(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
-        if let Some(code) = self.user_codes.get(&ModCoderParts::UserDefinedBuilder) {
-            code_lines.push(code.clone());
-        }
+        code_lines.push(self.user_region("            ", ModCoderParts::UserDefinedBuilder));
         code_lines.push(format!("        }};"));
         code_lines.join("\n")
     }
@@ -267,35 +383,35 @@ impl ProcessorCoder {
             code_lines.push(format!("        ret.new_state::<{}>(\"{}\", {});", state_type.data_type, state_name, state_type.default));
         }
         for (static_name, static_typed) in self.statics.iter() {
+            let default = settable_literal(&static_typed.data_type, &static_typed.default);
             if let Some(limits) = &static_typed.limits {
-                code_lines.push(format!("        ret.new_statics::<{}>(\"{}\", {}, Some(({}, {})));", static_typed.data_type, static_name, static_typed.default, limits.min, limits.max));
+                code_lines.push(format!("        ret.new_statics::<{}>(\"{}\", {}, Some(({}, {})));", static_typed.data_type, static_name, default,
+                    settable_literal(&static_typed.data_type, &limits.min), settable_literal(&static_typed.data_type, &limits.max)));
             } else {
-                code_lines.push(format!("        ret.new_statics::<{}>(\"{}\", {}, None);", static_typed.data_type, static_name, static_typed.default));
+                code_lines.push(format!("        ret.new_statics::<{}>(\"{}\", {}, None);", static_typed.data_type, static_name, default));
             }
         }
         for (param_name, param_typed) in self.parameters.iter() {
+            let default = settable_literal(&param_typed.data_type, &param_typed.default);
             if let Some(limits) = &param_typed.limits {
-                code_lines.push(format!("        ret.new_parameter::<{}>(\"{}\", {}, Some(({}, {})));", param_typed.data_type, param_name, param_typed.default, limits.min, limits.max));
+                code_lines.push(format!("        ret.new_parameter::<{}>(\"{}\", {}, Some(({}, {})));", param_typed.data_type, param_name, default,
+                    settable_literal(&param_typed.data_type, &limits.min), settable_literal(&param_typed.data_type, &limits.max)));
             } else {
-                code_lines.push(format!("        ret.new_parameter::<{}>(\"{}\", {}, None);", param_typed.data_type, param_name, param_typed.default));
+                code_lines.push(format!("        ret.new_parameter::<{}>(\"{}\", {}, None);", param_typed.data_type, param_name, default));
             }
         }
         code_lines.join("\n")
     }
     fn generate_user_member_creation(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
-        if let Some(code) = self.user_codes.get(&ModCoderParts::UserMemberCreation) {
-            code_lines.push(code.clone());
-        }
+        code_lines.push(self.user_region("        ", ModCoderParts::UserMemberCreation));
         code_lines.push(format!("        ret"));
         code_lines.push(format!("    }}"));
         code_lines.join("\n")
     }
     fn generate_user_defined_impl_struct(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
-        if let Some(code) = self.user_codes.get(&ModCoderParts::UserDefinedImplStruct) {
-            code_lines.push(code.clone());
-        }
+        code_lines.push(self.user_region("    ", ModCoderParts::UserDefinedImplStruct));
         code_lines.push(format!("}}"));
         code_lines.join("\n")
     }
@@ -303,37 +419,148 @@ impl ProcessorCoder {
         let mut code_lines: Vec<String> = Vec::new();
         code_lines.push(format!("impl StreamProcessor for {} {{", self.processor_name));
         code_lines.push(format!("    fn init(&mut self) -> Result<(), StreamingError> {{"));
-        if let Some(code) = self.user_codes.get(&ModCoderParts::InitBody) {
-            code_lines.push(code.clone());
-        }
+        code_lines.push(self.user_region("        ", ModCoderParts::InitBody));
         code_lines.push(format!("    }}"));
         code_lines.join("\n")
     }
     fn generate_run_body(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
         code_lines.push(format!("    fn run(&mut self) -> Result<(), StreamingError> {{"));
-        if let Some(code) = self.user_codes.get(&ModCoderParts::RunBody) {
-            code_lines.push(code.clone());
-        }
+        code_lines.push(self.user_region("        ", ModCoderParts::RunBody));
         code_lines.push(format!("    }}"));
         code_lines.join("\n")
     }
     fn generate_process_body(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
         code_lines.push(format!("    fn process(&mut self) -> Result<(), StreamingError> {{"));
-        if let Some(code) = self.user_codes.get(&ModCoderParts::ProcessBody) {
-            code_lines.push(code.clone());
-        }
+        code_lines.push(self.user_region("        ", ModCoderParts::ProcessBody));
         code_lines.push(format!("    }}"));
         code_lines.join("\n")
     }
     fn generate_stop_body(&self) -> String {
         let mut code_lines: Vec<String> = Vec::new();
         code_lines.push(format!("    fn stop(&mut self) -> Result<(), StreamingError> {{"));
-        if let Some(code) = self.user_codes.get(&ModCoderParts::StopBody) {
-            code_lines.push(code.clone());
+        code_lines.push(self.user_region("        ", ModCoderParts::StopBody));
+        code_lines.push(format!("    }}"));
+        code_lines.push(format!("}}"));
+        code_lines.join("\n")
+    }
+    fn generate_test_module(&self) -> String {
+        let mut code_lines: Vec<String> = Vec::new();
+        let mut inputs: Vec<(&String, &String)> = self.inputs.iter().collect();
+        inputs.sort();
+        let mut outputs: Vec<(&String, &String)> = self.outputs.iter().collect();
+        outputs.sort();
+        let mut statics: Vec<(&String, &Typed)> = self.statics.iter().collect();
+        statics.sort_by(|a, b| a.0.cmp(b.0));
+        let mut parameters: Vec<(&String, &Typed)> = self.parameters.iter().collect();
+        parameters.sort_by(|a, b| a.0.cmp(b.0));
+        code_lines.push(format!("#[cfg(test)]"));
+        code_lines.push(format!("mod tests {{"));
+        code_lines.push(format!("    use super::*;"));
+        code_lines.push(format!("    use std::sync::mpsc::{{self, Receiver}};"));
+        code_lines.push(format!(""));
+        code_lines.push(format!("    #[allow(dead_code)]"));
+        code_lines.push(format!("    struct Harness {{"));
+        code_lines.push(format!("        block: {},", self.processor_name));
+        for (input_name, input_type) in inputs.iter() {
+            code_lines.push(format!("        in_{}: SyncSender<{}>,", input_name, input_type));
+        }
+        for (output_name, output_type) in outputs.iter() {
+            code_lines.push(format!("        out_{}: Receiver<{}>,", output_name, output_type));
+        }
+        code_lines.push(format!("    }}"));
+        code_lines.push(format!(""));
+        code_lines.push(format!("    fn new_block() -> {} {{", self.processor_name));
+        code_lines.push(format!("        let mut block = {}::new(\"test_{}\");", self.processor_name, to_snake_case(&self.processor_name)));
+        for (static_name, static_typed) in statics.iter() {
+            code_lines.push(format!("        block.set_statics_value::<{}>(\"{}\", {}).unwrap();", static_typed.data_type, static_name,
+                settable_literal(&static_typed.data_type, &static_typed.default)));
+        }
+        for (param_name, param_typed) in parameters.iter() {
+            code_lines.push(format!("        block.set_parameter_value::<{}>(\"{}\", {}).unwrap();", param_typed.data_type, param_name,
+                settable_literal(&param_typed.data_type, &param_typed.default)));
+        }
+        code_lines.push(format!("        block"));
+        code_lines.push(format!("    }}"));
+        code_lines.push(format!(""));
+        code_lines.push(format!("    #[allow(dead_code)]"));
+        code_lines.push(format!("    fn harness() -> Harness {{"));
+        code_lines.push(format!("        let mut block = new_block();"));
+        for (input_name, input_type) in inputs.iter() {
+            code_lines.push(format!("        let in_{} = block.get_input::<{}>(\"{}\").unwrap().sender.clone();", input_name, input_type, input_name));
+        }
+        for (output_name, output_type) in outputs.iter() {
+            code_lines.push(format!("        let (out_{}_sender, out_{}) = mpsc::sync_channel::<{}>(64);", output_name, output_name, output_type));
+            code_lines.push(format!("        block.connect::<{}>(\"{}\", out_{}_sender).unwrap();", output_type, output_name, output_name));
+        }
+        code_lines.push(format!("        block.init().unwrap();"));
+        let mut members: Vec<String> = vec!["block".to_string()];
+        members.extend(inputs.iter().map(|(input_name, _)| format!("in_{}", input_name)));
+        members.extend(outputs.iter().map(|(output_name, _)| format!("out_{}", output_name)));
+        code_lines.push(format!("        Harness {{ {} }}", members.join(", ")));
+        code_lines.push(format!("    }}"));
+        code_lines.push(format!(""));
+        code_lines.push(format!("    #[test]"));
+        code_lines.push(format!("    fn test_new() {{"));
+        code_lines.push(format!("        let block = {}::new(\"test_{}\");", self.processor_name, to_snake_case(&self.processor_name)));
+        code_lines.push(format!("        assert_eq!(block.name, \"test_{}\");", to_snake_case(&self.processor_name)));
+        for (param_name, param_typed) in parameters.iter() {
+            code_lines.push(format!("        assert_eq!(block.get_parameter_value::<{}>(\"{}\").unwrap(), {});", param_typed.data_type, param_name,
+                settable_literal(&param_typed.data_type, &param_typed.default)));
         }
         code_lines.push(format!("    }}"));
+        let settables = statics.iter().map(|(name, typed)| ("statics", *name, *typed))
+            .chain(parameters.iter().map(|(name, typed)| ("parameter", *name, *typed)));
+        for (category, settable_name, typed) in settables {
+            let limits = match &typed.limits {
+                Some(limits) => limits,
+                None => continue,
+            };
+            code_lines.push(format!(""));
+            code_lines.push(format!("    #[test]"));
+            code_lines.push(format!("    fn test_{}_{}_limits() {{", category, settable_name));
+            code_lines.push(format!("        let mut block = new_block();"));
+            // Blocks read back their parameters only, statics are checked for acceptance.
+            let read_back = |code_lines: &mut Vec<String>, literal: String| if category == "parameter" {
+                code_lines.push(format!("        assert_eq!(block.get_parameter_value::<{}>(\"{}\").unwrap(), {});", typed.data_type, settable_name, literal));
+            };
+            for limit in [&limits.min, &limits.max] {
+                let literal = settable_literal(&typed.data_type, limit);
+                code_lines.push(format!("        assert!(block.set_{}_value::<{}>(\"{}\", {}).is_ok());", category, typed.data_type, settable_name, literal));
+                read_back(&mut code_lines, literal);
+            }
+            for (limit, below) in [(&limits.min, true), (&limits.max, false)] {
+                if let Some(outside) = outside_limit(&typed.data_type, limit, below) {
+                    code_lines.push(format!("        assert!(block.set_{}_value::<{}>(\"{}\", {}).is_err());", category, typed.data_type, settable_name, outside));
+                }
+            }
+            read_back(&mut code_lines, settable_literal(&typed.data_type, &limits.max));
+            code_lines.push(format!("    }}"));
+        }
+        code_lines.push(format!(""));
+        code_lines.push(format!("    #[test]"));
+        code_lines.push(format!("    fn test_process_default_inputs() {{"));
+        code_lines.push(format!("        // Two blocks fed with the same inputs must emit the same values. What the values are"));
+        code_lines.push(format!("        // depends on the process body: assert them in the user test cases below."));
+        code_lines.push(format!("        let mut harnesses = [harness(), harness()];"));
+        code_lines.push(format!("        for harness in harnesses.iter_mut() {{"));
+        for (input_name, input_type) in inputs.iter() {
+            code_lines.push(format!("            harness.in_{}.send(<{} as Default>::default()).unwrap();", input_name, input_type));
+        }
+        code_lines.push(format!("            assert!(harness.block.process().is_ok());"));
+        code_lines.push(format!("        }}"));
+        for (output_name, output_type) in outputs.iter() {
+            code_lines.push(format!("        let out_{}_values: Vec<Vec<{}>> = harnesses.iter().map(|harness| harness.out_{}.try_iter().collect()).collect();",
+                output_name, output_type, output_name));
+            code_lines.push(format!("        assert_eq!(out_{}_values[0], out_{}_values[1]);", output_name, output_name));
+        }
+        code_lines.push(format!("    }}"));
+        code_lines.join("\n")
+    }
+    fn generate_user_test_cases(&self) -> String {
+        let mut code_lines: Vec<String> = Vec::new();
+        code_lines.push(self.user_region("    ", ModCoderParts::UserTestCases));
         code_lines.push(format!("}}"));
         code_lines.join("\n")
     }
@@ -342,21 +569,7 @@ impl ProcessorCoder {
 impl Coder for ProcessorCoder {
     fn generate(&mut self) -> Result<(), String> {
         let code_file = self.get_tmp_file();
-        let mut code_lines: Vec<String> = Vec::new();
-        code_lines.push(self.generate_head_mod());
-        code_lines.push(self.generate_user_defined_code());
-        code_lines.push(self.generate_head_struct());
-        code_lines.push(self.generate_user_defined_struct());
-        code_lines.push(self.generate_head_builder());
-        code_lines.push(self.generate_user_defined_builder());
-        code_lines.push(self.generate_member_creation());
-        code_lines.push(self.generate_user_member_creation());
-        code_lines.push(self.generate_user_defined_impl_struct());
-        code_lines.push(self.generate_init_body());
-        code_lines.push(self.generate_run_body());
-        code_lines.push(self.generate_process_body());
-        code_lines.push(self.generate_stop_body());
-        let full_code = code_lines.join("\n");
+        let full_code = self.render()?;
         self.file_write(code_file.clone(), full_code)?;
         println!("Moving temp file to file {}", &self.file_path);
         std::fs::rename(&code_file.clone(), &self.file_path).map_err(|e| format!("Error renaming temp file to {}: {}", self.file_path, e))?;
@@ -364,11 +577,82 @@ impl Coder for ProcessorCoder {
         Ok(())
     }
 
+    fn render(&mut self) -> Result<String, String> {
+        let (full_code, sections) = join_sections(vec![
+            (format!("{:?}", ModCoderParts::HeadMod), self.generate_head_mod()),
+            (format!("{:?}", ModCoderParts::UsedDefinedCode), self.generate_user_defined_code()),
+            (format!("{:?}", ModCoderParts::HeadStruct), self.generate_head_struct()),
+            (format!("{:?}", ModCoderParts::UserDefinedStruct), self.generate_user_defined_struct()),
+            (format!("{:?}", ModCoderParts::HeadBuilder), self.generate_head_builder()),
+            (format!("{:?}", ModCoderParts::UserDefinedBuilder), self.generate_user_defined_builder()),
+            ("MemberCreation".to_string(), self.generate_member_creation()),
+            (format!("{:?}", ModCoderParts::UserMemberCreation), self.generate_user_member_creation()),
+            (format!("{:?}", ModCoderParts::UserDefinedImplStruct), self.generate_user_defined_impl_struct()),
+            (format!("{:?}", ModCoderParts::InitBody), self.generate_init_body()),
+            (format!("{:?}", ModCoderParts::RunBody), self.generate_run_body()),
+            (format!("{:?}", ModCoderParts::ProcessBody), self.generate_process_body()),
+            (format!("{:?}", ModCoderParts::StopBody), self.generate_stop_body()),
+            ("TestModule".to_string(), self.generate_test_module()),
+            (format!("{:?}", ModCoderParts::UserTestCases), self.generate_user_test_cases()),
+        ]);
+        self.sections = sections;
+        Ok(full_code)
+    }
+
+    fn sync_from_file(&mut self) -> Result<Vec<String>, String> {
+        let parts = (0..=u8::MAX).filter_map(|i| ModCoderParts::try_from(i).ok()).collect();
+        let edited = edited_regions(&self.file_path, parts, &self.user_codes)?;
+        let mut updated: Vec<String> = Vec::new();
+        for (part, code) in edited.into_iter() {
+            updated.push(format!("{:?}", part));
+            self.user_codes.insert(part, code);
+        }
+        if !updated.is_empty() {
+            self.save()?;
+        }
+        Ok(updated)
+    }
+
+    fn relocate(&mut self, path: String) {
+        self.file_path = format!("{}/src/{}.rs", path, to_snake_case(&self.processor_name));
+        self.crate_path = path;
+    }
+
     fn get_path(&self) -> String {
         self.crate_path.clone()
     }
 
+    fn get_file_path(&self) -> String {
+        self.file_path.clone()
+    }
+
+    fn get_save_path(&self) -> String {
+        format!("{}/.project/{}.json", self.crate_path, self.processor_name)
+    }
+
+    fn get_sections(&self) -> &Vec<SectionSpan> {
+        &self.sections
+    }
+
     fn as_any(&self) -> &dyn std::any::Any {self}
 
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {self}
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renamed_member_is_still_quoted_in_user_code() {
+        let mut gain = ProcessorCoder::new("/tmp/dsp".to_string(), "Gain".to_string());
+        gain.add_settable(&"parameter".to_string(), &"gain".to_string(), &"f32".to_string(), &"1.0".to_string(), None);
+        gain.add_code_section(ModCoderParts::ProcessBody, "
+        let gain = self.get_parameter_value::<f32>(\"gain\")?;
+        Ok(())".to_string());
+        gain.rename_member(&"gain".to_string(), &"level".to_string()).unwrap();
+        assert!(gain.get_parameters().contains_key("level"));
+        assert_eq!(gain.regions_quoting(&"gain".to_string()), vec!["ProcessBody".to_string()]);
+        assert!(gain.regions_quoting(&"level".to_string()).is_empty());
+    }
+}
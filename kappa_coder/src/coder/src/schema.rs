@@ -0,0 +1,147 @@
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use crate::processor_coder::ProcessorCoder;
+
+// Version of the files stored in `.project/`. Files written before versioning was introduced
+// hold the bare serialized struct and are treated as version 0.
+pub const SCHEMA_VERSION: u32 = 2;
+
+pub const MEMORY_MAP: &str = "memory_map";
+pub const LIB_CODER: &str = "lib_coder";
+pub const MAIN_CODER: &str = "main_coder";
+pub const PROCESSOR_CODER: &str = "processor_coder";
+pub const MANIFEST: &str = "manifest";
+pub const PROJECT: &str = "project";
+
+// Upgrades the data of a file from version `n` to `n + 1`.
+type Migration = fn(Value) -> Result<Value, String>;
+
+fn unchanged(data: Value) -> Result<Value, String> {
+    Ok(data)
+}
+
+// Version 1 names the module after the crate instead of leaving the name empty.
+fn lib_coder_v1(mut data: Value) -> Result<Value, String> {
+    let crate_name = data["crate_path"].as_str()
+        .and_then(|path| std::path::Path::new(path).file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_string();
+    if let Some(module) = data.get_mut("module_structs").and_then(|m| m.as_object_mut()) {
+        if module.get("name").and_then(|name| name.as_str()).map_or(true, |name| name.is_empty()) {
+            module.insert("name".to_string(), json!(crate_name));
+        }
+    }
+    Ok(data)
+}
+
+// Version 0 coders were saved without the default init, run, process and stop bodies.
+fn processor_coder_v1(mut data: Value) -> Result<Value, String> {
+    let defaults = serde_json::to_value(ProcessorCoder::default_user_codes())
+        .map_err(|e| format!("Error serializing default user code: {}", e))?;
+    if !data["user_codes"].is_object() {
+        data["user_codes"] = json!({});
+    }
+    let user_codes = data["user_codes"].as_object_mut().unwrap();
+    for (part, code) in defaults.as_object().unwrap().iter() {
+        if !user_codes.contains_key(part) {
+            user_codes.insert(part.clone(), code.clone());
+        }
+    }
+    Ok(data)
+}
+
+// The project file carried its own version field, which the envelope replaces.
+fn project_v1(mut data: Value) -> Result<Value, String> {
+    if let Some(project) = data.as_object_mut() {
+        project.remove("schema_version");
+    }
+    Ok(data)
+}
+
+// Version 2 stream processors belong to their task and carry their block type, which only the
+// application coder knows: the type is left empty here and filled in when the project is imported.
+fn memory_map_v2(mut data: Value) -> Result<Value, String> {
+    if let Some(objects) = data.as_object_mut() {
+        for (object_name, object) in objects.iter_mut() {
+            if object["object_category"] != "StreamProc" || object["object_type"] != "stream_proc" {
+                continue;
+            }
+            let task_name = object_name.rsplit_once('.').map(|(task_name, _)| task_name).unwrap_or_default();
+            object["parent"] = json!(task_name);
+            object["object_type"] = json!("");
+        }
+    }
+    Ok(data)
+}
+
+fn migrations(kind: &str) -> Result<Vec<Migration>, String> {
+    match kind {
+        MEMORY_MAP => Ok(vec![unchanged, memory_map_v2]),
+        LIB_CODER => Ok(vec![lib_coder_v1, unchanged]),
+        MAIN_CODER => Ok(vec![unchanged, unchanged]),
+        PROCESSOR_CODER => Ok(vec![processor_coder_v1, unchanged]),
+        MANIFEST => Ok(vec![unchanged, unchanged]),
+        PROJECT => Ok(vec![project_v1, unchanged]),
+        _ => Err(format!("Unknown project file kind {}.", kind)),
+    }
+}
+
+// Splits a stored file into its version and data, accepting the unversioned format.
+fn open_envelope(kind: &str, stored: Value) -> Result<(u32, Value), String> {
+    let is_envelope = stored.get("schema_version").is_some() && stored.get("kind").is_some() && stored.get("data").is_some();
+    if !is_envelope {
+        return Ok((0, stored));
+    }
+    let stored_kind = stored["kind"].as_str().unwrap_or_default();
+    if stored_kind != kind {
+        return Err(format!("Expected a {} file, found {}.", kind, stored_kind));
+    }
+    let version = stored["schema_version"].as_u64().ok_or_else(|| "Invalid schema version.".to_string())? as u32;
+    Ok((version, stored["data"].clone()))
+}
+
+fn migrate(kind: &str, version: u32, mut data: Value) -> Result<Value, String> {
+    if version > SCHEMA_VERSION {
+        return Err(format!("The {} file has schema version {}, newer than the supported version {}.", kind, version, SCHEMA_VERSION));
+    }
+    let migrations = migrations(kind)?;
+    for step in version..SCHEMA_VERSION {
+        data = migrations[step as usize](data).map_err(|e| format!("Error migrating {} file from version {}: {}", kind, step, e))?;
+    }
+    Ok(data)
+}
+
+pub fn to_versioned_string<T: Serialize>(kind: &str, data: &T) -> Result<String, String> {
+    let data = serde_json::to_value(data).map_err(|e| format!("Error serializing {}: {}", kind, e))?;
+    let envelope = json!({
+        "schema_version": SCHEMA_VERSION,
+        "kind": kind,
+        "data": data,
+    });
+    serde_json::to_string(&envelope).map_err(|e| format!("Error serializing {}: {}", kind, e))
+}
+
+pub fn from_versioned_str<T: DeserializeOwned>(kind: &str, json_data: &str) -> Result<T, String> {
+    let stored: Value = serde_json::from_str(json_data).map_err(|e| format!("Error parsing {} file: {}", kind, e))?;
+    let (version, data) = open_envelope(kind, stored)?;
+    let data = migrate(kind, version, data)?;
+    serde_json::from_value(data).map_err(|e| format!("Error deserializing {}: {}", kind, e))
+}
+
+pub fn write_versioned<T: Serialize>(path: &String, kind: &str, data: &T) -> Result<(), String> {
+    let json_string = to_versioned_string(kind, data)?;
+    std::fs::write(path, json_string).map_err(|e| format!("Error writing {} file {}: {}", kind, path, e))
+}
+
+pub fn read_versioned<T: DeserializeOwned>(path: &String, kind: &str) -> Result<T, String> {
+    let json_data = std::fs::read_to_string(path).map_err(|e| format!("Error reading {} file {}: {}", kind, path, e))?;
+    from_versioned_str(kind, &json_data)
+}
+
+pub fn file_version(path: &String, kind: &str) -> Result<u32, String> {
+    let json_data = std::fs::read_to_string(path).map_err(|e| format!("Error reading {} file {}: {}", kind, path, e))?;
+    let stored: Value = serde_json::from_str(&json_data).map_err(|e| format!("Error parsing {} file {}: {}", kind, path, e))?;
+    Ok(open_envelope(kind, stored)?.0)
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::any::Any;
+use std::fmt::Debug;
+use std::sync::mpsc::SyncSender;
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+use stream_proc_macro::{StreamBlockMacro};
+use data_model::streaming_data::{StreamingError, StreamingState};
+use data_model::memory_manager::{DataTrait, StaticsTrait, State, Parameter, Statics};
+use processor_engine::stream_processor::{StreamBlock, StreamBlockDyn, StreamProcessor};
+use processor_engine::connectors::{ConnectorTrait, Input, Output};
+// kappa:begin UsedDefinedCode
+// kappa:end
+#[derive(StreamBlockMacro)]
+pub struct Gain {
+    name:       &'static str,
+    inputs:     HashMap<&'static str, Box<dyn ConnectorTrait>>,
+    outputs:    HashMap<&'static str, Box<dyn ConnectorTrait>>,
+    parameters: HashMap<&'static str, Box<dyn DataTrait>>,
+    statics:    HashMap<&'static str, Box<dyn StaticsTrait>>,
+    state:      HashMap<&'static str, Box<dyn DataTrait>>,
+    lock:       Arc<Mutex<()>>,
+    proc_state: Arc<Mutex<StreamingState>>,
+    // kappa:begin UserDefinedStruct
+    // kappa:end
+}
+impl Gain {
+    pub fn new(name: &'static str) -> Self {
+        let mut ret = Self {
+            name,
+            inputs: HashMap::new(),
+            outputs: HashMap::new(),
+            parameters: HashMap::new(),
+            statics: HashMap::new(),
+            state: HashMap::new(),
+            lock: Arc::new(Mutex::new(())),
+            proc_state: Arc::new(Mutex::new(StreamingState::Null)),
+            // kappa:begin UserDefinedBuilder
+            // kappa:end
+        };
+        ret.new_input::<f32>("x");
+        ret.new_output::<f32>("y");
+        ret.new_statics::<String>("label", "gain".to_string(), None);
+        ret.new_parameter::<f32>("gain", 1.0, Some((0.0, 10.0)));
+        // kappa:begin UserMemberCreation
+        // kappa:end
+        ret
+    }
+    // kappa:begin UserDefinedImplStruct
+    // kappa:end
+}
+impl StreamProcessor for Gain {
+    fn init(&mut self) -> Result<(), StreamingError> {
+        // kappa:begin InitBody
+        
+        if self.check_state(StreamingState::Running) {
+            return Err(StreamingError::InvalidStateTransition)
+        }
+        if !self.is_initialized() {
+            return Err(StreamingError::InvalidStatics)
+        }
+        if self.check_state(StreamingState::Null) {
+            // Initialization code here
+        }
+        self.set_state(StreamingState::Initial);
+        Ok(())
+        // kappa:end
+    }
+    fn run(&mut self) -> Result<(), StreamingError> {
+        // kappa:begin RunBody
+
+        if self.check_state(StreamingState::Stopped) {
+            return Err(StreamingError::InvalidStateTransition);
+        }
+        self.set_state(StreamingState::Running);
+
+        // Here main processing loop with process calls
+        while !self.check_state(StreamingState::Stopped) {
+            self.process()?;
+        }
+        Ok(())
+        // kappa:end
+    }
+    fn process(&mut self) -> Result<(), StreamingError> {
+        // kappa:begin ProcessBody
+
+        Ok(())
+        // kappa:end
+    }
+    fn stop(&mut self) -> Result<(), StreamingError> {
+        // kappa:begin StopBody
+
+        self.set_state(StreamingState::Stopped);
+        Ok(())
+        // kappa:end
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{self, Receiver};
+
+    #[allow(dead_code)]
+    struct Harness {
+        block: Gain,
+        in_x: SyncSender<f32>,
+        out_y: Receiver<f32>,
+    }
+
+    fn new_block() -> Gain {
+        let mut block = Gain::new("test_gain");
+        block.set_statics_value::<String>("label", "gain".to_string()).unwrap();
+        block.set_parameter_value::<f32>("gain", 1.0).unwrap();
+        block
+    }
+
+    #[allow(dead_code)]
+    fn harness() -> Harness {
+        let mut block = new_block();
+        let in_x = block.get_input::<f32>("x").unwrap().sender.clone();
+        let (out_y_sender, out_y) = mpsc::sync_channel::<f32>(64);
+        block.connect::<f32>("y", out_y_sender).unwrap();
+        block.init().unwrap();
+        Harness { block, in_x, out_y }
+    }
+
+    #[test]
+    fn test_new() {
+        let block = Gain::new("test_gain");
+        assert_eq!(block.name, "test_gain");
+        assert_eq!(block.get_parameter_value::<f32>("gain").unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_parameter_gain_limits() {
+        let mut block = new_block();
+        assert!(block.set_parameter_value::<f32>("gain", 0.0).is_ok());
+        assert_eq!(block.get_parameter_value::<f32>("gain").unwrap(), 0.0);
+        assert!(block.set_parameter_value::<f32>("gain", 10.0).is_ok());
+        assert_eq!(block.get_parameter_value::<f32>("gain").unwrap(), 10.0);
+        assert!(block.set_parameter_value::<f32>("gain", -1.0).is_err());
+        assert!(block.set_parameter_value::<f32>("gain", 11.0).is_err());
+        assert_eq!(block.get_parameter_value::<f32>("gain").unwrap(), 10.0);
+    }
+
+    #[test]
+    fn test_process_default_inputs() {
+        // Two blocks fed with the same inputs must emit the same values. What the values are
+        // depends on the process body: assert them in the user test cases below.
+        let mut harnesses = [harness(), harness()];
+        for harness in harnesses.iter_mut() {
+            harness.in_x.send(<f32 as Default>::default()).unwrap();
+            assert!(harness.block.process().is_ok());
+        }
+        let out_y_values: Vec<Vec<f32>> = harnesses.iter().map(|harness| harness.out_y.try_iter().collect()).collect();
+        assert_eq!(out_y_values[0], out_y_values[1]);
+    }
+    // kappa:begin UserTestCases
+    // kappa:end
+}
\ No newline at end of file
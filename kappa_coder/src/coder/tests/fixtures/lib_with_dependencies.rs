@@ -0,0 +1,60 @@
+pub mod source;
+pub mod gain;
+pub mod sink;
+use std::ffi::c_char;
+use data_model::modules::{Version,ModuleStructFFI};
+use processor_engine::stream_processor::StreamProcessor;
+use processor_engine::ffi::{TraitObjectRepr, export_stream_processor, get_error_return};
+struct FfiStringArray<const N: usize>([*const c_char; N]);
+unsafe impl<const N: usize> Sync for FfiStringArray<N> {}
+static MODULE_DEPENDENCIES: FfiStringArray<2> = FfiStringArray([
+    b"filters@^1.0\0".as_ptr() as *const c_char,
+    b"io\0".as_ptr() as *const c_char,
+]);
+static MODULE_PROVIDES: FfiStringArray<2> = FfiStringArray([
+    b"gain\0".as_ptr() as *const c_char,
+    b"source\0".as_ptr() as *const c_char,
+]);
+#[unsafe(no_mangle)]
+pub static MODULE: ModuleStructFFI  = ModuleStructFFI {
+    name: b"dsp\0".as_ptr() as *const c_char,
+    description: b"Signal \"dsp\" blocks\0".as_ptr() as *const c_char,
+    authors: b"Kappa\0".as_ptr() as *const c_char,
+    release_date: b"2025-01-31\0".as_ptr() as *const c_char,
+    version: Version{ major: 1,minor: 2,build: 3},
+    dependencies: MODULE_DEPENDENCIES.0.as_ptr(),
+    dependency_number: 2,
+    provides: MODULE_PROVIDES.0.as_ptr(),
+    provides_lengths: 2,
+};
+#[unsafe(no_mangle)]
+pub extern "C" fn get_processor_modules(proc_block: *const u8, 
+    proc_block_len: usize, 
+    block_name: *const u8, 
+    block_name_len: usize) -> TraitObjectRepr {
+    let proc_block_str = unsafe {
+        std::str::from_utf8(std::slice::from_raw_parts(proc_block, proc_block_len)).unwrap()
+    };
+    let block_name_str = unsafe {
+        std::str::from_utf8(std::slice::from_raw_parts(block_name, block_name_len)).unwrap()
+    };
+    let proc: Box<dyn StreamProcessor>;
+    match proc_block_str {
+        "Source" => {
+            proc = Box::new(source::Source::new(block_name_str));
+            export_stream_processor(proc)
+        }
+        "Gain" => {
+            proc = Box::new(gain::Gain::new(block_name_str));
+            export_stream_processor(proc)
+        }
+        "Sink" => {
+            proc = Box::new(sink::Sink::new(block_name_str));
+            export_stream_processor(proc)
+        }
+        _ => {
+            eprintln!("Processor block {} not found", proc_block_str);
+            get_error_return(1)
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,55 @@
+// Auto-generated main.rs file
+#![allow(unused_imports, unused_mut)]
+use processor_engine::engine::ProcessorEngine;
+use processor_engine::task_monitor::TaskManager;
+use processor_engine::stream_processor::{StreamBlock, StreamBlockDyn, StreamProcessor};
+use processor_engine::connectors::ConnectorTrait;
+use data_model::streaming_data::{StreamingError, StreamingState};
+use dsp::gain::Gain;
+use dsp::sink::Sink;
+use dsp::source::Source;
+// kappa:begin HeadMain
+// kappa:end
+// kappa:begin UsedDefinedCode
+// kappa:end
+fn main() {
+    // Stream processor creation
+    let mut acquire_gain = Gain::new("app.acquire.gain");
+    let mut acquire_source = Source::new("app.acquire.source");
+    let mut output_sink = Sink::new("app.output.sink");
+    // Stream processor settings
+    acquire_source.set_statics_value::<i32>("count", 32).unwrap();
+    acquire_source.set_parameter_value::<bool>("enabled", false).unwrap();
+    acquire_gain.set_parameter_value::<f32>("gain", 2.0).unwrap();
+    acquire_gain.set_statics_value::<String>("label", "main gain".to_string()).unwrap();
+    // Stream processor connections
+    let sender = acquire_gain.get_input::<f32>("x").unwrap().sender.clone();
+    acquire_source.connect::<f32>("samples", sender).unwrap();
+    let sender = output_sink.get_input::<f32>("samples").unwrap().sender.clone();
+    acquire_gain.connect::<f32>("y", sender).unwrap();
+    // kappa:begin StreamProcessorUserCode
+    // kappa:end
+    {
+        let mut processor_engine = ProcessorEngine::get().lock().unwrap();
+        processor_engine.register_processor("app.acquire.gain", Box::new(acquire_gain)).unwrap();
+        processor_engine.register_processor("app.acquire.source", Box::new(acquire_source)).unwrap();
+        processor_engine.register_processor("app.output.sink", Box::new(output_sink)).unwrap();
+        processor_engine.init().unwrap();
+    }
+    {
+        let mut task_manager = TaskManager::get().lock().unwrap();
+        task_manager.spawn_task("app.acquire", || {
+            let mut processor_engine = ProcessorEngine::get().lock().unwrap();
+            processor_engine.process("app.acquire.source").unwrap();
+            processor_engine.process("app.acquire.gain").unwrap();
+        });
+        task_manager.spawn_task("app.output", || {
+            let mut processor_engine = ProcessorEngine::get().lock().unwrap();
+            processor_engine.process("app.output.sink").unwrap();
+        });
+    }
+    // The tasks run until Enter is pressed.
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line).unwrap();
+    ProcessorEngine::get().lock().unwrap().stop().unwrap();
+}
\ No newline at end of file
@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use coder::coder::Coder;
+use coder::lib_coder::LibCoder;
+use coder::main_coder::MainCoder;
+use coder::processor_coder::ProcessorCoder;
+
+// Run with KAPPA_UPDATE_GOLDEN=1 to rewrite the fixtures after an intended change of the generated code.
+fn assert_golden(fixture: &str, generated: &String) {
+    let path = fixture_path(fixture);
+    if std::env::var("KAPPA_UPDATE_GOLDEN").is_ok() {
+        std::fs::write(&path, generated).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("Error reading {}: {}", path.display(), e));
+    assert_eq!(*generated, expected, "Generated code differs from {}", fixture);
+}
+
+fn fixture_path(fixture: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(fixture)
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("kappa_golden_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+// Block crate `dsp` with a source, a gain and a sink, as created by `create block` and friends.
+fn dsp_blocks(crate_path: &String) -> Vec<ProcessorCoder> {
+    let s = |text: &str| text.to_string();
+    let mut source = ProcessorCoder::new(crate_path.clone(), s("Source"));
+    source.add_typed(&s("output"), &s("samples"), &s("f32"));
+    source.add_settable(&s("static"), &s("count"), &s("i32"), &s("16"), None);
+    source.add_settable(&s("parameter"), &s("enabled"), &s("bool"), &s("true"), None);
+    let mut gain = ProcessorCoder::new(crate_path.clone(), s("Gain"));
+    gain.add_typed(&s("input"), &s("x"), &s("f32"));
+    gain.add_typed(&s("output"), &s("y"), &s("f32"));
+    gain.add_settable(&s("parameter"), &s("gain"), &s("f32"), &s("1"), Some(&s("0,10")));
+    gain.add_settable(&s("static"), &s("label"), &s("String"), &s("gain"), None);
+    let mut sink = ProcessorCoder::new(crate_path.clone(), s("Sink"));
+    sink.add_typed(&s("input"), &s("samples"), &s("f32"));
+    vec![source, gain, sink]
+}
+
+// Library of the block crate with the metadata `manifest` sets, including dependencies and provides.
+fn dsp_lib_coder(crate_path: &String, blocks: &Vec<ProcessorCoder>) -> LibCoder {
+    let s = |text: &str| text.to_string();
+    let mut lib_coder = LibCoder::new(crate_path.clone());
+    for block in blocks.iter() {
+        lib_coder.add_module(block.get_processor_name().clone());
+    }
+    lib_coder.set_module_name(s("dsp")).unwrap();
+    lib_coder.set_module_description(s("Signal \"dsp\" blocks")).unwrap();
+    lib_coder.set_module_authors(s("Kappa")).unwrap();
+    lib_coder.set_module_release_date(s("2025-01-31")).unwrap();
+    lib_coder.set_module_version(s("1.2.3")).unwrap();
+    lib_coder.set_module_dependencies(vec![s("filters@^1.0"), s("io")]).unwrap();
+    lib_coder.set_module_provides(vec![s("gain"), s("source")]).unwrap();
+    lib_coder
+}
+
+// Application `app` with two tasks, typed settings of each kind and a chain of connections.
+fn pipeline_main_coder(crate_path: &String) -> MainCoder {
+    let s = |text: &str| text.to_string();
+    let mut main_coder = MainCoder::new(crate_path.clone());
+    main_coder.add_task_processor(s("app.acquire"));
+    main_coder.add_task_processor(s("app.output"));
+    main_coder.add_stream_processor(s("app.acquire.source"), s("dsp.Source")).unwrap();
+    main_coder.add_stream_processor(s("app.acquire.gain"), s("dsp.Gain")).unwrap();
+    main_coder.add_stream_processor(s("app.output.sink"), s("dsp.Sink")).unwrap();
+    main_coder.add_setting_value(s("app.acquire.source"), s("static"), s("app.acquire.source.count"), s("32"), s("i32"));
+    main_coder.add_setting_value(s("app.acquire.source"), s("parameter"), s("app.acquire.source.enabled"), s("false"), s("bool"));
+    main_coder.add_setting_value(s("app.acquire.gain"), s("parameter"), s("app.acquire.gain.gain"), s("2"), s("f32"));
+    main_coder.add_setting_value(s("app.acquire.gain"), s("static"), s("app.acquire.gain.label"), s("main gain"), s("String"));
+    main_coder.add_connection(s("app.acquire.source"), s("app.acquire.source.samples"), s("app.acquire.gain"), s("app.acquire.gain.x"), s("f32"));
+    main_coder.add_connection(s("app.acquire.gain"), s("app.acquire.gain.y"), s("app.output.sink"), s("app.output.sink.samples"), s("f32"));
+    main_coder
+}
+
+#[test]
+fn main_rs_of_pipeline_matches_golden_file() {
+    let dir = scratch_dir("main");
+    let mut main_coder = pipeline_main_coder(&path_string(&dir));
+    assert_golden("pipeline_main.rs", &main_coder.render().unwrap());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn lib_rs_with_dependencies_matches_golden_file() {
+    let dir = scratch_dir("lib");
+    let crate_path = path_string(&dir);
+    let mut lib_coder = dsp_lib_coder(&crate_path, &dsp_blocks(&crate_path));
+    assert_golden("lib_with_dependencies.rs", &lib_coder.render().unwrap());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// Literals of the declared types in settings, limits and the generated tests of the block.
+#[test]
+fn block_with_typed_settables_matches_golden_file() {
+    let dir = scratch_dir("block");
+    let mut gain = dsp_blocks(&path_string(&dir)).remove(1);
+    assert_golden("gain_block.rs", &gain.render().unwrap());
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+fn write_file(path: PathBuf, content: String) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, content).unwrap();
+}
+
+// Same dependencies as `cargo_add_commands` and `library_dependencies` give to created crates.
+fn crate_manifest(name: &str, kappa_lib: &Path, lib: bool, extra: &str) -> String {
+    let lib_section = if lib { "\n[lib]\ncrate-type = [\"cdylib\", \"rlib\"]\n" } else { "" };
+    format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2024\"\n{}
+[dependencies]
+num-traits = \"0.2\"
+serde_json = \"1.0\"
+serde = {{ version = \"1.0\", features = [\"derive\"] }}
+processor_engine = {{ path = \"{lib}/processor_engine\" }}
+stream_proc_macro = {{ path = \"{lib}/processor_engine/src/stream_proc_macro\" }}
+data_model = {{ path = \"{lib}/data_model\" }}
+utils = {{ path = \"{lib}/utils\" }}
+{}", name, lib_section, extra, lib = kappa_lib.display())
+}
+
+// Generates the block crate and the application of the golden file in a workspace and checks that
+// both, with the tests generated for the blocks, compile against kappa_lib. The check is skipped in
+// checkouts without kappa_lib next to kappa_coder.
+#[test]
+fn generated_application_passes_cargo_check() {
+    // An uninitialised submodule leaves an empty folder behind.
+    let kappa_lib = match Path::new(env!("CARGO_MANIFEST_DIR")).join("../../../kappa_lib").canonicalize() {
+        Ok(kappa_lib) if kappa_lib.join("data_model").join("Cargo.toml").exists() => kappa_lib,
+        _ => {
+            eprintln!("Skipping cargo check of the generated crates: kappa_lib is not checked out next to kappa_coder.");
+            return;
+        },
+    };
+    let root = scratch_dir("cargo_check");
+    write_file(root.join("Cargo.toml"), "[workspace]\nmembers = [\"dsp\", \"app\"]\nresolver = \"2\"\n".to_string());
+
+    let dsp = root.join("dsp");
+    write_file(dsp.join("Cargo.toml"), crate_manifest("dsp", &kappa_lib, true, ""));
+    std::fs::create_dir_all(dsp.join("src")).unwrap();
+    std::fs::create_dir_all(dsp.join(".project")).unwrap();
+    let mut blocks = dsp_blocks(&path_string(&dsp));
+    for block in blocks.iter_mut() {
+        block.generate().unwrap();
+    }
+    dsp_lib_coder(&path_string(&dsp), &blocks).generate().unwrap();
+
+    let app = root.join("app");
+    write_file(app.join("Cargo.toml"), crate_manifest("app", &kappa_lib, false, "dsp = { path = \"../dsp\" }\n"));
+    write_file(app.join("src").join("main.rs"), std::fs::read_to_string(fixture_path("pipeline_main.rs")).unwrap());
+
+    let output = Command::new("cargo")
+        .args(["check", "--workspace", "--all-targets", "--quiet"])
+        .current_dir(&root)
+        .output()
+        .expect("Error running cargo check");
+    assert!(output.status.success(), "cargo check of the generated crates failed:\n{}", String::from_utf8_lossy(&output.stderr));
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+use serde_json::{Value, json};
+use coder::coder::Coder;
+use coder::lib_coder::LibCoder;
+use coder::main_coder::MainCoder;
+use coder::manifest_coder::ManifestCoder;
+use coder::processor_coder::ProcessorCoder;
+use coder::schema;
+
+// `.project` files as written before the versioned envelope was introduced.
+fn legacy_file(project: &str, file_name: &str) -> String {
+    let path: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/legacy").join(project).join(".project").join(file_name);
+    path.to_string_lossy().to_string()
+}
+
+#[test]
+fn legacy_files_have_version_zero() {
+    for (project, file_name, kind) in [
+        ("dsp", "lib_coder.json", schema::LIB_CODER),
+        ("dsp", "Gain.json", schema::PROCESSOR_CODER),
+        ("dsp", "manifest.json", schema::MANIFEST),
+        ("dsp", "memory_map.json", schema::MEMORY_MAP),
+        ("dsp", "project.json", schema::PROJECT),
+        ("app", "main_coder.json", schema::MAIN_CODER),
+    ] {
+        assert_eq!(schema::file_version(&legacy_file(project, file_name), kind).unwrap(), 0, "{}/{}", project, file_name);
+    }
+}
+
+#[test]
+fn lib_coder_migration_names_module_after_crate() {
+    let lib_coder = LibCoder::load(legacy_file("dsp", "lib_coder.json")).unwrap();
+    assert_eq!(lib_coder.get_module_struct().name, "dsp");
+    assert_eq!(lib_coder.get_module_struct().description, "Signal blocks");
+    assert_eq!(*lib_coder.get_modules(), vec!["Gain".to_string()]);
+}
+
+#[test]
+fn processor_coder_migration_adds_missing_default_bodies() {
+    let mut processor_coder = ProcessorCoder::load(legacy_file("dsp", "Gain.json")).unwrap();
+    assert_eq!(processor_coder.get_processor_name(), "Gain");
+    assert_eq!(processor_coder.get_parameters()["gain"].data_type, "f32");
+    let code = processor_coder.render().unwrap();
+    // The edited body is kept, the missing ones get the defaults.
+    assert!(code.contains("// Scales x by gain"));
+    assert!(code.contains("self.set_state(StreamingState::Stopped);"));
+    assert!(code.contains("self.set_state(StreamingState::Running);"));
+}
+
+#[test]
+fn main_coder_migration_keeps_untyped_settings() {
+    let main_coder = MainCoder::load(legacy_file("app", "main_coder.json")).unwrap();
+    assert_eq!(main_coder.get_stream_processors()["app.main.gain"], "dsp.Gain");
+    let settings = main_coder.get_settings();
+    assert_eq!(settings.len(), 1);
+    assert_eq!(settings[0].value, "2.0");
+    assert_eq!(settings[0].data_type, "");
+}
+
+#[test]
+fn manifest_migration_keeps_dependencies() {
+    let manifest_coder = ManifestCoder::load(legacy_file("app", "manifest.json")).unwrap();
+    assert_eq!(manifest_coder.get_dependencies()["dsp"].path.as_deref(), Some("../dsp"));
+    assert_eq!(manifest_coder.get_path(), "/home/kappa/work/app");
+}
+
+#[test]
+fn memory_map_migration_keeps_objects() {
+    let memory_map: Value = schema::read_versioned(&legacy_file("dsp", "memory_map.json"), schema::MEMORY_MAP).unwrap();
+    assert_eq!(memory_map["dsp.Gain.gain"]["object_limits"], "0.0,10.0");
+    assert_eq!(memory_map.as_object().unwrap().len(), 5);
+}
+
+#[test]
+fn memory_map_migration_moves_stream_processors_to_their_task() {
+    let memory_map: Value = schema::read_versioned(&legacy_file("app", "memory_map.json"), schema::MEMORY_MAP).unwrap();
+    assert_eq!(memory_map["app.main.gain"]["parent"], "app.main");
+    assert_eq!(memory_map["app.main.gain"]["object_type"], "");
+    assert_eq!(memory_map["app.main"]["parent"], "app");
+    // Version 1 files hold the same stream processor objects as unversioned ones.
+    let version_1 = json!({ "schema_version": 1, "kind": schema::MEMORY_MAP, "data": {
+        "app.main.gain": { "parent": "app", "object_category": "StreamProc", "object_type": "stream_proc", "object_value": "", "object_limits": "" },
+    } }).to_string();
+    let memory_map: Value = schema::from_versioned_str(schema::MEMORY_MAP, &version_1).unwrap();
+    assert_eq!(memory_map["app.main.gain"]["parent"], "app.main");
+}
+
+#[test]
+fn project_migration_moves_version_to_envelope() {
+    let project: Value = schema::read_versioned(&legacy_file("dsp", "project.json"), schema::PROJECT).unwrap();
+    assert_eq!(project, json!({ "name": "dsp", "kind": "crate" }));
+}
+
+#[test]
+fn migrated_file_round_trips_in_current_version() {
+    let lib_coder = LibCoder::load(legacy_file("dsp", "lib_coder.json")).unwrap();
+    let stored = schema::to_versioned_string(schema::LIB_CODER, &lib_coder).unwrap();
+    let envelope: Value = serde_json::from_str(&stored).unwrap();
+    assert_eq!(envelope["schema_version"], schema::SCHEMA_VERSION);
+    assert_eq!(envelope["kind"], schema::LIB_CODER);
+    let reloaded: LibCoder = schema::from_versioned_str(schema::LIB_CODER, &stored).unwrap();
+    assert_eq!(reloaded.get_module_struct().name, "dsp");
+}
+
+#[test]
+fn newer_or_foreign_files_are_refused() {
+    let newer = json!({ "schema_version": schema::SCHEMA_VERSION + 1, "kind": schema::MEMORY_MAP, "data": {} }).to_string();
+    let error = schema::from_versioned_str::<Value>(schema::MEMORY_MAP, &newer).unwrap_err();
+    assert!(error.contains("newer than the supported version"), "{}", error);
+    let foreign = json!({ "schema_version": schema::SCHEMA_VERSION, "kind": schema::MAIN_CODER, "data": {} }).to_string();
+    let error = schema::from_versioned_str::<Value>(schema::LIB_CODER, &foreign).unwrap_err();
+    assert!(error.contains("Expected a lib_coder file"), "{}", error);
+}
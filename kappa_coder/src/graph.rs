@@ -0,0 +1,181 @@
+use coder::main_coder::{Connections, MainCoder};
+
+pub const GRAPH_FORMATS: [&str; 2] = ["dot", "mermaid"];
+
+struct TaskNodes {
+    name: String,
+    processors: Vec<(String, String)>,
+}
+
+fn local_name(application: &String, object_name: &String) -> String {
+    object_name.strip_prefix(&format!("{}.", application)).unwrap_or(object_name).to_string()
+}
+
+fn port_name(port: &String) -> &str {
+    port.rsplit('.').next().unwrap_or_default()
+}
+
+// Tasks with their `(processor, block type)` pairs, sorted so that diagrams stay stable under review.
+fn task_nodes(application: &String, main_coder: &MainCoder) -> Vec<TaskNodes> {
+    let mut tasks = main_coder.get_task_processors().iter()
+        .map(|(task_name, task)| {
+            let mut processors = task.stream_processors.iter()
+                .map(|proc_name| {
+                    let block_type = main_coder.get_stream_processors().get(&format!("{}.{}", task_name, proc_name)).cloned().unwrap_or_default();
+                    (proc_name.clone(), block_type)
+                })
+                .collect::<Vec<(String, String)>>();
+            processors.sort();
+            TaskNodes { name: local_name(application, task_name), processors }
+        })
+        .collect::<Vec<TaskNodes>>();
+    tasks.sort_by(|a, b| a.name.cmp(&b.name));
+    tasks
+}
+
+fn sorted_connections(main_coder: &MainCoder) -> Vec<&Connections> {
+    let mut connections = main_coder.get_connections().iter().collect::<Vec<&Connections>>();
+    connections.sort_by(|a, b| (&a.from_output, &a.to_input).cmp(&(&b.from_output, &b.to_input)));
+    connections
+}
+
+// Edge label lines: the connected ports, then the data type when it is known.
+fn edge_lines(connection: &Connections) -> Vec<String> {
+    let mut lines = vec![format!("{} -> {}", port_name(&connection.from_output), port_name(&connection.to_input))];
+    if !connection.data_type.is_empty() {
+        lines.push(connection.data_type.clone());
+    }
+    lines
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn render_dot(application: &String, main_coder: &MainCoder) -> String {
+    let mut code_lines: Vec<String> = Vec::new();
+    code_lines.push(format!("digraph \"{}\" {{", dot_escape(application)));
+    code_lines.push("    rankdir=LR;".to_string());
+    code_lines.push("    node [shape=box];".to_string());
+    for task in task_nodes(application, main_coder).iter() {
+        code_lines.push(format!("    subgraph \"cluster_{}\" {{", dot_escape(&task.name)));
+        code_lines.push(format!("        label=\"{}\";", dot_escape(&task.name)));
+        for (proc_name, block_type) in task.processors.iter() {
+            code_lines.push(format!("        \"{}.{}\" [label=\"{}\\n{}\"];",
+                dot_escape(&task.name), dot_escape(proc_name), dot_escape(proc_name), dot_escape(block_type)));
+        }
+        code_lines.push("    }".to_string());
+    }
+    for connection in sorted_connections(main_coder).into_iter() {
+        let label = edge_lines(connection).iter().map(|line| dot_escape(line)).collect::<Vec<String>>().join("\\n");
+        code_lines.push(format!("    \"{}\" -> \"{}\" [label=\"{}\"];",
+            dot_escape(&local_name(application, &connection.from_processor)),
+            dot_escape(&local_name(application, &connection.to_processor)),
+            label));
+    }
+    code_lines.push("}".to_string());
+    code_lines.join("\n")
+}
+
+// Mermaid ids only allow plain characters and must not collide with keywords such as `end`. Other
+// characters are escaped as `_<hex>_` and `_` as `__`, so that distinct names keep distinct ids.
+fn mermaid_id(prefix: &str, name: &String) -> String {
+    let mut id = format!("{}_", prefix);
+    for c in name.chars() {
+        match c {
+            '_' => id.push_str("__"),
+            c if c.is_ascii_alphanumeric() => id.push(c),
+            c => id.push_str(&format!("_{:x}_", c as u32)),
+        }
+    }
+    id
+}
+
+fn mermaid_escape(text: &str) -> String {
+    text.replace('"', "#quot;").replace('<', "#lt;").replace('>', "#gt;")
+}
+
+pub fn render_mermaid(application: &String, main_coder: &MainCoder) -> String {
+    let mut code_lines: Vec<String> = Vec::new();
+    code_lines.push("flowchart LR".to_string());
+    for task in task_nodes(application, main_coder).iter() {
+        code_lines.push(format!("    subgraph {}[\"{}\"]", mermaid_id("task", &task.name), mermaid_escape(&task.name)));
+        for (proc_name, block_type) in task.processors.iter() {
+            code_lines.push(format!("        {}[\"{}<br/>{}\"]",
+                mermaid_id("proc", &format!("{}.{}", task.name, proc_name)), mermaid_escape(proc_name), mermaid_escape(block_type)));
+        }
+        code_lines.push("    end".to_string());
+    }
+    for connection in sorted_connections(main_coder).into_iter() {
+        let label = edge_lines(connection).iter().map(|line| mermaid_escape(line)).collect::<Vec<String>>().join("<br/>");
+        code_lines.push(format!("    {} -->|\"{}\"| {}",
+            mermaid_id("proc", &local_name(application, &connection.from_processor)),
+            label,
+            mermaid_id("proc", &local_name(application, &connection.to_processor))));
+    }
+    code_lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `app` reads a source into a gain in `main` and writes the gain to a sink in `out`.
+    fn application() -> MainCoder {
+        let mut main_coder = MainCoder::new("/tmp/app".to_string());
+        main_coder.add_task_processor("app.main".to_string());
+        main_coder.add_task_processor("app.out".to_string());
+        for (proc_name, block_type) in [("app.main.source", "dsp.Source"), ("app.main.gain", "dsp.Gain"), ("app.out.sink", "dsp.Sink")] {
+            main_coder.add_stream_processor(proc_name.to_string(), block_type.to_string()).unwrap();
+        }
+        main_coder.add_connection("app.main.gain".to_string(), "app.main.gain.y".to_string(), "app.out.sink".to_string(), "app.out.sink.x".to_string(), String::new());
+        main_coder.add_connection("app.main.source".to_string(), "app.main.source.y".to_string(), "app.main.gain".to_string(), "app.main.gain.x".to_string(), "f32".to_string());
+        main_coder
+    }
+
+    #[test]
+    fn dot_output_matches_golden() {
+        assert_eq!(render_dot(&"app".to_string(), &application()), [
+            "digraph \"app\" {",
+            "    rankdir=LR;",
+            "    node [shape=box];",
+            "    subgraph \"cluster_main\" {",
+            "        label=\"main\";",
+            "        \"main.gain\" [label=\"gain\\ndsp.Gain\"];",
+            "        \"main.source\" [label=\"source\\ndsp.Source\"];",
+            "    }",
+            "    subgraph \"cluster_out\" {",
+            "        label=\"out\";",
+            "        \"out.sink\" [label=\"sink\\ndsp.Sink\"];",
+            "    }",
+            "    \"main.gain\" -> \"out.sink\" [label=\"y -> x\"];",
+            "    \"main.source\" -> \"main.gain\" [label=\"y -> x\\nf32\"];",
+            "}",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn mermaid_output_matches_golden() {
+        assert_eq!(render_mermaid(&"app".to_string(), &application()), [
+            "flowchart LR",
+            "    subgraph task_main[\"main\"]",
+            "        proc_main_2e_gain[\"gain<br/>dsp.Gain\"]",
+            "        proc_main_2e_source[\"source<br/>dsp.Source\"]",
+            "    end",
+            "    subgraph task_out[\"out\"]",
+            "        proc_out_2e_sink[\"sink<br/>dsp.Sink\"]",
+            "    end",
+            "    proc_main_2e_gain -->|\"y -#gt; x\"| proc_out_2e_sink",
+            "    proc_main_2e_source -->|\"y -#gt; x<br/>f32\"| proc_main_2e_gain",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn mermaid_ids_of_distinct_names_differ() {
+        let names = ["a_b.c", "a.b_c", "a_b_c", "a.b.c", "a_2e_b"].map(|n| mermaid_id("proc", &n.to_string()));
+        for (index, id) in names.iter().enumerate() {
+            assert!(id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'), "{}", id);
+            assert!(!names[index + 1..].contains(id), "{}", id);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::io::Write;
+use serde::{Serialize, Deserialize};
+use coder::coder::Coder;
+use coder::lib_coder::LibCoder;
+use coder::main_coder::MainCoder;
+use coder::manifest_coder::ManifestCoder;
+use coder::processor_coder::ProcessorCoder;
+
+use crate::parser::MemoryObject;
+
+const MAX_JOURNAL_ENTRIES: usize = 100;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub enum CoderState {
+    Lib(LibCoder),
+    Main(MainCoder),
+    Processor(ProcessorCoder),
+    Manifest(ManifestCoder),
+}
+
+impl CoderState {
+    pub fn capture(coder: &Box<dyn Coder>) -> Option<CoderState> {
+        if let Some(lib_coder) = coder.as_any().downcast_ref::<LibCoder>() {
+            Some(CoderState::Lib(lib_coder.clone()))
+        } else if let Some(main_coder) = coder.as_any().downcast_ref::<MainCoder>() {
+            Some(CoderState::Main(main_coder.clone()))
+        } else if let Some(processor_coder) = coder.as_any().downcast_ref::<ProcessorCoder>() {
+            Some(CoderState::Processor(processor_coder.clone()))
+        } else if let Some(manifest_coder) = coder.as_any().downcast_ref::<ManifestCoder>() {
+            Some(CoderState::Manifest(manifest_coder.clone()))
+        } else {
+            None
+        }
+    }
+
+    pub fn into_coder(self) -> Box<dyn Coder> {
+        match self {
+            CoderState::Lib(lib_coder) => Box::new(lib_coder),
+            CoderState::Main(main_coder) => Box::new(main_coder),
+            CoderState::Processor(processor_coder) => Box::new(processor_coder),
+            CoderState::Manifest(manifest_coder) => Box::new(manifest_coder),
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProjectSnapshot {
+    pub memory_map: HashMap<String, MemoryObject>,
+    pub coders: HashMap<String, CoderState>,
+}
+
+// Values a command replaced: `None` stands for memory objects and coders the command created.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ProjectDelta {
+    pub memory_map: HashMap<String, Option<MemoryObject>>,
+    pub coders: HashMap<String, Option<CoderState>>,
+}
+
+impl ProjectDelta {
+    // Values of `before` for everything that differs in `after`.
+    pub fn between(before: &ProjectSnapshot, after: &ProjectSnapshot) -> ProjectDelta {
+        ProjectDelta {
+            memory_map: changed_values(&before.memory_map, &after.memory_map),
+            coders: changed_values(&before.coders, &after.coders),
+        }
+    }
+
+    pub fn apply(self, mut snapshot: ProjectSnapshot) -> ProjectSnapshot {
+        apply_values(&mut snapshot.memory_map, self.memory_map);
+        apply_values(&mut snapshot.coders, self.coders);
+        snapshot
+    }
+}
+
+// Coders have no equality of their own, they are compared through their saved form.
+fn changed_values<T: Clone + Serialize>(before: &HashMap<String, T>, after: &HashMap<String, T>) -> HashMap<String, Option<T>> {
+    let mut changed: HashMap<String, Option<T>> = HashMap::new();
+    for (name, value) in before.iter() {
+        if after.get(name).map_or(true, |new_value| serde_json::to_value(new_value).ok() != serde_json::to_value(value).ok()) {
+            changed.insert(name.clone(), Some(value.clone()));
+        }
+    }
+    for name in after.keys() {
+        if !before.contains_key(name) {
+            changed.insert(name.clone(), None);
+        }
+    }
+    changed
+}
+
+fn apply_values<T>(values: &mut HashMap<String, T>, changes: HashMap<String, Option<T>>) {
+    for (name, value) in changes.into_iter() {
+        match value {
+            Some(value) => values.insert(name, value),
+            None => values.remove(&name),
+        };
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub command: String,
+    pub delta: ProjectDelta,
+}
+
+// One line of the journal file. The file is replayed on load, `Stacks` replaces everything before it.
+#[derive(Clone, Serialize, Deserialize)]
+enum JournalRecord {
+    Record(JournalEntry),
+    Undo(JournalEntry),
+    Redo(JournalEntry),
+    Stacks { undo: Vec<JournalEntry>, redo: Vec<JournalEntry> },
+}
+
+#[derive(Clone, Default)]
+pub struct Journal {
+    pub undo: Vec<JournalEntry>,
+    pub redo: Vec<JournalEntry>,
+    // Records not yet appended to the file, and the number of records the file holds.
+    pending: Vec<JournalRecord>,
+    written: usize,
+}
+
+impl Journal {
+    fn file_path(project_path: &String) -> String {
+        format!("{}/.project/journal.jsonl", project_path)
+    }
+
+    pub fn load(project_path: &String) -> Result<Self, String> {
+        let path = Journal::file_path(project_path);
+        let mut journal = Journal::default();
+        if !std::path::Path::new(&path).exists() {
+            return Ok(journal);
+        }
+        let json_data = std::fs::read_to_string(&path).map_err(|e| format!("Error reading journal file: {}", e))?;
+        for line in json_data.lines().filter(|line| !line.trim().is_empty()) {
+            let record = serde_json::from_str(line).map_err(|e| format!("Error deserializing journal: {}", e))?;
+            journal.apply(record);
+            journal.written += 1;
+        }
+        Ok(journal)
+    }
+
+    // Appends the records of the commands run since the last save. Once the file holds far more
+    // records than entries, it is rewritten as a single `Stacks` record.
+    pub fn save(&mut self, project_path: &String) -> Result<(), String> {
+        if self.written + self.pending.len() > 2 * MAX_JOURNAL_ENTRIES {
+            return self.rewrite(project_path);
+        }
+        let mut lines = String::new();
+        for record in self.pending.iter() {
+            lines += &serde_json::to_string(record).map_err(|e| format!("Error serializing journal: {}", e))?;
+            lines += "\n";
+        }
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(Journal::file_path(project_path))
+            .map_err(|e| format!("Error writing journal file: {}", e))?;
+        file.write_all(lines.as_bytes()).map_err(|e| format!("Error writing journal file: {}", e))?;
+        self.written += self.pending.len();
+        self.pending.clear();
+        Ok(())
+    }
+
+    // Replaces the file, for journals that were reset or restored as a whole.
+    pub fn rewrite(&mut self, project_path: &String) -> Result<(), String> {
+        let record = JournalRecord::Stacks { undo: self.undo.clone(), redo: self.redo.clone() };
+        let json_string = serde_json::to_string(&record).map_err(|e| format!("Error serializing journal: {}", e))?;
+        std::fs::write(Journal::file_path(project_path), json_string + "\n").map_err(|e| format!("Error writing journal file: {}", e))?;
+        self.written = 1;
+        self.pending.clear();
+        Ok(())
+    }
+
+    fn apply(&mut self, record: JournalRecord) {
+        match record {
+            JournalRecord::Record(entry) => {
+                self.undo.push(entry);
+                if self.undo.len() > MAX_JOURNAL_ENTRIES {
+                    self.undo.remove(0);
+                }
+                self.redo.clear();
+            },
+            JournalRecord::Undo(redo_entry) => {
+                self.undo.pop();
+                self.redo.push(redo_entry);
+            },
+            JournalRecord::Redo(undo_entry) => {
+                self.redo.pop();
+                self.undo.push(undo_entry);
+            },
+            JournalRecord::Stacks { undo, redo } => {
+                self.undo = undo;
+                self.redo = redo;
+            },
+        }
+    }
+
+    fn push(&mut self, record: JournalRecord) {
+        self.apply(record.clone());
+        self.pending.push(record);
+    }
+
+    pub fn record(&mut self, command: String, before: &ProjectSnapshot, after: &ProjectSnapshot) {
+        self.push(JournalRecord::Record(JournalEntry { command, delta: ProjectDelta::between(before, after) }));
+    }
+
+    // Drops the last undo entry once it has been restored, `redo_entry` goes back to the state before.
+    pub fn undone(&mut self, redo_entry: JournalEntry) {
+        self.push(JournalRecord::Undo(redo_entry));
+    }
+
+    pub fn redone(&mut self, undo_entry: JournalEntry) {
+        self.push(JournalRecord::Redo(undo_entry));
+    }
+}
+
+pub struct Transaction {
+    pub explicit: bool,
+    pub projects: Vec<String>,
+    pub snapshots: HashMap<String, ProjectSnapshot>,
+    pub journals: HashMap<String, Journal>,
+    // Project folders created on disk by the transaction, removed again on rollback.
+    pub folders: Vec<String>,
+}
+
+impl Transaction {
+    pub fn new(explicit: bool, projects: Vec<String>) -> Self {
+        Transaction {
+            explicit,
+            projects,
+            snapshots: HashMap::new(),
+            journals: HashMap::new(),
+            folders: Vec::new(),
+        }
+    }
+}
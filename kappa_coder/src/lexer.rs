@@ -0,0 +1,307 @@
+// Tokenizer for the command language.
+//
+// Commands are separated by `;` or by an unquoted newline. Inside a command, tokens are
+// separated by spaces or tabs. A token can be:
+//  - a bare word, where `\` escapes the next character;
+//  - a double-quoted string supporting the escapes \n \r \t \0 \\ \" \' \; and \<newline>;
+//  - a single-quoted string, taken literally;
+//  - a heredoc `<<TAG`: the token value is made of the lines following the current one,
+//    up to a line containing only TAG.
+// A `#` at the start of a token starts a comment that runs to the end of the line.
+
+struct PendingHeredoc {
+    command: usize,
+    token: usize,
+    tag: String,
+    line: usize,
+    column: usize,
+}
+
+impl PendingHeredoc {
+    // Reported at the `<<` marker, the end of input says nothing about which heredoc is open.
+    fn unterminated(&self) -> String {
+        format!("Syntax error at line {}, column {}: heredoc <<{} is never terminated.", self.line, self.column, self.tag)
+    }
+}
+
+pub struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+    commands: Vec<Vec<String>>,
+    current: Vec<String>,
+    pending_heredocs: Vec<PendingHeredoc>,
+}
+
+impl Lexer {
+    pub fn new(script: &str) -> Self {
+        Lexer {
+            chars: script.chars().collect(),
+            pos: 0,
+            line: 1,
+            column: 1,
+            commands: Vec::new(),
+            current: Vec::new(),
+            pending_heredocs: Vec::new(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.chars.get(self.pos).copied()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> String {
+        format!("Syntax error at line {}, column {}: {}", self.line, self.column, message)
+    }
+
+    fn end_command(&mut self) {
+        if !self.current.is_empty() {
+            let command = std::mem::take(&mut self.current);
+            self.commands.push(command);
+        }
+    }
+
+    pub fn tokenize(mut self) -> Result<Vec<Vec<String>>, String> {
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' => {
+                    self.advance();
+                },
+                '\n' => {
+                    self.advance();
+                    self.read_heredoc_bodies()?;
+                    self.end_command();
+                },
+                ';' => {
+                    self.advance();
+                    self.end_command();
+                },
+                '#' => {
+                    while let Some(c) = self.peek() {
+                        if c == '\n' {
+                            break;
+                        }
+                        self.advance();
+                    }
+                },
+                '<' if self.peek_at(1) == Some('<') => {
+                    self.read_heredoc_start()?;
+                },
+                _ => {
+                    let token = self.read_word()?;
+                    self.current.push(token);
+                },
+            }
+        }
+        if let Some(heredoc) = self.pending_heredocs.first() {
+            return Err(heredoc.unterminated());
+        }
+        self.end_command();
+        Ok(self.commands)
+    }
+
+    fn read_word(&mut self) -> Result<String, String> {
+        let mut token = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                ' ' | '\t' | '\r' | '\n' | ';' => break,
+                '"' => {
+                    self.advance();
+                    self.read_double_quoted(&mut token)?;
+                },
+                '\'' => {
+                    self.advance();
+                    self.read_single_quoted(&mut token)?;
+                },
+                '\\' => {
+                    self.advance();
+                    match self.advance() {
+                        Some('\n') => {},
+                        Some(escaped) => token.push(escaped),
+                        None => return Err(self.error("dangling escape at end of input.")),
+                    }
+                },
+                _ => {
+                    self.advance();
+                    token.push(c);
+                },
+            }
+        }
+        Ok(token)
+    }
+
+    fn read_double_quoted(&mut self, token: &mut String) -> Result<(), String> {
+        loop {
+            match self.advance() {
+                None => return Err(self.error("unterminated double-quoted string.")),
+                Some('"') => return Ok(()),
+                Some('\\') => {
+                    match self.advance() {
+                        Some('n') => token.push('\n'),
+                        Some('r') => token.push('\r'),
+                        Some('t') => token.push('\t'),
+                        Some('0') => token.push('\0'),
+                        Some('\\') => token.push('\\'),
+                        Some('"') => token.push('"'),
+                        Some('\'') => token.push('\''),
+                        Some(';') => token.push(';'),
+                        Some('\n') => {},
+                        Some(other) => return Err(self.error(&format!("unknown escape sequence \\{}.", other))),
+                        None => return Err(self.error("unterminated double-quoted string.")),
+                    }
+                },
+                Some(c) => token.push(c),
+            }
+        }
+    }
+
+    fn read_single_quoted(&mut self, token: &mut String) -> Result<(), String> {
+        loop {
+            match self.advance() {
+                None => return Err(self.error("unterminated single-quoted string.")),
+                Some('\'') => return Ok(()),
+                Some(c) => token.push(c),
+            }
+        }
+    }
+
+    fn read_heredoc_start(&mut self) -> Result<(), String> {
+        let (line, column) = (self.line, self.column);
+        self.advance();
+        self.advance();
+        let mut tag = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_alphanumeric() || c == '_' {
+                tag.push(c);
+                self.advance();
+            } else {
+                break;
+            }
+        }
+        if tag.is_empty() {
+            return Err(self.error("missing heredoc tag after <<."));
+        }
+        self.current.push(String::new());
+        self.pending_heredocs.push(PendingHeredoc {
+            command: self.commands.len(),
+            token: self.current.len() - 1,
+            tag,
+            line,
+            column,
+        });
+        Ok(())
+    }
+
+    fn read_line(&mut self) -> Option<String> {
+        self.peek()?;
+        let mut line = String::new();
+        while let Some(c) = self.advance() {
+            if c == '\n' {
+                break;
+            }
+            line.push(c);
+        }
+        Some(line.trim_end_matches('\r').to_string())
+    }
+
+    fn read_heredoc_bodies(&mut self) -> Result<(), String> {
+        let pending = std::mem::take(&mut self.pending_heredocs);
+        for heredoc in pending {
+            let mut body: Vec<String> = Vec::new();
+            loop {
+                match self.read_line() {
+                    Some(line) if line.trim() == heredoc.tag => break,
+                    Some(line) => body.push(line),
+                    None => return Err(heredoc.unterminated()),
+                }
+            }
+            // Tokens of the command holding the heredoc are still in `current` until the line ends.
+            let command = if heredoc.command == self.commands.len() {
+                &mut self.current
+            } else {
+                &mut self.commands[heredoc.command]
+            };
+            command[heredoc.token] = body.join("\n");
+        }
+        Ok(())
+    }
+}
+
+pub fn tokenize(script: &str) -> Result<Vec<Vec<String>>, String> {
+    Lexer::new(script).tokenize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commands(script: &str) -> Vec<Vec<String>> {
+        tokenize(script).unwrap()
+    }
+
+    #[test]
+    fn quoted_strings_keep_spaces_and_separators() {
+        assert_eq!(commands("create input \"a b;c\" 'x \\n y'"), vec![vec!["create", "input", "a b;c", "x \\n y"]]);
+        assert_eq!(commands("set \"\" pre\"fix\"'ed'"), vec![vec!["set", "", "prefixed"]]);
+    }
+
+    #[test]
+    fn escapes_are_decoded() {
+        assert_eq!(commands(r#"code "a\nb\t\"c\"\\\;\0""#), vec![vec!["code", "a\nb\t\"c\"\\;\0"]]);
+        assert_eq!(commands(r"bare\ word \;"), vec![vec!["bare word", ";"]]);
+        assert_eq!(commands("\"line \\\ncontinued\""), vec![vec!["line continued"]]);
+        assert!(tokenize(r#""\q""#).unwrap_err().contains("unknown escape sequence \\q"));
+        assert!(tokenize("word\\").unwrap_err().contains("dangling escape"));
+    }
+
+    #[test]
+    fn semicolons_and_newlines_separate_commands() {
+        assert_eq!(commands("list; show app ;; \n\n build app # all\n"), vec![vec!["list"], vec!["show", "app"], vec!["build", "app"]]);
+        assert!(commands(" ; # only a comment\n").is_empty());
+    }
+
+    #[test]
+    fn heredoc_body_is_the_following_lines() {
+        assert_eq!(commands("code app.main <<END\nfn a() {\n    \"x\";\n}\nEND\nbuild app"),
+            vec![vec!["code", "app.main", "fn a() {\n    \"x\";\n}"], vec!["build", "app"]]);
+    }
+
+    #[test]
+    fn heredocs_of_one_line_are_read_in_order() {
+        assert_eq!(commands("code <<A x <<B\na body\nA\nb body\nB\n"), vec![vec!["code", "a body", "x", "b body"]]);
+    }
+
+    #[test]
+    fn heredoc_followed_by_a_separator() {
+        assert_eq!(commands("code <<END; show app\nbody\nEND\n"), vec![vec!["code", "body"], vec!["show", "app"]]);
+    }
+
+    #[test]
+    fn unterminated_heredoc_reports_its_marker() {
+        assert_eq!(tokenize("list\ncode app <<END\nbody\n").unwrap_err(), "Syntax error at line 2, column 10: heredoc <<END is never terminated.");
+        assert_eq!(tokenize("code <<END").unwrap_err(), "Syntax error at line 1, column 6: heredoc <<END is never terminated.");
+    }
+
+    #[test]
+    fn unterminated_quotes_report_their_position() {
+        assert_eq!(tokenize("list\nshow \"app").unwrap_err(), "Syntax error at line 2, column 10: unterminated double-quoted string.");
+        assert!(tokenize("show 'app").unwrap_err().contains("unterminated single-quoted string"));
+    }
+}
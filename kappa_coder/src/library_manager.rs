@@ -1,16 +1,323 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use coder::lib_coder::split_module_dependency;
+use data_model::modules::ModuleStruct;
 use processor_engine::ffi::ModuleHandle;
 
+pub struct LibraryFile {
+    pub source_path: String,
+    pub shadow_path: String,
+    pub modified: SystemTime,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LibraryInfo {
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    pub path: String,
+    pub dependencies: Vec<String>,
+    pub provides: Vec<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct LibraryEvent {
+    pub module: String,
+    pub path: String,
+    pub action: String,
+    pub previous_version: Option<String>,
+    pub version: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DependencyIssue {
+    pub module: String,
+    pub path: String,
+    pub kind: String,
+    pub detail: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DependencyNode {
+    pub name: String,
+    pub version: String,
+    pub loaded: bool,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: Option<String>,
+    pub requirement: String,
+    pub status: String,
+}
+
+#[derive(Clone, Serialize)]
+pub struct DependencyGraph {
+    pub root: String,
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+    pub issues: Vec<DependencyIssue>,
+}
+
+// Dynamic libraries of the platform: `.so`, `.dylib` or `.dll`.
+pub fn is_library_file(path: &Path) -> bool {
+    path.extension().and_then(|e| e.to_str()) == Some(std::env::consts::DLL_EXTENSION)
+}
+
+// Modules whose dependencies are provided by `module_name`.
+fn dependents_of(module_name: &String, modules: &Vec<&ModuleStruct>) -> Vec<String> {
+    let mut dependents = modules.iter()
+        .filter(|m| m.name != *module_name)
+        .filter(|m| m.dependencies.iter().any(|dependency| {
+            resolve_dependency(dependency, modules).map_or(false, |provider| provider.name == *module_name)
+        }))
+        .map(|m| m.name.clone())
+        .collect::<Vec<String>>();
+    dependents.sort();
+    dependents
+}
+
+// A module stays loaded while other loaded modules depend on it.
+fn check_unload(module_name: &String, modules: &Vec<&ModuleStruct>) -> Result<(), String> {
+    let dependents = dependents_of(module_name, modules);
+    if !dependents.is_empty() {
+        return Err(format!("Module {} is used by {}, unload them first.", module_name, dependents.join(", ")));
+    }
+    Ok(())
+}
+
+// Library files that are new or modified since they were last seen, as `(path, modified)`.
+// Broken or unresolved files are seen too, so that they are retried only once they change again.
+fn changed_files(library_paths: &Vec<PathBuf>, seen: &Vec<(&String, &SystemTime)>) -> Vec<PathBuf> {
+    library_paths.iter()
+        .filter(|library_path| {
+            let source_path = library_path.display().to_string();
+            let modified = modified_time(library_path);
+            !seen.iter().any(|(path, time)| **path == source_path && **time == modified)
+        })
+        .cloned()
+        .collect()
+}
+
+// Candidates to register, as indices in dependency order, and the rejected ones with their issue kind and detail.
+struct LoadPlan {
+    order: Vec<usize>,
+    rejected: Vec<(usize, String, String)>,
+}
+
+// Plans the registration of `candidates` next to the `loaded` modules, whose existing source files are
+// given by `owners`.
+fn plan_load(candidates: &Vec<(&Path, &ModuleStruct)>, loaded: &Vec<&ModuleStruct>, owners: &HashMap<String, String>) -> LoadPlan {
+    let mut rejected: Vec<(usize, String, String)> = Vec::new();
+
+    // Two files exporting the same module: the highest version wins.
+    let mut sorted: Vec<usize> = (0..candidates.len()).collect();
+    sorted.sort_by(|a, b| {
+        let ((a_path, a_module), (b_path, b_module)) = (candidates[*a], candidates[*b]);
+        a_module.name.cmp(&b_module.name)
+            .then(module_version(b_module).cmp(&module_version(a_module)))
+            .then(a_path.cmp(b_path))
+    });
+    let mut unique: Vec<usize> = Vec::new();
+    for index in sorted.into_iter() {
+        let (path, module) = candidates[index];
+        let source_path = path.display().to_string();
+        if let Some(kept) = unique.iter().find(|kept| candidates[**kept].1.name == module.name) {
+            let (kept_path, kept_module) = candidates[*kept];
+            let detail = format!("Module {} is also exported by {:?} with version {}.", module.name, kept_path, module_version_string(kept_module));
+            rejected.push((index, "duplicate_module".to_string(), detail));
+        } else if let Some(owner) = owners.get(&module.name).filter(|owner| **owner != source_path) {
+            let detail = format!("Module {} is already loaded from {}.", module.name, owner);
+            rejected.push((index, "duplicate_module".to_string(), detail));
+        } else {
+            unique.push(index);
+        }
+    }
+
+    // Drop candidates with unsatisfied dependencies until the remaining set is closed.
+    loop {
+        let mut available: Vec<&ModuleStruct> = loaded.iter()
+            .filter(|m| !unique.iter().any(|index| candidates[*index].1.name == m.name))
+            .copied()
+            .collect();
+        available.extend(unique.iter().map(|index| candidates[*index].1));
+        let unsatisfied = unique.iter().enumerate().find_map(|(position, index)| {
+            candidates[*index].1.dependencies.iter()
+                .find_map(|dependency| resolve_dependency(dependency, &available).err())
+                .map(|(kind, detail)| (position, kind, detail))
+        });
+        match unsatisfied {
+            Some((position, kind, detail)) => rejected.push((unique.remove(position), kind, detail)),
+            None => break,
+        }
+    }
+
+    // Kahn's algorithm over the dependencies between candidates.
+    let modules: Vec<&ModuleStruct> = unique.iter().map(|index| candidates[*index].1).collect();
+    let mut indegree: Vec<usize> = vec![0; unique.len()];
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); unique.len()];
+    for (position, module) in modules.iter().enumerate() {
+        for dependency in module.dependencies.iter() {
+            if let Ok(provider) = resolve_dependency(dependency, &modules) {
+                let provider_position = modules.iter().position(|m| m.name == provider.name).unwrap();
+                if provider_position != position && !dependents[provider_position].contains(&position) {
+                    dependents[provider_position].push(position);
+                    indegree[position] += 1;
+                }
+            }
+        }
+    }
+    let mut ready: VecDeque<usize> = (0..unique.len()).filter(|i| indegree[*i] == 0).collect();
+    let mut order: Vec<usize> = Vec::new();
+    while let Some(position) = ready.pop_front() {
+        order.push(position);
+        for dependent in dependents[position].clone() {
+            indegree[dependent] -= 1;
+            if indegree[dependent] == 0 {
+                ready.push_back(dependent);
+            }
+        }
+    }
+    for (position, module) in modules.iter().enumerate().filter(|(position, _)| !order.contains(position)) {
+        rejected.push((unique[position], "dependency_cycle".to_string(), format!("Module {} is part of a dependency cycle.", module.name)));
+    }
+    LoadPlan {
+        order: order.into_iter().map(|position| unique[position]).collect(),
+        rejected,
+    }
+}
+
+// Dependency graph of a module against the `loaded` modules, following the providers transitively.
+fn dependency_graph_of(root: &ModuleStruct, loaded: &Vec<&ModuleStruct>, dependency_issues: &Vec<DependencyIssue>) -> DependencyGraph {
+    let mut nodes: Vec<DependencyNode> = Vec::new();
+    let mut edges: Vec<DependencyEdge> = Vec::new();
+    let mut queue: VecDeque<&ModuleStruct> = VecDeque::new();
+    queue.push_back(root);
+    while let Some(module) = queue.pop_front() {
+        if nodes.iter().any(|n| n.name == module.name) {
+            continue;
+        }
+        nodes.push(DependencyNode {
+            name: module.name.clone(),
+            version: module_version_string(module),
+            loaded: loaded.iter().any(|m| m.name == module.name),
+        });
+        for dependency in module.dependencies.iter() {
+            match resolve_dependency(dependency, &loaded) {
+                Ok(provider) => {
+                    edges.push(DependencyEdge {
+                        from: module.name.clone(),
+                        to: Some(provider.name.clone()),
+                        requirement: dependency.clone(),
+                        status: "ok".to_string(),
+                    });
+                    queue.push_back(provider);
+                },
+                Err((kind, _)) => {
+                    edges.push(DependencyEdge {
+                        from: module.name.clone(),
+                        to: None,
+                        requirement: dependency.clone(),
+                        status: kind,
+                    });
+                },
+            }
+        }
+    }
+    let issues = dependency_issues.iter()
+        .filter(|issue| nodes.iter().any(|n| n.name == issue.module))
+        .cloned()
+        .collect();
+    DependencyGraph {
+        root: root.name.clone(),
+        nodes,
+        edges,
+        issues,
+    }
+}
+
+// A library opened from disk, not yet registered in the manager.
+struct LibraryCandidate<'a> {
+    path: PathBuf,
+    shadow_path: String,
+    handle: ModuleHandle<'a>,
+}
+
 pub struct LibraryManager<'a> {
     library_handles: HashMap<String, ModuleHandle<'a>>,
+    library_files: HashMap<String, LibraryFile>,
+    failed_files: HashMap<String, SystemTime>,
+    unresolved_files: HashMap<String, SystemTime>,
+    dependency_issues: Vec<DependencyIssue>,
+    // Latest load, reload and unload events and the last watch error, reported by the `libraries` command.
+    events: VecDeque<LibraryEvent>,
+    watch_error: Option<String>,
+    libraries_path: String,
+}
+
+const MAX_LIBRARY_EVENTS: usize = 100;
+
+fn version_string(handle: &ModuleHandle) -> String {
+    module_version_string(&handle.module)
+}
+
+fn module_version_string(module: &ModuleStruct) -> String {
+    let version = &module.version;
+    format!("{}.{}.{}", version.major, version.minor, version.build)
+}
+
+fn module_version(module: &ModuleStruct) -> semver::Version {
+    let version = &module.version;
+    semver::Version::new(version.major as u64, version.minor as u64, version.build as u64)
+}
+
+fn provides_name(module: &ModuleStruct, name: &String) -> bool {
+    module.name == *name || module.provides.contains(name)
+}
+
+// Checks a dependency against the available modules: the matching provider, or the issue kind and detail.
+fn resolve_dependency<'m>(dependency: &String, modules: &Vec<&'m ModuleStruct>) -> Result<&'m ModuleStruct, (String, String)> {
+    let (name, requirement) = split_module_dependency(dependency)
+        .map_err(|e| ("invalid_dependency".to_string(), e))?;
+    let providers = modules.iter()
+        .filter(|m| provides_name(m, &name))
+        .collect::<Vec<_>>();
+    if providers.is_empty() {
+        return Err(("missing_provider".to_string(), format!("No loaded module provides {}.", name)));
+    }
+    let matching = providers.iter()
+        .find(|m| requirement.as_ref().map_or(true, |r| r.matches(&module_version(m))));
+    match matching {
+        Some(provider) => Ok(**provider),
+        None => {
+            let found = providers.iter()
+                .map(|m| format!("{} {}", m.name, module_version_string(m)))
+                .collect::<Vec<String>>();
+            Err(("version_conflict".to_string(), format!("{} is required but only {} is available.", dependency, found.join(", "))))
+        },
+    }
+}
+
+fn modified_time(path: &Path) -> SystemTime {
+    fs::metadata(path).and_then(|m| m.modified()).unwrap_or(UNIX_EPOCH)
 }
 
 impl<'a> LibraryManager<'a> {
     fn new() -> Self {
         LibraryManager {
             library_handles: HashMap::new(),
+            library_files: HashMap::new(),
+            failed_files: HashMap::new(),
+            unresolved_files: HashMap::new(),
+            dependency_issues: Vec::new(),
+            events: VecDeque::new(),
+            watch_error: None,
+            libraries_path: String::new(),
         }
     }
     pub fn get() -> &'static Mutex<LibraryManager<'a>> {
@@ -19,33 +326,450 @@ impl<'a> LibraryManager<'a> {
         })
     }
 
+    pub fn get_libraries_path(&self) -> &String {
+        &self.libraries_path
+    }
+
     pub fn load_library(&mut self, path: &str) -> Result<(), String> {
-        // Implementation for loading librariwa
-        let entries: fs::ReadDir = fs::read_dir(path).map_err(|e| e.to_string())?;
+        self.libraries_path = path.to_string();
+        let library_paths = self.scan_directory()?;
+        self.load_files(library_paths);
+        Ok(())
+    }
 
+    fn scan_directory(&self) -> Result<Vec<PathBuf>, String> {
+        let entries: fs::ReadDir = fs::read_dir(&self.libraries_path).map_err(|e| e.to_string())?;
+        let mut library_paths: Vec<PathBuf> = Vec::new();
         for entry in entries {
-            let entry = entry.map_err(|e| e.to_string())?; 
+            let entry = entry.map_err(|e| e.to_string())?;
             let path = entry.path();
+            if path.is_file() && is_library_file(&path) {
+                library_paths.push(path);
+            }
+        }
+        library_paths.sort();
+        Ok(library_paths)
+    }
 
-            if path.is_file() {
-                if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                    if ext == "so" {
-                        match ModuleHandle::new(path.display().to_string()) {
-                            Ok(handle) => {
-                                let module_name = handle.clone().module.name;
-                                self.library_handles.insert(module_name.clone(), handle);
-                                println!("Loaded module: {}", module_name);
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to load module from {:?}: {:?}", path, e);
-                            }
-                        }
-                    }
-                }
+    // The dynamic loader caches handles by path, so every load goes through a uniquely named
+    // copy of the library: the new module is opened before the old handle is dropped.
+    fn shadow_copy(&self, path: &Path) -> Result<String, String> {
+        let shadow_dir = std::env::temp_dir().join("kappa_coder_libraries");
+        fs::create_dir_all(&shadow_dir).map_err(|e| format!("Error creating shadow library folder: {}", e))?;
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("library");
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        let shadow_path = shadow_dir.join(format!("{}-{}.{}", stem, nanos, std::env::consts::DLL_EXTENSION));
+        fs::copy(path, &shadow_path).map_err(|e| format!("Error copying library {:?}: {}", path, e))?;
+        Ok(shadow_path.display().to_string())
+    }
+
+    fn open_library(&self, path: &Path) -> Result<LibraryCandidate<'a>, String> {
+        let shadow_path = self.shadow_copy(path)?;
+        match ModuleHandle::new(shadow_path.clone()) {
+            Ok(handle) => Ok(LibraryCandidate { path: path.to_path_buf(), shadow_path, handle }),
+            Err(e) => {
+                let _ = fs::remove_file(&shadow_path);
+                Err(format!("Failed to load module from {:?}: {:?}", path, e))
             }
         }
-        Ok(())
+    }
+
+    fn register(&mut self, candidate: LibraryCandidate<'a>) -> LibraryEvent {
+        let path = candidate.path;
+        let module_name = candidate.handle.module.name.clone();
+        let version = version_string(&candidate.handle);
+        let previous = self.library_handles.insert(module_name.clone(), candidate.handle);
+        let previous_version = previous.as_ref().map(|h| version_string(h));
+        drop(previous);
+        let previous_file = self.library_files.insert(module_name.clone(), LibraryFile {
+            source_path: path.display().to_string(),
+            shadow_path: candidate.shadow_path,
+            modified: modified_time(&path),
+        });
+        if let Some(previous_file) = previous_file {
+            let _ = fs::remove_file(previous_file.shadow_path);
+        }
+        self.failed_files.remove(&path.display().to_string());
+        self.unresolved_files.remove(&path.display().to_string());
+        self.note(LibraryEvent {
+            module: module_name,
+            path: path.display().to_string(),
+            action: if previous_version.is_some() { "reloaded".to_string() } else { "loaded".to_string() },
+            previous_version,
+            version: Some(version),
+        })
+    }
+
+    fn note(&mut self, event: LibraryEvent) -> LibraryEvent {
+        self.events.push_back(event.clone());
+        if self.events.len() > MAX_LIBRARY_EVENTS {
+            self.events.pop_front();
+        }
+        event
+    }
+
+    fn reject(&mut self, candidate: LibraryCandidate<'a>, kind: &str, detail: String) {
+        let _ = fs::remove_file(&candidate.shadow_path);
+        let path = candidate.path.display().to_string();
+        self.unresolved_files.insert(path.clone(), modified_time(&candidate.path));
+        self.dependency_issues.retain(|issue| issue.path != path);
+        self.dependency_issues.push(DependencyIssue {
+            module: candidate.handle.module.name.clone(),
+            path,
+            kind: kind.to_string(),
+            detail,
+        });
+    }
+
+    // Opens a set of libraries and registers them in dependency order. Libraries whose name is
+    // already taken, whose dependencies cannot be satisfied or that form a cycle are left out and
+    // reported in the dependency issues.
+    fn load_files(&mut self, paths: Vec<PathBuf>) -> Vec<LibraryEvent> {
+        let mut candidates: Vec<LibraryCandidate<'a>> = Vec::new();
+        for path in paths.iter() {
+            match self.open_library(path) {
+                Ok(candidate) => candidates.push(candidate),
+                Err(e) => {
+                    let source_path = path.display().to_string();
+                    self.failed_files.insert(source_path.clone(), modified_time(path));
+                    self.dependency_issues.retain(|issue| issue.path != source_path);
+                    self.dependency_issues.push(DependencyIssue {
+                        module: path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string(),
+                        path: source_path,
+                        kind: "load_failed".to_string(),
+                        detail: e,
+                    });
+                },
+            }
+        }
+
+        let owners: HashMap<String, String> = self.library_files.iter()
+            .filter(|(_, f)| Path::new(&f.source_path).exists())
+            .map(|(name, f)| (name.clone(), f.source_path.clone()))
+            .collect();
+        let loaded: Vec<&ModuleStruct> = self.library_handles.values().map(|h| &h.module).collect();
+        let plan = plan_load(&candidates.iter().map(|c| (c.path.as_path(), &c.handle.module)).collect(), &loaded, &owners);
+        let mut slots: Vec<Option<LibraryCandidate<'a>>> = candidates.into_iter().map(Some).collect();
+        for (index, kind, detail) in plan.rejected.into_iter() {
+            let candidate = slots[index].take().unwrap();
+            self.reject(candidate, &kind, detail);
+        }
+        let mut events: Vec<LibraryEvent> = Vec::new();
+        for index in plan.order.iter() {
+            let candidate = slots[*index].take().unwrap();
+            let path = candidate.path.display().to_string();
+            self.dependency_issues.retain(|issue| issue.path != path);
+            events.push(self.register(candidate));
+        }
+        events
+    }
+
+    pub fn load_file(&mut self, path: &Path) -> Result<LibraryEvent, String> {
+        let source_path = path.display().to_string();
+        match self.load_files(vec![path.to_path_buf()]).pop() {
+            Some(event) => Ok(event),
+            None => {
+                let issue = self.dependency_issues.iter().find(|issue| issue.path == source_path);
+                match issue {
+                    Some(issue) => Err(format!("Module {} not loaded: {}", issue.module, issue.detail)),
+                    None => Err(format!("Failed to load module from {:?}.", path)),
+                }
+            },
+        }
+    }
+
+    pub fn unload(&mut self, module_name: &String) -> Result<LibraryEvent, String> {
+        if !self.library_handles.contains_key(module_name) {
+            return Err(format!("Module {} is not loaded.", module_name));
+        }
+        let loaded: Vec<&ModuleStruct> = self.library_handles.values().map(|h| &h.module).collect();
+        check_unload(module_name, &loaded)?;
+        self.remove_library(module_name)
+    }
+
+    // Drops a loaded library without looking at its dependents.
+    fn remove_library(&mut self, module_name: &String) -> Result<LibraryEvent, String> {
+        let handle = self.library_handles.remove(module_name)
+            .ok_or_else(|| format!("Module {} is not loaded.", module_name))?;
+        let version = version_string(&handle);
+        drop(handle);
+        let mut path = String::new();
+        if let Some(library_file) = self.library_files.remove(module_name) {
+            let _ = fs::remove_file(&library_file.shadow_path);
+            path = library_file.source_path;
+        }
+        Ok(self.note(LibraryEvent {
+            module: module_name.clone(),
+            path,
+            action: "unloaded".to_string(),
+            previous_version: Some(version),
+            version: None,
+        }))
+    }
+
+    pub fn reload(&mut self, module_name: &String) -> Result<LibraryEvent, String> {
+        let source_path = self.library_files.get(module_name)
+            .map(|f| f.source_path.clone())
+            .ok_or_else(|| format!("Module {} is not loaded.", module_name))?;
+        self.load_file(Path::new(&source_path))
+    }
+
+    // Loads new or changed libraries of the dynamic library folder and unloads the removed ones.
+    pub fn refresh(&mut self) -> Result<Vec<LibraryEvent>, String> {
+        let mut events: Vec<LibraryEvent> = Vec::new();
+        if !Path::new(&self.libraries_path).is_dir() {
+            return Ok(events);
+        }
+        let library_paths = self.scan_directory()?;
+        let seen = self.library_files.values()
+            .map(|f| (&f.source_path, &f.modified))
+            .chain(self.failed_files.iter())
+            .chain(self.unresolved_files.iter())
+            .collect::<Vec<(&String, &SystemTime)>>();
+        let changed_paths = changed_files(&library_paths, &seen);
+        events.extend(self.load_files(changed_paths));
+        let removed = self.library_files.iter()
+            .filter(|(_, f)| !library_paths.iter().any(|p| p.display().to_string() == f.source_path))
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<String>>();
+        // The files are gone, so their modules are dropped even if others still depend on them.
+        for module_name in removed {
+            events.push(self.remove_library(&module_name)?);
+        }
+        self.unresolved_files.retain(|path, _| Path::new(path).exists());
+        self.dependency_issues.retain(|issue| Path::new(&issue.path).exists());
+        // New modules may satisfy libraries that were waiting for a provider.
+        if !events.is_empty() && !self.unresolved_files.is_empty() {
+            let waiting = self.unresolved_files.keys().map(PathBuf::from).collect::<Vec<PathBuf>>();
+            events.extend(self.load_files(waiting));
+        }
+        Ok(events)
+    }
+
+    // Called periodically by the server, whose clients read the outcome with the `libraries` command.
+    pub fn watch(&mut self) {
+        self.watch_error = self.refresh().err();
+    }
+
+    pub fn get_events(&self) -> Vec<LibraryEvent> {
+        self.events.iter().cloned().collect()
+    }
+
+    pub fn get_watch_error(&self) -> &Option<String> {
+        &self.watch_error
+    }
+
+    pub fn get_dependency_issues(&self) -> &Vec<DependencyIssue> {
+        &self.dependency_issues
+    }
+
+    pub fn dependency_graph(&self, root: &ModuleStruct) -> DependencyGraph {
+        let loaded: Vec<&ModuleStruct> = self.library_handles.values().map(|h| &h.module).collect();
+        dependency_graph_of(root, &loaded, &self.dependency_issues)
+    }
+
+    // The loaded module named `name` or providing it.
+    pub fn provider_of(&self, name: &String) -> Option<&ModuleStruct> {
+        self.library_handles.values()
+            .map(|handle| &handle.module)
+            .find(|module| provides_name(module, name))
+    }
+
+    pub fn list_libraries(&self) -> Vec<LibraryInfo> {
+        let mut libraries = self.library_handles.iter()
+            .map(|(name, handle)| LibraryInfo {
+                name: name.clone(),
+                description: handle.module.description.clone(),
+                version: version_string(handle),
+                path: self.library_files.get(name).map(|f| f.source_path.clone()).unwrap_or_default(),
+                dependencies: handle.module.dependencies.clone(),
+                provides: handle.module.provides.clone(),
+            })
+            .collect::<Vec<LibraryInfo>>();
+        libraries.sort_by(|a, b| a.name.cmp(&b.name));
+        libraries
     }
 }
 
-static LIBRARY_MANAGER: OnceLock<Mutex<LibraryManager>> = OnceLock::new();
\ No newline at end of file
+static LIBRARY_MANAGER: OnceLock<Mutex<LibraryManager>> = OnceLock::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data_model::modules::Version;
+
+    fn module(name: &str, dependencies: &[&str], provides: &[&str]) -> ModuleStruct {
+        ModuleStruct {
+            name: name.to_string(),
+            description: String::new(),
+            authors: String::new(),
+            release_date: String::new(),
+            version: Version { major: 1, minor: 2, build: 0 },
+            dependencies: dependencies.iter().map(|d| d.to_string()).collect(),
+            provides: provides.iter().map(|p| p.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn dependents_are_modules_resolved_to_the_provider() {
+        let dsp = module("dsp", &[], &["filters"]);
+        let app = module("app", &["filters@^1.0"], &[]);
+        let mixer = module("mixer", &["dsp"], &[]);
+        let old = module("old", &["dsp@^2.0"], &[]);
+        let io = module("io", &[], &[]);
+        let modules = vec![&dsp, &app, &mixer, &old, &io];
+        assert_eq!(dependents_of(&"dsp".to_string(), &modules), vec!["app", "mixer"]);
+        assert!(dependents_of(&"io".to_string(), &modules).is_empty());
+    }
+
+    // Plans the load of `(file, module)` candidates, returning the registered files in order and the rejected files with their issue kind.
+    fn plan(candidates: &[(&str, &ModuleStruct)], loaded: &[&ModuleStruct], owners: &[(&str, &str)]) -> (Vec<String>, Vec<(String, String)>) {
+        let paths: Vec<PathBuf> = candidates.iter().map(|(file, _)| PathBuf::from(file)).collect();
+        let candidates = paths.iter().zip(candidates.iter()).map(|(path, (_, module))| (path.as_path(), *module)).collect();
+        let owners = owners.iter().map(|(name, path)| (name.to_string(), path.to_string())).collect();
+        let plan = plan_load(&candidates, &loaded.to_vec(), &owners);
+        let file = |index: usize| paths[index].display().to_string();
+        let mut rejected = plan.rejected.iter().map(|(index, kind, _)| (file(*index), kind.clone())).collect::<Vec<(String, String)>>();
+        rejected.sort();
+        (plan.order.into_iter().map(file).collect(), rejected)
+    }
+
+    fn versioned(mut module: ModuleStruct, major: u32, minor: u32) -> ModuleStruct {
+        module.version = Version { major, minor, build: 0 };
+        module
+    }
+
+    #[test]
+    fn providers_are_loaded_before_their_dependents() {
+        let app = module("app", &["dsp"], &[]);
+        let dsp = module("dsp", &["filters@^1.0"], &[]);
+        let io = module("io", &[], &["filters"]);
+        let (order, rejected) = plan(&[("app.so", &app), ("dsp.so", &dsp), ("io.so", &io)], &[], &[]);
+        assert_eq!(order, vec!["io.so", "dsp.so", "app.so"]);
+        assert!(rejected.is_empty());
+    }
+
+    #[test]
+    fn highest_version_wins_between_files_of_one_module() {
+        let old = versioned(module("dsp", &[], &[]), 1, 0);
+        let new = versioned(module("dsp", &[], &[]), 1, 3);
+        let (order, rejected) = plan(&[("a.so", &old), ("b.so", &new)], &[], &[]);
+        assert_eq!(order, vec!["b.so"]);
+        assert_eq!(rejected, vec![("a.so".to_string(), "duplicate_module".to_string())]);
+        // A module still loaded from another file keeps its name.
+        let (order, rejected) = plan(&[("b.so", &new)], &[&old], &[("dsp", "a.so")]);
+        assert!(order.is_empty());
+        assert_eq!(rejected, vec![("b.so".to_string(), "duplicate_module".to_string())]);
+        let (order, _) = plan(&[("a.so", &new)], &[&old], &[("dsp", "a.so")]);
+        assert_eq!(order, vec!["a.so"]);
+    }
+
+    #[test]
+    fn dependency_cycles_are_rejected() {
+        let a = module("a", &["b"], &[]);
+        let b = module("b", &["a"], &[]);
+        let c = module("c", &[], &[]);
+        let (order, rejected) = plan(&[("a.so", &a), ("b.so", &b), ("c.so", &c)], &[], &[]);
+        assert_eq!(order, vec!["c.so"]);
+        assert_eq!(rejected, vec![("a.so".to_string(), "dependency_cycle".to_string()), ("b.so".to_string(), "dependency_cycle".to_string())]);
+    }
+
+    #[test]
+    fn missing_providers_reject_their_dependents_too() {
+        let app = module("app", &["filters"], &[]);
+        let mixer = module("mixer", &["app"], &[]);
+        let io = module("io", &[], &[]);
+        let (order, rejected) = plan(&[("app.so", &app), ("mixer.so", &mixer)], &[&io], &[]);
+        assert!(order.is_empty());
+        assert_eq!(rejected, vec![("app.so".to_string(), "missing_provider".to_string()), ("mixer.so".to_string(), "missing_provider".to_string())]);
+        // Loaded modules provide dependencies as well.
+        let uses_io = module("player", &["io@^1.2"], &[]);
+        assert_eq!(plan(&[("player.so", &uses_io)], &[&io], &[]).0, vec!["player.so"]);
+    }
+
+    #[test]
+    fn version_conflicts_are_rejected() {
+        let app = module("app", &["dsp@^2.0"], &[]);
+        let dsp = module("dsp", &[], &[]);
+        let (order, rejected) = plan(&[("app.so", &app), ("dsp.so", &dsp)], &[], &[]);
+        assert_eq!(order, vec!["dsp.so"]);
+        assert_eq!(rejected, vec![("app.so".to_string(), "version_conflict".to_string())]);
+    }
+
+    #[test]
+    fn dependency_graph_follows_loaded_providers() {
+        let app = module("app", &["filters@^1.0", "io", "dsp@^2.0"], &[]);
+        let dsp = module("dsp", &["io"], &["filters"]);
+        let graph = dependency_graph_of(&app, &vec![&dsp], &Vec::new());
+        let nodes = graph.nodes.iter().map(|n| (n.name.as_str(), n.loaded)).collect::<Vec<(&str, bool)>>();
+        assert_eq!(nodes, vec![("app", false), ("dsp", true)]);
+        let edges = graph.edges.iter().map(|e| (e.from.as_str(), e.to.as_deref(), e.status.as_str())).collect::<Vec<_>>();
+        assert_eq!(edges, vec![
+            ("app", Some("dsp"), "ok"),
+            ("app", None, "missing_provider"),
+            ("app", None, "version_conflict"),
+            ("dsp", None, "missing_provider"),
+        ]);
+    }
+
+    #[test]
+    fn scan_finds_libraries_of_the_platform() {
+        let folder = std::env::temp_dir().join(format!("kappa_scan_{}", std::process::id()));
+        std::fs::create_dir_all(folder.join(format!("nested.{}", std::env::consts::DLL_EXTENSION))).unwrap();
+        let library = folder.join(format!("libdsp.{}", std::env::consts::DLL_EXTENSION));
+        for file in [library.clone(), folder.join("libdsp.rlib"), folder.join("libdsp.d")] {
+            std::fs::write(file, "").unwrap();
+        }
+        let mut manager = LibraryManager::new();
+        manager.libraries_path = folder.display().to_string();
+        assert_eq!(manager.scan_directory().unwrap(), vec![library.clone()]);
+        let shadow_path = manager.shadow_copy(&library).unwrap();
+        assert!(is_library_file(Path::new(&shadow_path)));
+        let _ = std::fs::remove_file(shadow_path);
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn unload_is_refused_while_other_modules_depend_on_it() {
+        let dsp = module("dsp", &[], &["filters"]);
+        let app = module("app", &["filters@^1.0"], &[]);
+        let mixer = module("mixer", &["dsp"], &[]);
+        let modules = vec![&dsp, &app, &mixer];
+        assert_eq!(check_unload(&"dsp".to_string(), &modules).unwrap_err(), "Module dsp is used by app, mixer, unload them first.");
+        assert!(check_unload(&"app".to_string(), &modules).is_ok());
+        assert_eq!(LibraryManager::new().unload(&"dsp".to_string()).err().as_deref(), Some("Module dsp is not loaded."));
+    }
+
+    #[test]
+    fn modified_libraries_are_reloaded() {
+        let folder = std::env::temp_dir().join(format!("kappa_refresh_{}", std::process::id()));
+        std::fs::create_dir_all(&folder).unwrap();
+        let loaded = folder.join(format!("libdsp.{}", std::env::consts::DLL_EXTENSION));
+        let broken = folder.join(format!("libbroken.{}", std::env::consts::DLL_EXTENSION));
+        let added = folder.join(format!("libnew.{}", std::env::consts::DLL_EXTENSION));
+        for file in [&loaded, &broken, &added] {
+            std::fs::write(file, "").unwrap();
+        }
+        let (loaded_path, broken_path) = (loaded.display().to_string(), broken.display().to_string());
+        let (loaded_time, broken_time) = (modified_time(&loaded), modified_time(&broken));
+        let seen = vec![(&loaded_path, &loaded_time), (&broken_path, &broken_time)];
+        let library_paths = vec![loaded.clone(), broken.clone(), added.clone()];
+        assert_eq!(changed_files(&library_paths, &seen), vec![added.clone()]);
+
+        let later = loaded_time + std::time::Duration::from_secs(10);
+        for file in [&loaded, &broken] {
+            std::fs::File::options().write(true).open(file).unwrap().set_modified(later).unwrap();
+        }
+        assert_eq!(changed_files(&library_paths, &seen), library_paths);
+        let _ = std::fs::remove_dir_all(&folder);
+    }
+
+    #[test]
+    fn refresh_without_a_library_folder_reports_nothing() {
+        let mut manager = LibraryManager::new();
+        manager.libraries_path = std::env::temp_dir().join(format!("kappa_no_libraries_{}", std::process::id())).display().to_string();
+        manager.watch();
+        assert!(manager.get_watch_error().is_none());
+        assert!(manager.get_events().is_empty());
+    }
+}
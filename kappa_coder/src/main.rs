@@ -2,28 +2,57 @@ pub mod library_manager;
 pub mod server;
 pub mod parser;
 pub mod cargo_interface;
+pub mod protocol;
+pub mod lexer;
+pub mod history;
+pub mod workspace;
+pub mod project_io;
+pub mod validation;
+pub mod pipeline;
+pub mod graph;
 
 use std::env;
 
 use crate::server::Server;
+use crate::workspace::Workspace;
 
 
 fn print_usage() {
-    println!("Usage: kappa_coder [help|[port=port_number] [addr=server_address]]");
+    println!("Usage: kappa_coder [help|[port=port_number] [addr=server_address] [dynamic_lib=path] [kappa_lib=path] [workspace=path]]");
 }
 
 fn main() {
     let args: Vec<String> = env::args().collect();
     let application_path = env::current_exe().unwrap();
-    let mut server_port: u16 = 8080;
-    let mut server_addr: String = "0.0.0.0".to_string();
-    let mut dynamic_libraries_path: String = format!("{}/libraries", application_path.parent().unwrap().to_str().unwrap());
-    let mut kappa_library_path: String = format!("{}/kappa_library", application_path.parent().unwrap().to_str().unwrap());
+    let mut workspace_path: String = format!("{}/workspace", application_path.parent().unwrap().to_str().unwrap());
+    for arg in args.iter().skip(1) {
+        if let Some(path) = arg.strip_prefix("workspace=") {
+            workspace_path = path.to_string();
+        }
+    }
+    // Settings saved in the workspace replace the defaults, command line arguments replace both
+    // for this run only. A workspace file that cannot be read is not overwritten: the server stops.
+    let settings = match Workspace::load(&workspace_path) {
+        Ok(workspace) => workspace.settings,
+        Err(e) => {
+            eprintln!("Failed to open workspace {}: {}", workspace_path, e);
+            return;
+        }
+    };
+    let mut server_port: u16 = settings.port.unwrap_or(8080);
+    let mut server_addr: String = settings.address.unwrap_or("0.0.0.0".to_string());
+    let mut dynamic_libraries_path: String = settings.dynamic_libraries
+        .unwrap_or(format!("{}/libraries", application_path.parent().unwrap().to_str().unwrap()));
+    let mut kappa_library_path: String = settings.kappa_library
+        .unwrap_or(format!("{}/kappa_library", application_path.parent().unwrap().to_str().unwrap()));
     for arg in args.into_iter().skip(1) {
         if arg == "help" {
             print_usage();
             return;
         }
+        if arg.starts_with("workspace=") {
+            continue;
+        }
         if arg.contains("port") {
             arg.split('=').for_each(|part| {
                 if let Ok(port) = part.parse::<u16>() {
@@ -51,7 +80,8 @@ fn main() {
         server_addr, 
         server_port, 
         dynamic_libraries_path,
-        kappa_library_path);
+        kappa_library_path,
+        workspace_path);
     match join_handle {
         Ok(handle) => handle.join().unwrap(),
         Err(e) => {
@@ -2,12 +2,24 @@ use std::collections::HashMap;
 use std::sync::{Mutex, OnceLock};
 use std::path::Path;
 use serde::{Serialize, Deserialize};
+use serde_json::{Value, json};
 use coder::lib_coder::LibCoder;
 use coder::main_coder::{MainCoderParts, MainCoder};
-use coder::processor_coder::{ModCoderParts, ProcessorCoder};
-use coder::coder::{Coder, to_snake_case};
+use coder::manifest_coder::{DependencySpec, ManifestCoder};
+use coder::processor_coder::{ModCoderParts, ProcessorCoder, typed_literal};
+use coder::coder::{Coder, rename_prefix, to_snake_case};
+use coder::schema;
 
-use crate::cargo_interface::CargoInterface;
+use crate::cargo_interface::{BuildDiagnostic, CargoInterface};
+use crate::graph::{GRAPH_FORMATS, render_dot, render_mermaid};
+use crate::history::{CoderState, Journal, JournalEntry, ProjectDelta, ProjectSnapshot, Transaction};
+use crate::lexer::tokenize;
+use crate::library_manager::{LibraryManager, is_library_file};
+use crate::pipeline::Pipeline;
+use crate::project_io::{ProjectInfo, archive_folder, export_archive, extract_archive, is_archive};
+use crate::protocol::ErrorCode;
+use crate::validation::{ValidationIssue, validate_application, validate_block};
+use crate::workspace::Workspace;
 #[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum ObjectCategory {
     Crate,
@@ -71,15 +83,65 @@ pub struct MemoryObject {
     pub object_limits: String,
 }
 
-type ParserFunctionReturn = Result<(), String>;
+// Everything a `delete` removes. A dry run reports it without applying it.
+#[derive(Default, Serialize)]
+struct DeletionPlan {
+    objects: Vec<String>,
+    files: Vec<String>,
+    modules: Vec<String>,
+    processors: Vec<String>,
+    connections: Vec<String>,
+    settings: Vec<String>,
+    dependencies: Vec<String>,
+    #[serde(skip)]
+    applications: Vec<(String, MainCoder)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ParserError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub payload: Value,
+}
+
+impl ParserError {
+    pub fn new(code: ErrorCode, message: String) -> Self {
+        ParserError {
+            code,
+            message,
+            payload: Value::Null,
+        }
+    }
+}
+
+impl From<String> for ParserError {
+    fn from(message: String) -> Self {
+        ParserError::new(ErrorCode::CommandFailed, message)
+    }
+}
+
+impl std::fmt::Display for ParserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+pub type ParserFunctionReturn = Result<Value, ParserError>;
 type ParserFunction = fn(&mut Parser, &Vec<String>) -> ParserFunctionReturn;
 
+const JOURNALED_COMMANDS: [&str; 12] = ["create", "set", "unset", "connect", "disconnect", "code", "delete", "rename", "move", "manifest", "module", "apply"];
+const HISTORY_COMMANDS: [&str; 2] = ["undo", "redo"];
+
 
 pub struct Parser {
     commands_fn: HashMap<String, ParserFunction>,
     create_types_fn: HashMap<String, ParserFunction>,
     projects_map: HashMap<String, HashMap<String, MemoryObject>>,
     coder_map: HashMap<String, Box<dyn Coder>>,
+    journals: HashMap<String, Journal>,
+    transaction: Option<Transaction>,
+    workspace_dir: String,
+    workspace: Workspace,
     library_path: String,
     cargo_if: CargoInterface,
 }
@@ -89,11 +151,39 @@ impl Parser {
         let mut commands_fn: HashMap<String, ParserFunction> = HashMap::new();
         commands_fn.insert("create".to_string(), Parser::parse_create);
         commands_fn.insert("connect".to_string(), Parser::parse_connect);
+        commands_fn.insert("disconnect".to_string(), Parser::parse_disconnect);
+        commands_fn.insert("connections".to_string(), Parser::parse_connections);
+        commands_fn.insert("graph".to_string(), Parser::parse_graph);
         commands_fn.insert("set".to_string(), Parser::parse_set);
+        commands_fn.insert("unset".to_string(), Parser::parse_unset);
+        commands_fn.insert("apply".to_string(), Parser::parse_apply);
+        commands_fn.insert("dump".to_string(), Parser::parse_dump);
         commands_fn.insert("delete".to_string(), Parser::parse_delete);
+        commands_fn.insert("rename".to_string(), Parser::parse_rename);
+        commands_fn.insert("move".to_string(), Parser::parse_move);
         commands_fn.insert("code".to_string(), Parser::parse_code);
         commands_fn.insert("build".to_string(), Parser::parse_build);
+        commands_fn.insert("check".to_string(), Parser::parse_check);
+        commands_fn.insert("validate".to_string(), Parser::parse_validate);
+        commands_fn.insert("test".to_string(), Parser::parse_test);
+        commands_fn.insert("install".to_string(), Parser::parse_install);
         commands_fn.insert("import".to_string(), Parser::parse_import);
+        commands_fn.insert("export".to_string(), Parser::parse_export);
+        commands_fn.insert("list".to_string(), Parser::parse_list);
+        commands_fn.insert("show".to_string(), Parser::parse_show);
+        commands_fn.insert("tree".to_string(), Parser::parse_tree);
+        commands_fn.insert("undo".to_string(), Parser::parse_undo);
+        commands_fn.insert("redo".to_string(), Parser::parse_redo);
+        commands_fn.insert("libraries".to_string(), Parser::parse_libraries);
+        commands_fn.insert("reload".to_string(), Parser::parse_reload);
+        commands_fn.insert("unload".to_string(), Parser::parse_unload);
+        commands_fn.insert("deps".to_string(), Parser::parse_deps);
+        commands_fn.insert("workspace".to_string(), Parser::parse_workspace);
+        commands_fn.insert("begin".to_string(), Parser::parse_begin);
+        commands_fn.insert("commit".to_string(), Parser::parse_commit);
+        commands_fn.insert("rollback".to_string(), Parser::parse_rollback);
+        commands_fn.insert("manifest".to_string(), Parser::parse_manifest);
+        commands_fn.insert("module".to_string(), Parser::parse_module);
 
         let mut create_types_fn: HashMap<String, ParserFunction> = HashMap::new();
         create_types_fn.insert("crate".to_string(), Parser::create_crate);
@@ -118,6 +208,10 @@ impl Parser {
             create_types_fn,
             projects_map: HashMap::new(),
             coder_map: HashMap::new(),
+            journals: HashMap::new(),
+            transaction: None,
+            workspace_dir: "".to_string(),
+            workspace: Workspace::default(),
             library_path: "".to_string(),
             cargo_if: CargoInterface {
                 cargo_path: cargo_path,
@@ -136,34 +230,245 @@ impl Parser {
         self.cargo_if.library_path = canonical_path;
         Ok(())
     }
+    // Loads the workspace file, records the server settings in it and imports every listed project.
+    // Settings of the workspace file are only read: overrides given on the command line are not stored.
+    pub fn open_workspace(&mut self, workspace_dir: String) -> Result<Value, String> {
+        let workspace = Workspace::load(&workspace_dir)?;
+        workspace.save(&workspace_dir)?;
+        self.workspace_dir = workspace_dir;
+        self.workspace = workspace;
+        let mut restored: Vec<String> = Vec::new();
+        let mut failed: Vec<Value> = Vec::new();
+        for project_path in self.workspace.projects.clone().iter() {
+            let known_projects = self.projects_map.keys().cloned().collect::<Vec<String>>();
+            match self.parse_import(&vec!["import".to_string(), project_path.clone()]) {
+                Ok(_) => {
+                    restored.extend(self.projects_map.keys().filter(|k| !known_projects.contains(k)).cloned());
+                },
+                Err(e) => failed.push(json!({ "path": project_path, "error": e.message })),
+            }
+        }
+        Ok(json!({ "restored": restored, "failed": failed }))
+    }
+    fn register_project(&mut self, project_path: &String) -> Result<(), String> {
+        if !self.workspace_dir.is_empty() && self.workspace.add_project(project_path) {
+            self.workspace.save(&self.workspace_dir)?;
+        }
+        Ok(())
+    }
+    fn unregister_project(&mut self, project_path: &String) -> Result<(), String> {
+        if !self.workspace_dir.is_empty() && self.workspace.remove_project(project_path) {
+            self.workspace.save(&self.workspace_dir)?;
+        }
+        Ok(())
+    }
     pub fn get() -> &'static Mutex<Parser> {
         PARSER.get_or_init(|| Mutex::new(Parser::new()))
     }
     fn check_var(&self, var_name: &String, expected_type: &String) -> ParserFunctionReturn {
         let split_name = var_name.split(".").collect::<Vec<&str>>();
-        let object_map = self.projects_map.get(&split_name[0].to_string()).ok_or_else(|| format!("Project {} not found.", split_name[0]))?;
+        let object_map = self.projects_map.get(&split_name[0].to_string())
+            .ok_or_else(|| ParserError::new(ErrorCode::NotFound, format!("Project {} not found.", split_name[0])))?;
         if object_map.contains_key(var_name) {
             let memory_object = object_map.get(var_name).unwrap();
             if memory_object.object_category != (&expected_type.clone()).into() {
-                return Err(format!("Type mismatch for variable {}: expected {}, found {}.", var_name, expected_type, <ObjectCategory as Into<String>>::into(memory_object.object_category)));
+                return Err(ParserError::new(ErrorCode::TypeMismatch, format!("Type mismatch for variable {}: expected {}, found {}.", var_name, expected_type, <ObjectCategory as Into<String>>::into(memory_object.object_category))));
             }
-            Ok(())
+            Ok(Value::Null)
         } else {
-            Err(format!("Object {} not found.", var_name))
+            Err(ParserError::new(ErrorCode::NotFound, format!("Object {} not found.", var_name)))
         }
     }
     fn insert_in_memory_map(&mut self, project_name: String, object_name: String, object: MemoryObject) -> ParserFunctionReturn {
         let object_map = self.projects_map.get_mut(&project_name).unwrap();
         if object_map.contains_key(&object_name) {
-            return Err(format!("Object {} already exists.", object_name));
+            return Err(ParserError::new(ErrorCode::AlreadyExists, format!("Object {} already exists.", object_name)));
         }
         object_map.insert(object_name.clone(), object);
-        let json_string = serde_json::to_string(&object_map).map_err(|e| format!("Error serializing object map: {}", e))?;
-        let coder = self.coder_map.get(&project_name).ok_or_else(|| format!("Coder for project {} not found.", project_name))?;
-        std::fs::write(format!("{}/.project/memory_map.json", coder.get_path()), json_string).map_err(|e| format!("Error writing object map file: {}", e))?;
+        self.save_memory_map(&project_name)?;
+        Ok(Value::Null)
+    }
+    fn save_memory_map(&self, project_name: &String) -> Result<(), String> {
+        let object_map = self.projects_map.get(project_name).ok_or_else(|| format!("Project {} not found.", project_name))?;
+        let project_path = self.project_path(project_name)?;
+        schema::write_versioned(&format!("{}/.project/memory_map.json", project_path), schema::MEMORY_MAP, object_map)
+    }
+    fn project_path(&self, project_name: &String) -> Result<String, String> {
+        let coder = self.coder_map.get(project_name).ok_or_else(|| format!("Coder for project {} not found.", project_name))?;
+        Ok(coder.get_path())
+    }
+    fn project_coder_names(&self, project_name: &String) -> Vec<String> {
+        let project_prefix = format!("{}.", project_name);
+        self.coder_map.keys()
+            .filter(|k| *k == project_name || k.starts_with(&project_prefix))
+            .cloned()
+            .collect()
+    }
+    fn take_snapshot(&self, project_name: &String) -> Result<ProjectSnapshot, String> {
+        let memory_map = self.projects_map.get(project_name).ok_or_else(|| format!("Project {} not found.", project_name))?.clone();
+        let mut coders: HashMap<String, CoderState> = HashMap::new();
+        for coder_name in self.project_coder_names(project_name) {
+            let state = CoderState::capture(&self.coder_map[&coder_name])
+                .ok_or_else(|| format!("Coder for {} cannot be captured.", coder_name))?;
+            coders.insert(coder_name, state);
+        }
+        Ok(ProjectSnapshot { memory_map, coders })
+    }
+    fn restore_snapshot(&mut self, project_name: &String, snapshot: ProjectSnapshot) -> Result<(), String> {
+        for coder_name in self.project_coder_names(project_name) {
+            if snapshot.coders.contains_key(&coder_name) {
+                continue;
+            }
+            // Coders created after the snapshot: drop them together with their generated files.
+            if let Some(coder) = self.coder_map.remove(&coder_name) {
+                let _ = std::fs::remove_file(coder.get_file_path());
+                let _ = std::fs::remove_file(coder.get_save_path());
+            }
+        }
+        for (coder_name, state) in snapshot.coders.into_iter() {
+            let mut coder = state.into_coder();
+            coder.generate()?;
+            self.coder_map.insert(coder_name, coder);
+        }
+        self.projects_map.insert(project_name.clone(), snapshot.memory_map);
+        self.save_memory_map(project_name)?;
         Ok(())
     }
-
+    fn journal_mut(&mut self, project_name: &String) -> Result<&mut Journal, String> {
+        if !self.journals.contains_key(project_name) {
+            let journal = Journal::load(&self.project_path(project_name)?)?;
+            self.journals.insert(project_name.clone(), journal);
+        }
+        Ok(self.journals.get_mut(project_name).unwrap())
+    }
+    fn save_journal(&mut self, project_name: &String) -> Result<(), String> {
+        let project_path = self.project_path(project_name)?;
+        if let Some(journal) = self.journals.get_mut(project_name) {
+            journal.save(&project_path)?;
+        }
+        Ok(())
+    }
+    // For journals replaced as a whole, which the records appended to the file no longer describe.
+    fn rewrite_journal(&mut self, project_name: &String, mut journal: Journal) -> Result<(), String> {
+        journal.rewrite(&self.project_path(project_name)?)?;
+        self.journals.insert(project_name.clone(), journal);
+        Ok(())
+    }
+    // Picks up the protected regions edited in the generated files, so that regenerating keeps them.
+    fn sync_project_sources(&mut self, project_name: &String) -> Result<Vec<String>, String> {
+        let mut updated: Vec<String> = Vec::new();
+        for coder_name in self.project_coder_names(project_name) {
+            let coder = self.coder_map.get_mut(&coder_name).unwrap();
+            for section in coder.sync_from_file()?.iter() {
+                updated.push(format!("{}: {}", coder.get_file_path(), section));
+            }
+        }
+        Ok(updated)
+    }
+    // Existing project whose model a command edits, if any.
+    fn command_project(&self, tokens: &Vec<String>) -> Option<String> {
+        let key_command = tokens.get(0)?;
+        if !JOURNALED_COMMANDS.contains(&key_command.as_str()) && !HISTORY_COMMANDS.contains(&key_command.as_str()) {
+            return None;
+        }
+        let object_name = match key_command.as_str() {
+            "create" | "set" | "unset" => tokens.get(2)?.clone(),
+            // The application of a pipeline file is named inside the file.
+            "apply" => Pipeline::load(tokens.get(1)?).ok()?.application,
+            _ => tokens.get(1)?.clone(),
+        };
+        let project_name = object_name.split(".").next()?.to_string();
+        if !self.projects_map.contains_key(&project_name) {
+            return None;
+        }
+        Some(project_name)
+    }
+    fn is_project_deletion(tokens: &Vec<String>) -> bool {
+        tokens.get(0).map(|c| c.as_str()) == Some("delete")
+            && tokens.get(1).map_or(false, |name| !name.contains('.'))
+            && !Parser::is_dry_run(tokens)
+    }
+    fn is_dry_run(tokens: &Vec<String>) -> bool {
+        tokens.get(0).map(|c| c.as_str()) == Some("delete") && tokens.get(2).map(|t| t.as_str()) == Some("dry_run")
+    }
+    // `manifest <project>` and `module <crate>` without an action only show the model.
+    fn is_model_query(tokens: &Vec<String>) -> bool {
+        matches!(tokens.get(0).map(|c| c.as_str()), Some("manifest") | Some("module"))
+            && tokens.get(2).map_or(true, |action| action == "show")
+    }
+    fn journaled_project(&self, tokens: &Vec<String>) -> Option<String> {
+        let key_command = tokens.get(0)?;
+        if !JOURNALED_COMMANDS.contains(&key_command.as_str()) || Parser::is_project_deletion(tokens) || Parser::is_model_query(tokens) || Parser::is_dry_run(tokens) {
+            return None;
+        }
+        self.command_project(tokens)
+    }
+    // Saves the state of a project the first time the active transaction touches it.
+    fn track_project(&mut self, project_name: &String) -> Result<(), String> {
+        let already_tracked = match &self.transaction {
+            // Projects created inside the transaction are simply dropped on rollback.
+            Some(transaction) => transaction.snapshots.contains_key(project_name) || !transaction.projects.contains(project_name),
+            None => return Ok(()),
+        };
+        if already_tracked {
+            return Ok(());
+        }
+        let snapshot = self.take_snapshot(project_name)?;
+        let journal = self.journal_mut(project_name)?.clone();
+        let transaction = self.transaction.as_mut().unwrap();
+        transaction.snapshots.insert(project_name.clone(), snapshot);
+        transaction.journals.insert(project_name.clone(), journal);
+        Ok(())
+    }
+    // Called before a project folder is created, so that a failed transaction does not leave it behind.
+    fn track_new_folder(&mut self, folder: &String) {
+        if let Some(transaction) = self.transaction.as_mut() {
+            if !Path::new(folder).exists() {
+                transaction.folders.push(folder.clone());
+            }
+        }
+    }
+    fn rollback_transaction(&mut self) -> Result<Value, String> {
+        let transaction = match self.transaction.take() {
+            Some(transaction) => transaction,
+            None => return Ok(Value::Null),
+        };
+        let created_projects = self.projects_map.keys()
+            .filter(|k| !transaction.projects.contains(k))
+            .cloned()
+            .collect::<Vec<String>>();
+        for project_name in created_projects.iter() {
+            if let Ok(project_path) = self.project_path(project_name) {
+                self.unregister_project(&project_path)?;
+            }
+            for coder_name in self.project_coder_names(project_name) {
+                self.coder_map.remove(&coder_name);
+            }
+            self.projects_map.remove(project_name);
+            self.journals.remove(project_name);
+        }
+        let mut restored: Vec<String> = Vec::new();
+        for (project_name, snapshot) in transaction.snapshots.into_iter() {
+            self.restore_snapshot(&project_name, snapshot)?;
+            restored.push(project_name);
+        }
+        for (project_name, journal) in transaction.journals.into_iter() {
+            self.rewrite_journal(&project_name, journal)?;
+        }
+        // Folders are removed last, once no coder refers to them anymore.
+        for folder in transaction.folders.iter() {
+            match std::fs::remove_dir_all(folder) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(format!("Error removing {}: {}", folder, e)),
+                _ => {},
+            }
+        }
+        restored.sort();
+        Ok(json!({
+            "restored": restored,
+            "removed": created_projects,
+            "removed_folders": transaction.folders,
+        }))
+    }
     fn get_coder<T>(&mut self, coder_name: String) -> Result<&mut T, String>
     where
         T: Coder + 'static,
@@ -179,26 +484,65 @@ impl Parser {
             None => Err(format!("Coder for {} not found.", coder_name)),
         }
     }
+    fn get_coder_ref<T>(&self, coder_name: &String) -> Result<&T, String>
+    where
+        T: Coder + 'static,
+    {
+        match self.coder_map.get(coder_name) {
+            Some(some_coder) => {
+                if let Some(coder_ref) = some_coder.as_any().downcast_ref::<T>() {
+                    Ok(coder_ref)
+                } else {
+                    Err(format!("Coder for {} is not of the expected type.", coder_name))
+                }
+            },
+            None => Err(format!("Coder for {} not found.", coder_name)),
+        }
+    }
+    fn get_object(&self, object_name: &String) -> Result<&MemoryObject, ParserError> {
+        let split_name = object_name.split(".").collect::<Vec<&str>>();
+        let object_map = self.projects_map.get(&split_name[0].to_string())
+            .ok_or_else(|| ParserError::new(ErrorCode::NotFound, format!("Project {} not found.", split_name[0])))?;
+        object_map.get(object_name)
+            .ok_or_else(|| ParserError::new(ErrorCode::NotFound, format!("Object {} not found.", object_name)))
+    }
+    fn manifest_coder_name(project_name: &String) -> String {
+        format!("{}.Cargo.toml", project_name)
+    }
+    fn add_manifest_coder(&mut self, project_name: &String, project_path: &String, crate_types: Vec<String>) -> Result<(), String> {
+        let mut manifest_coder = ManifestCoder::from_manifest(project_path.clone())?;
+        if !crate_types.is_empty() {
+            manifest_coder.set_crate_types(crate_types)?;
+        }
+        manifest_coder.generate()?;
+        self.coder_map.insert(Parser::manifest_coder_name(project_name), Box::new(manifest_coder));
+        Ok(())
+    }
     fn create_crate(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let crate_name = tokens.get(2).ok_or_else(|| "Missing crate name".to_string())?;
         if self.projects_map.contains_key(crate_name) {
-            return Err(format!("Crate {} already exists.", crate_name));
+            return Err(ParserError::new(ErrorCode::AlreadyExists, format!("Crate {} already exists.", crate_name)));
         }
         if tokens.get(3) != Some(&"path".to_string()) {
-            return Err(format!("Expected path keyword."));
+            return Err(format!("Expected path keyword.").into());
         }
         let crate_folder = tokens.get(4).ok_or_else(|| "Missing crate path".to_string())?;
         if tokens.get(5) != Some(&"metadata".to_string()) {
-            return Err(format!("Expected metadata keyword."));
+            return Err(format!("Expected metadata keyword.").into());
         }
         let metadata = tokens.get(6).ok_or_else(|| "Missing metadata value".to_string())?;
         let crate_path = format!("{}/{}", crate_folder, crate_name);
+        // The metadata is checked before cargo creates the crate folder.
+        let mut lib_coder = LibCoder::new(crate_path.clone());
+        lib_coder.set_module_description(metadata.clone()).map_err(|e| ParserError::new(ErrorCode::InvalidArguments, e))?;
+        self.track_new_folder(&crate_path);
         self.cargo_if.cargo_new_library(crate_path.to_string())?;
         self.cargo_if.cargo_add_commands(crate_path.to_string())?; 
         self.projects_map.insert(crate_name.clone(), HashMap::new());
-        let mut lib_coder = LibCoder::new(crate_path.clone());
         lib_coder.generate()?;
         self.coder_map.insert(crate_name.clone(), Box::new(lib_coder));
+        // The crate is loaded through ModuleHandle as a dynamic library and linked by applications as a Rust library.
+        self.add_manifest_coder(crate_name, &crate_path, vec!["cdylib".to_string(), "rlib".to_string()])?;
         let memory_object = MemoryObject {
             parent: "".to_string(),
             object_category: ObjectCategory::Crate,
@@ -207,13 +551,15 @@ impl Parser {
             object_limits: "".to_string(),
         };
         self.insert_in_memory_map(crate_name.clone(), crate_name.clone(), memory_object)?;
-        Ok(())
+        self.save_project_info(crate_name)?;
+        self.register_project(&crate_path)?;
+        Ok(Value::Null)
     }
     fn create_stream_proc_block(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let block_name = tokens.get(2).ok_or_else(|| "Missing stream processor block name".to_string())?;
         let split_name = block_name.split(".").collect::<Vec<&str>>();
         if split_name.len() != 2 {
-            return Err(format!("Stream processor block name must be in the format <crate_name>.<block_name>."));
+            return Err(format!("Stream processor block name must be in the format <crate_name>.<block_name>.").into());
         }
         self.check_var(&split_name[0].to_string(), &"crate".to_string())?;
         
@@ -235,19 +581,19 @@ impl Parser {
         lib_coder.add_module(split_name[1].to_string());
         lib_coder.generate()?;
         self.coder_map.insert(split_name[0].to_string(), Box::new(lib_coder));
-        Ok(())
+        Ok(Value::Null)
     }
     fn create_typed(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let object_category = tokens.get(1).ok_or_else(|| "Missing object type".to_string())?;
         let object_name = tokens.get(2).ok_or_else(|| "Missing object name".to_string())?;
         let split_name = object_name.split(".").collect::<Vec<&str>>();
         if split_name.len() != 3 {
-            return Err(format!("Settable name must be in the format <>.<>.<>."));
+            return Err(format!("Settable name must be in the format <>.<>.<>.").into());
         }
         let parent_block = format!("{}.{}", split_name[0], split_name[1]);
         self.check_var(&parent_block.clone(), &"stream_proc_block".to_string())?;
         if tokens.get(3) != Some(&"type".to_string()) {
-            return Err(format!("Expected type keyword."));
+            return Err(format!("Expected type keyword.").into());
         }
         let object_type = tokens.get(4).ok_or_else(|| format!("Missing type"))?;
         let memory_object = MemoryObject {
@@ -262,29 +608,29 @@ impl Parser {
         coder.add_typed(&object_category.clone(), &split_name[2].to_string(), &object_type.clone());
         coder.generate()?;
         self.coder_map.insert(parent_block.clone(), Box::new(coder));
-        Ok(())
+        Ok(Value::Null)
     }
     fn create_settable(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let object_category = tokens.get(1).ok_or_else(|| "Missing object type".to_string())?;
         let object_name = tokens.get(2).ok_or_else(|| "Missing crate name".to_string())?;
         let split_name = object_name.split(".").collect::<Vec<&str>>();
         if split_name.len() != 3 {
-            return Err(format!("Input name must be in the format <>.<>.<>."));
+            return Err(format!("Input name must be in the format <>.<>.<>.").into());
         }
         let parent_block = format!("{}.{}", split_name[0], split_name[1]);
         self.check_var(&parent_block, &"stream_proc_block".to_string())?;
         if tokens.get(3) != Some(&"type".to_string()) {
-            return Err(format!("Expected type keyword."));
+            return Err(format!("Expected type keyword.").into());
         }
         let object_type = tokens.get(4).ok_or_else(|| format!("Missing type"))?;
         if tokens.get(5) != Some(&"value".to_string()) {
-            return Err(format!("Expected value keyword."));
+            return Err(format!("Expected value keyword.").into());
         }
         let object_value = tokens.get(6).ok_or_else(|| format!("Missing value"))?;
         let mut object_limits = None;
         if let Some(limits_key) = tokens.get(7) {
             if limits_key != "limits" {
-                return Err(format!("Expected limits keyword."));
+                return Err(format!("Expected limits keyword.").into());
             }
             let value_limits = tokens.get(8).ok_or_else(|| format!("Missing limits"))?;
             object_limits = Some(value_limits);
@@ -303,21 +649,27 @@ impl Parser {
         coder.add_settable(&object_category.clone(), &split_name[2].to_string(), &object_type.clone(), &object_value.clone(), object_limits);
         coder.generate()?;
         self.coder_map.insert(parent_block.clone(), Box::new(coder));
-        Ok(())
+        Ok(Value::Null)
     }
     fn create_application(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let application_name = tokens.get(2).ok_or_else(|| "Missing application name".to_string())?;
         if self.projects_map.contains_key(application_name) {
-            return Err(format!("Application {} already exists.", application_name));
+            return Err(ParserError::new(ErrorCode::AlreadyExists, format!("Application {} already exists.", application_name)));
         }
         if tokens.get(3) != Some(&"path".to_string()) {
-            return Err(format!("Expected path keyword."));
+            return Err(format!("Expected path keyword.").into());
         }
         let application_folder = tokens.get(4).ok_or_else(|| "Missing application path".to_string())?;
         let metadata = tokens.get(6).ok_or_else(|| "Missing metadata value".to_string())?;
         let application_path = format!("{}/{}", application_folder, application_name);
+        self.track_new_folder(&application_path);
         self.cargo_if.cargo_new_application(application_path.to_string())?;
         self.cargo_if.cargo_add_commands(application_path.to_string())?; 
+        self.projects_map.insert(application_name.clone(), HashMap::new());
+        let mut main_coder = MainCoder::new(application_path.clone());
+        main_coder.generate()?;
+        self.coder_map.insert(application_name.clone(), Box::new(main_coder));
+        self.add_manifest_coder(application_name, &application_path, Vec::new())?;
         let memory_object = MemoryObject {
             parent: "".to_string(),
             object_category: ObjectCategory::Application,
@@ -325,19 +677,16 @@ impl Parser {
             object_value: metadata.clone(),
             object_limits: "".to_string(),
         };
-        self.projects_map.insert(application_name.clone(), HashMap::new());
         self.insert_in_memory_map(application_name.clone(), application_name.clone(), memory_object)?;
-        let path = format!("{}/src/main.rs", application_path);
-        let mut main_coder = MainCoder::new(path.clone());
-        main_coder.generate()?;
-        self.coder_map.insert(application_name.clone(), Box::new(main_coder));
-        Ok(())
+        self.save_project_info(application_name)?;
+        self.register_project(&application_path)?;
+        Ok(Value::Null)
     }
     fn create_task(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let task_name = tokens.get(2).ok_or_else(|| "Missing task name".to_string())?;
         let split_name = task_name.split(".").collect::<Vec<&str>>();
         if split_name.len() != 2 {
-            return Err(format!("Task name must be in the format <>.<>."));
+            return Err(format!("Task name must be in the format <>.<>.").into());
         }
         self.check_var(&split_name[0].to_string(), &"application".to_string())?;
         
@@ -354,37 +703,70 @@ impl Parser {
         main_coder.add_task_processor(task_name.clone());
         main_coder.generate()?;
         self.coder_map.insert(split_name[0].to_string(), Box::new(main_coder));
-        Ok(())
+        Ok(Value::Null)
     }
     fn create_stream_proc(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
-        let object_category = tokens.get(1).ok_or_else(|| "Missing object type".to_string())?;
         let object_name = tokens.get(2).ok_or_else(|| "Missing stream processor name".to_string())?;
         
         let split_name = object_name.split(".").collect::<Vec<&str>>();
         if split_name.len() != 3 {
-            return Err(format!("Input name must be in the format <>.<>.<>."));
+            return Err(format!("Input name must be in the format <>.<>.<>.").into());
         }
         let parent_block = format!("{}.{}", split_name[0], split_name[1]);
         self.check_var(&parent_block, &"task".to_string())?;
         if tokens.get(3) != Some(&"type".to_string()) {
-            return Err(format!("Expected type keyword."));
+            return Err(format!("Expected type keyword.").into());
         }
         let object_type = tokens.get(4).ok_or_else(|| format!("Missing type"))?;
         let memory_object = MemoryObject {
-            parent: split_name[0].to_string(),
+            parent: parent_block.clone(),
             object_category: ObjectCategory::StreamProc,
-            object_type: object_category.clone(),
+            object_type: object_type.clone(),
             object_value: "".to_string(),
             object_limits: "".to_string(),
         };
         self.insert_in_memory_map(split_name[0].to_string(), object_name.clone(), memory_object)?;
 
         let mut main_coder: MainCoder = self.get_coder::<MainCoder>(split_name[0].to_string())?.clone();
-        main_coder.add_stream_processor(object_name.clone(), object_type.clone());
+        main_coder.add_stream_processor(object_name.clone(), object_type.clone())?;
         main_coder.generate()?;
         self.coder_map.insert(split_name[0].to_string(), Box::new(main_coder));
+        self.add_crate_dependency(&split_name[0].to_string(), object_type)?;
+        Ok(Value::Null)
+    }
+    // A `<crate>.<Block>` type from a crate of the workspace makes the application depend on that crate.
+    fn add_crate_dependency(&mut self, application_name: &String, object_type: &String) -> Result<(), String> {
+        let crate_name = match object_type.split_once('.') {
+            Some((crate_name, _)) => crate_name.to_string(),
+            None => return Ok(()),
+        };
+        if !self.get_object(&crate_name).map_or(false, |object| object.object_category == ObjectCategory::Crate) {
+            return Ok(());
+        }
+        let crate_path = self.project_path(&crate_name)?;
+        let manifest_coder = self.get_coder::<ManifestCoder>(Parser::manifest_coder_name(application_name))?;
+        if manifest_coder.get_dependencies().contains_key(&crate_name) {
+            return Ok(());
+        }
+        manifest_coder.set_dependency(crate_name, DependencySpec { path: Some(crate_path), ..Default::default() })?;
+        manifest_coder.generate()?;
         Ok(())
     }
+    // Block coder of a stream processor whose type is a `<crate>.<Block>` of the workspace.
+    fn stream_proc_block(&self, proc_name: &String) -> Option<&ProcessorCoder> {
+        let application_name = proc_name.split(".").next()?.to_string();
+        let main_coder = self.coder_map.get(&application_name)?.as_any().downcast_ref::<MainCoder>()?;
+        let proc_type = main_coder.get_stream_processors().get(proc_name)?;
+        self.coder_map.get(proc_type)?.as_any().downcast_ref::<ProcessorCoder>()
+    }
+    // The loaded library providing the block type of a stream processor that is not part of the workspace.
+    fn stream_proc_library(&self, proc_name: &String) -> Option<String> {
+        let application_name = proc_name.split(".").next()?.to_string();
+        let main_coder = self.coder_map.get(&application_name)?.as_any().downcast_ref::<MainCoder>()?;
+        let (library_name, _) = main_coder.get_stream_processors().get(proc_name)?.split_once('.')?;
+        let library_manager = LibraryManager::get().lock().unwrap();
+        library_manager.provider_of(&library_name.to_string()).map(|module| module.name.clone())
+    }
     fn parse_create(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let key_type = tokens.get(1).ok_or_else(|| "Missing object type".to_string())?;
         let key_type_str: &str = key_type.as_str();
@@ -392,118 +774,571 @@ impl Parser {
         if let Some((_, key_value)) = self.create_types_fn.get_key_value(key_type_str) {
             create_function = *key_value;
         } else {
-            return Err(format!("Unknown command: {}", key_type_str));
+            return Err(ParserError::new(ErrorCode::UnknownCommand, format!("Unknown command: {}", key_type_str)));
         }
-        create_function(self, tokens)?;
-        Ok(())
+        create_function(self, tokens)
     }
-    fn parse_connect(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
-        let source_name = tokens.get(1).ok_or_else(|| "Missing source name".to_string())?;
-        let target_name = tokens.get(2).ok_or_else(|| "Missing target name".to_string())?;
+    // Checks `<app>.<task>.<proc>.<port>` source and target names, returning their processors.
+    fn connection_ends(&self, source_name: &String, target_name: &String) -> Result<(String, String), ParserError> {
         let source_split_name = source_name.split(".").collect::<Vec<&str>>();
         if source_split_name.len() != 4 {
-            return Err(format!("Connectable object name must be in the format <>.<>.<>.<>."));
+            return Err(format!("Connectable object name must be in the format <>.<>.<>.<>.").into());
         }
         let target_split_name = target_name.split(".").collect::<Vec<&str>>();
         if target_split_name.len() != 4 {
-            return Err(format!("Connectable object name must be in the format <>.<>.<>.<>."));
+            return Err(format!("Connectable object name must be in the format <>.<>.<>.<>.").into());
+        }
+        if source_split_name[0] != target_split_name[0] {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("{} and {} belong to different applications.", source_name, target_name)));
         }
         let from_processor = format!("{}.{}.{}", source_split_name[0], source_split_name[1], source_split_name[2]);
         let to_processor = format!("{}.{}.{}", target_split_name[0], target_split_name[1], target_split_name[2]);
         self.check_var(&from_processor, &"stream_proc".to_string())?;
         self.check_var(&to_processor, &"stream_proc".to_string())?;
+        Ok((from_processor, to_processor))
+    }
+    // connect <source output> <target input> [type <T>]
+    fn parse_connect(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let source_name = tokens.get(1).ok_or_else(|| "Missing source name".to_string())?;
+        let target_name = tokens.get(2).ok_or_else(|| "Missing target name".to_string())?;
+        let (from_processor, to_processor) = self.connection_ends(source_name, target_name)?;
+        let explicit_type = match tokens.get(3).map(|t| t.as_str()) {
+            Some("type") => Some(tokens.get(4).ok_or_else(|| "Missing type".to_string())?.clone()),
+            Some(option) => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown connect option {}.", option))),
+            None => None,
+        };
+
+        // Ports of blocks that are part of the workspace are checked against their declaration.
+        let output_name = source_name.rsplit('.').next().unwrap().to_string();
+        let output_type = match self.stream_proc_block(&from_processor) {
+            Some(block) => Some(block.get_outputs().get(&output_name).cloned()
+                .ok_or_else(|| ParserError::new(ErrorCode::NotFound, format!("Block {} of {} has no output {}.", block.get_processor_name(), from_processor, output_name)))?),
+            None => None,
+        };
+        let input_name = target_name.rsplit('.').next().unwrap().to_string();
+        let input_type = match self.stream_proc_block(&to_processor) {
+            Some(block) => Some(block.get_inputs().get(&input_name).cloned()
+                .ok_or_else(|| ParserError::new(ErrorCode::NotFound, format!("Block {} of {} has no input {}.", block.get_processor_name(), to_processor, input_name)))?),
+            None => None,
+        };
+        if let (Some(output_type), Some(input_type)) = (&output_type, &input_type) {
+            if output_type != input_type {
+                return Err(ParserError::new(ErrorCode::TypeMismatch, format!("Cannot connect {} ({}) to {} ({}).", source_name, output_type, target_name, input_type)));
+            }
+        }
+        let declared_type = output_type.or(input_type);
+        let data_type = match (declared_type, explicit_type) {
+            (Some(declared_type), Some(explicit_type)) if declared_type != explicit_type => {
+                return Err(ParserError::new(ErrorCode::TypeMismatch, format!("The connection carries {}, not {}.", declared_type, explicit_type)));
+            },
+            (Some(declared_type), _) => declared_type,
+            (None, Some(explicit_type)) => explicit_type,
+            (None, None) => {
+                // Loaded libraries only expose their module description, not the ports of their blocks.
+                let loaded = [&from_processor, &to_processor].iter()
+                    .filter_map(|proc_name| self.stream_proc_library(proc_name).map(|library| format!("{} ({})", proc_name, library)))
+                    .collect::<Vec<String>>();
+                let message = if loaded.is_empty() {
+                    format!("Neither {} nor {} is a block of the workspace, give the data type with `type <T>`.", from_processor, to_processor)
+                } else {
+                    format!("The ports of blocks from loaded libraries are not known to the coder: {}. Give the data type with `type <T>`.", loaded.join(", "))
+                };
+                return Err(ParserError::new(ErrorCode::InvalidArguments, message));
+            },
+        };
 
-        let mut main_coder: MainCoder = self.get_coder::<MainCoder>(source_split_name[0].to_string())?.clone();
-        main_coder.add_connection(from_processor.clone(), source_name.clone(), to_processor.clone(), target_name.clone());
+        let application_name = from_processor.split(".").next().unwrap().to_string();
+        let mut main_coder: MainCoder = self.get_coder::<MainCoder>(application_name.clone())?.clone();
+        // An input reads a single output.
+        if let Some(connection) = main_coder.get_connections().iter().find(|c| c.to_input == *target_name) {
+            return Err(ParserError::new(ErrorCode::AlreadyExists, format!("{} is already connected to {}.", connection.from_output, target_name)));
+        }
+        main_coder.add_connection(from_processor.clone(), source_name.clone(), to_processor.clone(), target_name.clone(), data_type);
         main_coder.generate()?;
-        self.coder_map.insert(source_split_name[0].to_string(), Box::new(main_coder));
-        Ok(())
+        self.coder_map.insert(application_name, Box::new(main_coder));
+        Ok(Value::Null)
+    }
+    // disconnect <source output> <target input>
+    fn parse_disconnect(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let source_name = tokens.get(1).ok_or_else(|| "Missing source name".to_string())?;
+        let target_name = tokens.get(2).ok_or_else(|| "Missing target name".to_string())?;
+        let (from_processor, _) = self.connection_ends(source_name, target_name)?;
+        let application_name = from_processor.split(".").next().unwrap().to_string();
+        let mut main_coder: MainCoder = self.get_coder::<MainCoder>(application_name.clone())?.clone();
+        if !main_coder.remove_connection(source_name, target_name) {
+            return Err(ParserError::new(ErrorCode::NotFound, format!("{} is not connected to {}.", source_name, target_name)));
+        }
+        main_coder.generate()?;
+        self.coder_map.insert(application_name, Box::new(main_coder));
+        Ok(Value::Null)
+    }
+    // connections <application>
+    fn parse_connections(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let application_name = tokens.get(1).ok_or_else(|| "Missing application name".to_string())?;
+        self.check_var(application_name, &"application".to_string())?;
+        let main_coder = self.get_coder::<MainCoder>(application_name.clone())?;
+        let connections = main_coder.get_connections().iter()
+            .map(|c| json!({ "from": c.from_output, "to": c.to_input, "type": c.data_type }))
+            .collect::<Vec<Value>>();
+        Ok(Value::Array(connections))
+    }
+    fn parse_graph(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let application_name = tokens.get(1).ok_or_else(|| "Missing application name".to_string())?;
+        let format = tokens.get(2).map(|f| f.as_str()).unwrap_or("dot");
+        if !GRAPH_FORMATS.contains(&format) {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown graph format {}, expected one of {}.", format, GRAPH_FORMATS.join(", "))));
+        }
+        self.check_var(application_name, &"application".to_string())?;
+        let main_coder = self.get_coder_ref::<MainCoder>(application_name)?;
+        let graph = match format {
+            "mermaid" => render_mermaid(application_name, main_coder),
+            _ => render_dot(application_name, main_coder),
+        };
+        Ok(json!({ "application": application_name, "format": format, "graph": graph }))
     }
     fn parse_set(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let object_category = tokens.get(1).ok_or_else(|| "Missing variable type".to_string())?;
         let object_name = tokens.get(2).ok_or_else(|| "Missing variable name".to_string())?;
         let split_name = object_name.split(".").collect::<Vec<&str>>();
         if split_name.len() != 4 {
-            return Err(format!("Settable object name must be in the format <>.<>.<>."));
+            return Err(format!("Settable object name must be in the format <>.<>.<>.").into());
         }
         let parent_block = format!("{}.{}.{}", split_name[0], split_name[1], split_name[2]);
         self.check_var(&parent_block, &"stream_proc".to_string())?;
         let value = tokens.get(3).ok_or_else(|| "Missing variable value".to_string())?;
+        let explicit_type = match tokens.get(4).map(|t| t.as_str()) {
+            Some("type") => Some(tokens.get(5).ok_or_else(|| "Missing type".to_string())?.clone()),
+            Some(option) => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown set option {}.", option))),
+            None => None,
+        };
+        let settable_name = split_name[3].to_string();
+        if object_category != "parameter" && object_category != "static" {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Only parameter and static values can be set, not {}.", object_category)));
+        }
+
+        // The declaration comes from the block when it is part of the workspace, otherwise the type must be given.
+        let data_type = match self.stream_proc_block(&parent_block) {
+            Some(block) => {
+                let settables = if object_category == "parameter" { block.get_parameters() } else { block.get_statics() };
+                let typed = settables.get(&settable_name).ok_or_else(|| ParserError::new(ErrorCode::NotFound, format!("Block {} has no {} {}.", block.get_processor_name(), object_category, settable_name)))?;
+                if let Some(explicit_type) = explicit_type.as_ref().filter(|t| **t != typed.data_type) {
+                    return Err(ParserError::new(ErrorCode::TypeMismatch, format!("{} is declared as {}, not {}.", object_name, typed.data_type, explicit_type)));
+                }
+                typed_literal(&typed.data_type, value, typed.limits.as_ref())
+                    .map_err(|e| ParserError::new(ErrorCode::TypeMismatch, format!("Invalid value for {}: {}", object_name, e)))?;
+                typed.data_type.clone()
+            },
+            None => {
+                let data_type = explicit_type.ok_or_else(|| ParserError::new(ErrorCode::InvalidArguments,
+                    format!("The block of {} is not part of the workspace, give the value type with `type <T>`.", parent_block)))?;
+                typed_literal(&data_type, value, None)
+                    .map_err(|e| ParserError::new(ErrorCode::TypeMismatch, format!("Invalid value for {}: {}", object_name, e)))?;
+                data_type
+            },
+        };
 
         let mut main_coder: MainCoder = self.get_coder::<MainCoder>(split_name[0].to_string())?.clone();
-        main_coder.add_setting_value(parent_block.clone(), object_category.clone(), object_name.clone(), value.clone());
+        main_coder.add_setting_value(parent_block.clone(), object_category.clone(), object_name.clone(), value.clone(), data_type);
         main_coder.generate()?;
         self.coder_map.insert(split_name[0].to_string(), Box::new(main_coder));
-        Ok(())
+        Ok(Value::Null)
+    }
+    fn parse_unset(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let object_category = tokens.get(1).ok_or_else(|| "Missing variable type".to_string())?;
+        let object_name = tokens.get(2).ok_or_else(|| "Missing variable name".to_string())?;
+        let split_name = object_name.split(".").collect::<Vec<&str>>();
+        if split_name.len() != 4 {
+            return Err(format!("Settable object name must be in the format <>.<>.<>.").into());
+        }
+        let mut main_coder: MainCoder = self.get_coder::<MainCoder>(split_name[0].to_string())?.clone();
+        if !main_coder.remove_setting(object_category, object_name) {
+            return Err(ParserError::new(ErrorCode::NotFound, format!("No {} value is set for {}.", object_category, object_name)));
+        }
+        main_coder.generate()?;
+        self.coder_map.insert(split_name[0].to_string(), Box::new(main_coder));
+        Ok(Value::Null)
     }
-    fn delete(&mut self, object_name: String) -> Result<(), String> {
+    fn application_pipeline(&mut self, application_name: &String) -> Result<Pipeline, ParserError> {
+        self.check_var(application_name, &"application".to_string())?;
+        let description = self.projects_map[application_name][application_name].object_value.clone();
+        let main_coder = self.get_coder_ref::<MainCoder>(application_name)?;
+        Ok(Pipeline::from_application(application_name, &description, main_coder))
+    }
+    fn parse_dump(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let application_name = tokens.get(1).ok_or_else(|| "Missing application name".to_string())?;
+        let pipeline = self.application_pipeline(application_name)?;
+        match tokens.get(2) {
+            Some(file) => {
+                pipeline.save(file)?;
+                Ok(json!({ "application": application_name, "file": file }))
+            },
+            None => Ok(json!({ "application": application_name, "pipeline": pipeline.to_toml_string()? })),
+        }
+    }
+    // `apply <file> [path <folder>]` creates the application of the pipeline file in <folder> when it
+    // does not exist yet, then runs the commands that bring the application in line with the file.
+    fn parse_apply(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let file = tokens.get(1).ok_or_else(|| "Missing pipeline file".to_string())?;
+        let folder = match tokens.get(2).map(|t| t.as_str()) {
+            Some("path") => Some(tokens.get(3).ok_or_else(|| "Missing application path".to_string())?.clone()),
+            Some(option) => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown apply option {}.", option))),
+            None => None,
+        };
+        let pipeline = Pipeline::load(file).map_err(|e| ParserError::new(ErrorCode::InvalidArguments, e))?;
+        let application_name = pipeline.application.clone();
+        let mut commands: Vec<Vec<String>> = Vec::new();
+        if !self.projects_map.contains_key(&application_name) {
+            let folder = folder.ok_or_else(|| ParserError::new(ErrorCode::InvalidArguments,
+                format!("Application {} does not exist, give its folder with `path <folder>`.", application_name)))?;
+            let create_tokens = vec!["create".to_string(), "application".to_string(), application_name.clone(),
+                "path".to_string(), folder, "metadata".to_string(), pipeline.description.clone()];
+            self.create_application(&create_tokens)?;
+            commands.push(create_tokens);
+        } else if folder.is_some() {
+            return Err(ParserError::new(ErrorCode::InvalidArguments,
+                format!("Application {} already exists in {}, `path` only applies when it is created.", application_name, self.project_path(&application_name)?)));
+        }
+        let changes = pipeline.changes(self.get_coder_ref::<MainCoder>(&application_name)?)?;
+        for change in changes.iter() {
+            let parser_function = self.commands_fn[&change[0]];
+            parser_function(self, change).map_err(|mut e| {
+                e.message = format!("{}: {}", change.join(" "), e.message);
+                e
+            })?;
+        }
+        commands.extend(changes);
+        // The description is the metadata of the application object.
+        let application_object = self.projects_map.get_mut(&application_name).unwrap().get_mut(&application_name)
+            .ok_or_else(|| format!("Object {} not found.", application_name))?;
+        let description_changed = application_object.object_value != pipeline.description;
+        if description_changed {
+            application_object.object_value = pipeline.description.clone();
+            self.save_memory_map(&application_name)?;
+        }
+        Ok(json!({
+            "application": application_name,
+            "changes": commands.iter().map(|c| c.join(" ")).collect::<Vec<String>>(),
+            "description_changed": description_changed,
+        }))
+    }
+    // Works on copies of the application coders, so that the plan can be shown before it is applied.
+    fn plan_deletion(&self, object_name: &String) -> Result<DeletionPlan, ParserError> {
+        let object = self.get_object(object_name)?.clone();
         let split_name = object_name.split(".").collect::<Vec<&str>>();
-        let object_map = self.projects_map.get_mut(&split_name[0].to_string()).unwrap();
-        if split_name.len() == 1 {
-            // Application or Crate deletion
-            let children = object_map.iter()
-                .filter(|(_, v)| v.parent == object_name)
-                .map(|(k, _)| k.clone())
+        let project_name = split_name[0].to_string();
+        let prefix = format!("{}.", object_name);
+        let mut plan = DeletionPlan::default();
+        plan.objects = self.projects_map[&project_name].keys()
+            .filter(|k| *k == object_name || k.starts_with(&prefix))
+            .cloned()
+            .collect();
+        plan.objects.sort();
+
+        // Block types whose stream processors go away with the object.
+        let mut removed_types: Vec<String> = Vec::new();
+        let mut applications: Vec<(String, MainCoder)> = Vec::new();
+        match object.object_category {
+            ObjectCategory::Crate | ObjectCategory::Application => {
+                plan.files.push(self.project_path(&project_name)?);
+                if object.object_category == ObjectCategory::Crate {
+                    plan.modules = self.get_coder_ref::<LibCoder>(&project_name)?.get_modules().clone();
+                    removed_types = plan.modules.iter().map(|module| format!("{}.{}", project_name, module)).collect();
+                }
+            },
+            ObjectCategory::StreamProcBlock => {
+                let coder = self.get_coder_ref::<ProcessorCoder>(object_name)?;
+                plan.files.extend([coder.get_file_path(), coder.get_save_path()]);
+                plan.modules.push(split_name[1].to_string());
+                removed_types.push(object_name.clone());
+            },
+            ObjectCategory::Task | ObjectCategory::StreamProc => {
+                let mut main_coder = self.get_coder_ref::<MainCoder>(&project_name)?.clone();
+                main_coder.delete_object(object_name);
+                applications.push((project_name.clone(), main_coder));
+            },
+            _ => {
+                let block_name = format!("{}.{}", split_name[0], split_name[1]);
+                let category: String = object.object_category.into();
+                for (application_name, main_coder) in self.block_users(&vec![block_name.clone()]) {
+                    let mut main_coder = main_coder.clone();
+                    main_coder.remove_block_member(&block_name, &category, &split_name[2].to_string());
+                    applications.push((application_name, main_coder));
+                }
+            },
+        }
+        for (application_name, main_coder) in self.block_users(&removed_types) {
+            let mut main_coder = main_coder.clone();
+            let mut proc_names = main_coder.get_stream_processors().iter()
+                .filter(|(_, proc_type)| removed_types.contains(proc_type))
+                .map(|(proc_name, _)| proc_name.clone())
+                .collect::<Vec<String>>();
+            proc_names.sort();
+            for proc_name in proc_names.iter() {
+                main_coder.delete_object(proc_name);
+            }
+            plan.processors.extend(proc_names);
+            applications.push((application_name, main_coder));
+        }
+        if object.object_category == ObjectCategory::Crate {
+            let mut manifest_names = self.coder_map.keys()
+                .filter(|coder_name| coder_name.ends_with(".Cargo.toml") && **coder_name != Parser::manifest_coder_name(&project_name))
+                .cloned()
+                .collect::<Vec<String>>();
+            manifest_names.sort();
+            for manifest_name in manifest_names.iter() {
+                if self.get_coder_ref::<ManifestCoder>(manifest_name)?.get_dependencies().contains_key(&project_name) {
+                    plan.dependencies.push(format!("{}: {}", manifest_name.trim_end_matches(".Cargo.toml"), project_name));
+                }
+            }
+        }
+        for (application_name, main_coder) in applications.iter() {
+            let current = self.get_coder_ref::<MainCoder>(application_name)?;
+            let connection_names = |coder: &MainCoder| coder.get_connections().iter()
+                .map(|c| format!("{} -> {}", c.from_output, c.to_input))
+                .collect::<Vec<String>>();
+            let setting_names = |coder: &MainCoder| coder.get_settings().iter()
+                .map(|s| s.settable_name.clone())
                 .collect::<Vec<String>>();
-            for child in children {
-                object_map.retain(|_, v| v.parent != child);
-                object_map.retain(|k, _| *k != child);
-            }
-            object_map.retain(|k, _| k != &object_name);
-            self.cargo_if.delete_project(object_name.clone())?;
-        } else if split_name.len() == 2 {
-            //  Task or stream_proc_block deletion
-            object_map.retain(|_, v| v.parent != object_name);
-            object_map.retain(|k, _| k != &object_name);
-        } else if split_name.len() == 3 {
-            // Typed object or stream_proc deletion
-            object_map.retain(|k, _| k != &object_name);
+            let (kept_connections, kept_settings) = (connection_names(main_coder), setting_names(main_coder));
+            plan.connections.extend(connection_names(current).into_iter().filter(|c| !kept_connections.contains(c)));
+            plan.settings.extend(setting_names(current).into_iter().filter(|s| !kept_settings.contains(s)));
+        }
+        plan.applications = applications;
+        Ok(plan)
+    }
+    // Applications running stream processors of one of the blocks, sorted by name.
+    fn block_users(&self, block_names: &Vec<String>) -> Vec<(String, &MainCoder)> {
+        let mut applications = self.coder_map.iter()
+            .filter_map(|(coder_name, coder)| Some((coder_name.clone(), coder.as_any().downcast_ref::<MainCoder>()?)))
+            .filter(|(_, main_coder)| main_coder.get_stream_processors().values().any(|t| block_names.contains(t)))
+            .collect::<Vec<(String, &MainCoder)>>();
+        applications.sort_by(|a, b| a.0.cmp(&b.0));
+        applications
+    }
+    fn delete(&mut self, object_name: &String, plan: DeletionPlan, command: &String) -> Result<(), ParserError> {
+        let split_name = object_name.split(".").collect::<Vec<&str>>();
+        let project_name = split_name[0].to_string();
+        let object = self.get_object(object_name)?.clone();
+
+        // Other projects get their own journal entry, the project of the object is journaled by the command.
+        let mut affected = plan.applications.iter().map(|(application_name, _)| application_name.clone()).collect::<Vec<String>>();
+        for dependency in plan.dependencies.iter() {
+            let dependent_name = dependency.split(": ").next().unwrap_or_default().to_string();
+            if !affected.contains(&dependent_name) {
+                affected.push(dependent_name);
+            }
         }
+        let mut main_coders = plan.applications.into_iter().collect::<HashMap<String, MainCoder>>();
+        for affected_name in affected.iter() {
+            let snapshot = if *affected_name != project_name {
+                self.sync_project_sources(affected_name)?;
+                self.track_project(affected_name)?;
+                Some(self.take_snapshot(affected_name)?)
+            } else {
+                None
+            };
+            if let Some(mut main_coder) = main_coders.remove(affected_name) {
+                main_coder.generate()?;
+                self.coder_map.insert(affected_name.clone(), Box::new(main_coder));
+            }
+            if plan.dependencies.contains(&format!("{}: {}", affected_name, project_name)) {
+                let manifest_coder = self.get_coder::<ManifestCoder>(Parser::manifest_coder_name(affected_name))?;
+                manifest_coder.remove_dependency(&project_name)?;
+                manifest_coder.generate()?;
+            }
+            if let Some(snapshot) = snapshot {
+                self.projects_map.get_mut(affected_name).unwrap().retain(|k, _| !plan.processors.contains(k));
+                self.save_memory_map(affected_name)?;
+                let after = self.take_snapshot(affected_name)?;
+                self.journal_mut(affected_name)?.record(command.clone(), &snapshot, &after);
+                self.save_journal(affected_name)?;
+            }
+        }
+
+        match object.object_category {
+            ObjectCategory::Crate | ObjectCategory::Application => {
+                let project_path = self.project_path(&project_name)?;
+                self.unregister_project(&project_path)?;
+                self.cargo_if.delete_project(project_path)?;
+                for coder_name in self.project_coder_names(&project_name) {
+                    self.coder_map.remove(&coder_name);
+                }
+                self.projects_map.remove(&project_name);
+                self.journals.remove(&project_name);
+                return Ok(());
+            },
+            ObjectCategory::StreamProcBlock => {
+                for file in plan.files.iter() {
+                    Parser::remove_file_if_exists(file)?;
+                }
+                self.coder_map.remove(object_name);
+                let mut lib_coder = self.get_coder::<LibCoder>(project_name.clone())?.clone();
+                lib_coder.delete_object(&split_name[1].to_string());
+                lib_coder.generate()?;
+                self.coder_map.insert(project_name.clone(), Box::new(lib_coder));
+            },
+            ObjectCategory::Task | ObjectCategory::StreamProc => {},
+            _ => {
+                let block_name = format!("{}.{}", split_name[0], split_name[1]);
+                let mut coder: ProcessorCoder = self.get_coder::<ProcessorCoder>(block_name.clone())?.clone();
+                coder.delete_object(&split_name[2].to_string());
+                coder.generate()?;
+                self.coder_map.insert(block_name, Box::new(coder));
+            },
+        }
+        self.projects_map.get_mut(&project_name).unwrap().retain(|k, _| !plan.objects.contains(k));
+        self.save_memory_map(&project_name)?;
         Ok(())
     }
+    // `delete <object> [dry_run]`: the dry run only lists what the deletion would remove.
     fn parse_delete(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let object_name = tokens.get(1).ok_or_else(|| "Missing object name".to_string())?;
-        let split_name = object_name.split(".").collect::<Vec<&str>>();
-        if split_name.len() == 0 {
-            return Err(format!("Invalid object name."));
+        let dry_run = match tokens.get(2).map(|t| t.as_str()) {
+            Some("dry_run") => true,
+            Some(option) => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown delete option {}.", option))),
+            None => false,
+        };
+        let plan = self.plan_deletion(object_name)?;
+        let mut payload = serde_json::to_value(&plan).map_err(|e| format!("Error serializing deletion plan: {}", e))?;
+        payload["deleted"] = json!(object_name);
+        payload["dry_run"] = json!(dry_run);
+        if !dry_run {
+            self.delete(object_name, plan, &tokens.join(" "))?;
         }
-        let object_map = self.projects_map.get_mut(&split_name[0].to_string()).ok_or_else(|| format!("Project {} does not exist.", split_name[0]))?;
-        if !object_map.contains_key(object_name) {
-            return Err(format!("Object {} does not exist.", object_name));
+        Ok(payload)
+    }
+    fn parse_rename(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let object_name = tokens.get(1).ok_or_else(|| "Missing object name".to_string())?;
+        let new_name = tokens.get(2).ok_or_else(|| "Missing new name".to_string())?;
+        if new_name.is_empty() || new_name.contains('.') {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Invalid name {}: rename takes the new last part of the name, use move to change the parent.", new_name)));
         }
-        if split_name.len() == 1 {
-            self.projects_map.remove(&object_name.clone());
-            self.coder_map.remove(object_name);
-        } else {
-            let object = object_map.get(object_name).unwrap();
-            if object.object_category == ObjectCategory::StreamProcBlock {
-                self.coder_map.remove(object_name);
-            } 
-            match object.object_category {
-                ObjectCategory::StreamProcBlock => {
-                    let mut coder = self.get_coder::<LibCoder>(split_name[0].to_string())?.clone();
-                    coder.delete_object(&split_name[1].to_string());
-                    coder.generate()?;
-                    self.coder_map.insert(split_name[0].to_string(), Box::new(coder));
+        let parent = match object_name.rsplit_once('.') {
+            Some((parent, _)) => parent,
+            None => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("{} is a project: import it under a new name instead.", object_name))),
+        };
+        self.rename_object(object_name, &format!("{}.{}", parent, new_name), tokens.join(" "))
+    }
+    fn parse_move(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let object_name = tokens.get(1).ok_or_else(|| "Missing object name".to_string())?;
+        let new_name = tokens.get(2).ok_or_else(|| "Missing new name".to_string())?;
+        self.rename_object(object_name, new_name, tokens.join(" "))
+    }
+    // Renames an object of the memory map together with its children and every coder referring
+    // to it. `new_name` is the full new name and may have another parent for stream processors.
+    fn rename_object(&mut self, old_name: &String, new_name: &String, command: String) -> ParserFunctionReturn {
+        let object = self.get_object(old_name)?.clone();
+        let old_split = old_name.split(".").collect::<Vec<&str>>();
+        let new_split = new_name.split(".").collect::<Vec<&str>>();
+        if old_split.len() == 1 {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("{} is a project: import it under a new name instead.", old_name)));
+        }
+        if new_split.len() != old_split.len() || new_split[0] != old_split[0] || new_split.iter().any(|part| part.is_empty()) {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("{} must stay in project {} at the same level, {} does not.", old_name, old_split[0], new_name)));
+        }
+        if new_name == old_name {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("{} already has that name.", old_name)));
+        }
+        if self.get_object(new_name).is_ok() {
+            return Err(ParserError::new(ErrorCode::AlreadyExists, format!("Object {} already exists.", new_name)));
+        }
+        let project_name = old_split[0].to_string();
+        let (old_parent, old_local) = old_name.rsplit_once('.').unwrap();
+        let (new_parent, new_local) = new_name.rsplit_once('.').unwrap();
+        let (old_local, new_local) = (old_local.to_string(), new_local.to_string());
+        let mut updated: Vec<String> = Vec::new();
+        // User code is not rewritten: regions still naming the old member are reported instead.
+        let mut user_code_references: Vec<String> = Vec::new();
+        match object.object_category {
+            ObjectCategory::StreamProcBlock => {
+                let mut coder = self.get_coder::<ProcessorCoder>(old_name.clone())?.clone();
+                let old_files = [coder.get_file_path(), coder.get_save_path()];
+                coder.set_processor_name(new_local.clone());
+                coder.generate()?;
+                // The old files go only once the new ones are written, unless both names map to the same file.
+                for old_file in old_files.iter() {
+                    if ![coder.get_file_path(), coder.get_save_path()].contains(old_file) {
+                        Parser::remove_file_if_exists(old_file)?;
+                    }
+                }
+                self.coder_map.remove(old_name);
+                self.coder_map.insert(new_name.clone(), Box::new(coder));
+                let mut lib_coder = self.get_coder::<LibCoder>(project_name.clone())?.clone();
+                lib_coder.rename_module(&old_local, &new_local);
+                lib_coder.generate()?;
+                self.coder_map.insert(project_name.clone(), Box::new(lib_coder));
+                updated = self.update_block_users(old_name, &command, |main_coder, object_map| {
+                    for object in object_map.values_mut().filter(|o| o.object_category == ObjectCategory::StreamProc && o.object_type == *old_name) {
+                        object.object_type = new_name.clone();
+                    }
+                    main_coder.rename_block_type(old_name, new_name);
+                })?;
+            },
+            ObjectCategory::Task | ObjectCategory::StreamProc => {
+                if object.object_category == ObjectCategory::StreamProc {
+                    self.check_var(&new_parent.to_string(), &"task".to_string())?;
                 }
-                ObjectCategory::Task | ObjectCategory::StreamProc=> {
-                    let parent_app = split_name[0].to_string();
-                    let mut main_coder: MainCoder = self.get_coder::<MainCoder>(parent_app.clone())?.clone();
-                    main_coder.delete_object(&object_name.clone());
-                    main_coder.generate()?;
-                    self.coder_map.insert(parent_app.clone(), Box::new(main_coder));
+                let mut main_coder = self.get_coder::<MainCoder>(project_name.clone())?.clone();
+                main_coder.rename_object(old_name, new_name);
+                main_coder.generate()?;
+                self.coder_map.insert(project_name.clone(), Box::new(main_coder));
+            },
+            _ => {
+                if new_parent != old_parent {
+                    return Err(ParserError::new(ErrorCode::InvalidArguments, format!("{} can only be renamed within block {}.", old_name, old_parent)));
                 }
-                _ => {
-                    let parent_block = format!("{}.{}", split_name[0], split_name[1]);
-                    let mut coder: ProcessorCoder = self.get_coder::<ProcessorCoder>(parent_block.clone())?.clone();
-                    coder.delete_object(&object_name.clone());
-                    coder.generate()?;
-                    self.coder_map.insert(parent_block.clone(), Box::new(coder));
-                },
-            }
+                let block_name = old_parent.to_string();
+                let mut coder = self.get_coder::<ProcessorCoder>(block_name.clone())?.clone();
+                coder.rename_member(&old_local, &new_local)?;
+                coder.generate()?;
+                user_code_references.extend(coder.regions_quoting(&old_local).into_iter().map(|region| format!("{}: {}", block_name, region)));
+                self.coder_map.insert(block_name.clone(), Box::new(coder));
+                let category: String = object.object_category.into();
+                updated = self.update_block_users(&block_name, &command, |main_coder, _| {
+                    main_coder.rename_block_member(&block_name, &category, &old_local, &new_local);
+                })?;
+                for application_name in updated.iter() {
+                    let main_coder = self.get_coder_ref::<MainCoder>(application_name)?;
+                    user_code_references.extend(main_coder.regions_quoting(&old_local).into_iter().map(|region| format!("{}: {}", application_name, region)));
+                }
+            },
         }
-        self.delete(object_name.clone())?;
-        Ok(())
+        let object_map = self.projects_map.remove(&project_name).unwrap();
+        let mut object_map = object_map.into_iter()
+            .map(|(object_name, mut object)| {
+                object.parent = rename_prefix(&object.parent, old_name, new_name);
+                (rename_prefix(&object_name, old_name, new_name), object)
+            })
+            .collect::<HashMap<String, MemoryObject>>();
+        object_map.get_mut(new_name).unwrap().parent = new_parent.to_string();
+        self.projects_map.insert(project_name.clone(), object_map);
+        self.save_memory_map(&project_name)?;
+        Ok(json!({ "renamed": old_name, "to": new_name, "updated": updated, "user_code_references": user_code_references }))
+    }
+    // Applications running stream processors of a renamed block follow the new names. Each one
+    // gets its own journal entry, so that undo in the application reverts its part of the rename.
+    fn update_block_users<F>(&mut self, block_name: &String, command: &String, update: F) -> Result<Vec<String>, ParserError>
+    where F: Fn(&mut MainCoder, &mut HashMap<String, MemoryObject>) {
+        let mut applications = self.coder_map.iter()
+            .filter_map(|(coder_name, coder)| Some((coder_name, coder.as_any().downcast_ref::<MainCoder>()?)))
+            .filter(|(_, main_coder)| main_coder.get_stream_processors().values().any(|t| t == block_name))
+            .map(|(coder_name, _)| coder_name.clone())
+            .collect::<Vec<String>>();
+        applications.sort();
+        for application_name in applications.iter() {
+            self.sync_project_sources(application_name)?;
+            self.track_project(application_name)?;
+            let snapshot = self.take_snapshot(application_name)?;
+            let mut main_coder = self.get_coder::<MainCoder>(application_name.clone())?.clone();
+            update(&mut main_coder, self.projects_map.get_mut(application_name).unwrap());
+            main_coder.generate()?;
+            self.coder_map.insert(application_name.clone(), Box::new(main_coder));
+            self.save_memory_map(application_name)?;
+            let after = self.take_snapshot(application_name)?;
+            self.journal_mut(application_name)?.record(command.clone(), &snapshot, &after);
+            self.save_journal(application_name)?;
+        }
+        Ok(applications)
     }
     fn parse_code(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let object_name = tokens.get(1).ok_or_else(|| "Missing processor name".to_string())?;
@@ -530,11 +1365,97 @@ impl Parser {
                         self.coder_map.insert(object_name.clone(), Box::new(coder));
                         
                     },
-                    Err(_) => {return Err(format!("Object {} does not allow user code.", object_name));},
+                    Err(_) => {return Err(format!("Object {} does not allow user code.", object_name).into());},
                 }
             },
         }
-        Ok(())
+        Ok(Value::Null)
+    }
+    fn remove_file_if_exists(path: &String) -> Result<(), String> {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Error removing {}: {}", path, e)),
+            _ => Ok(()),
+        }
+    }
+    fn same_file(first: &String, second: &String) -> bool {
+        match (std::fs::canonicalize(first), std::fs::canonicalize(second)) {
+            (Ok(first_path), Ok(second_path)) => first_path == second_path,
+            _ => Path::new(first) == Path::new(second),
+        }
+    }
+    // Maps each diagnostic back to the object whose generated file it points to, and to the coder section at that line.
+    fn locate_diagnostics(&self, project_name: &String, diagnostics: &mut Vec<BuildDiagnostic>) {
+        let project_prefix = format!("{}.", project_name);
+        for diagnostic in diagnostics.iter_mut() {
+            let (file, line) = match (&diagnostic.file, diagnostic.line) {
+                (Some(file), Some(line)) => (file.clone(), line),
+                _ => continue,
+            };
+            for (object_name, coder) in self.coder_map.iter() {
+                if object_name != project_name && !object_name.starts_with(&project_prefix) {
+                    continue;
+                }
+                if Parser::same_file(&coder.get_file_path(), &file) {
+                    diagnostic.object = Some(object_name.clone());
+                    diagnostic.section = coder.find_section(line);
+                    break;
+                }
+            }
+        }
+    }
+    // Checks the model of a crate or an application without invoking cargo.
+    fn validate_project(&self, project_name: &String) -> Result<Vec<ValidationIssue>, ParserError> {
+        let mut issues: Vec<ValidationIssue> = Vec::new();
+        match self.get_object(project_name)?.object_category {
+            ObjectCategory::Crate => {
+                let mut coder_names = self.project_coder_names(project_name);
+                coder_names.sort();
+                for coder_name in coder_names.iter() {
+                    if let Some(block) = self.coder_map[coder_name].as_any().downcast_ref::<ProcessorCoder>() {
+                        issues.extend(validate_block(coder_name, block));
+                    }
+                }
+            },
+            ObjectCategory::Application => {
+                let main_coder = self.coder_map.get(project_name)
+                    .and_then(|coder| coder.as_any().downcast_ref::<MainCoder>())
+                    .ok_or_else(|| format!("Coder for application {} not found.", project_name))?;
+                let blocks = main_coder.get_stream_processors().keys()
+                    .filter_map(|proc_name| self.stream_proc_block(proc_name).map(|block| (proc_name.clone(), block)))
+                    .collect::<HashMap<String, &ProcessorCoder>>();
+                let stream_procs = self.projects_map[project_name].iter()
+                    .filter(|(_, object)| object.object_category == ObjectCategory::StreamProc)
+                    .map(|(object_name, _)| object_name.clone())
+                    .collect::<Vec<String>>();
+                issues.extend(validate_application(main_coder, &blocks, &stream_procs));
+            },
+            _ => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("{} is not a crate or an application.", project_name))),
+        }
+        Ok(issues)
+    }
+    fn validation_payload(issues: &Vec<ValidationIssue>) -> Value {
+        let (errors, warnings): (Vec<&ValidationIssue>, Vec<&ValidationIssue>) = issues.iter().partition(|issue| issue.is_error());
+        json!({
+            "valid": errors.is_empty(),
+            "errors": errors,
+            "warnings": warnings,
+        })
+    }
+    // Stops builds of models that cannot work before cargo is run.
+    fn ensure_valid(&self, project_name: &String) -> ParserFunctionReturn {
+        let issues = self.validate_project(project_name)?;
+        let payload = Parser::validation_payload(&issues);
+        let error_count = issues.iter().filter(|issue| issue.is_error()).count();
+        if error_count > 0 {
+            let mut error = ParserError::new(ErrorCode::ValidationFailed, format!("Validation of {} found {} error(s).", project_name, error_count));
+            error.payload = payload;
+            return Err(error);
+        }
+        Ok(payload)
+    }
+    fn parse_validate(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let project_name = tokens.get(1).ok_or_else(|| "Missing crate or application name".to_string())?;
+        self.ensure_valid(project_name)
     }
     fn parse_build(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let build_object_name = tokens.get(1).ok_or_else(|| "Missing artifact name".to_string())?;
@@ -546,32 +1467,32 @@ impl Parser {
                         if let Some(lib_coder_mut) = coder.as_any_mut().downcast_mut::<LibCoder>() {
                             build_path = lib_coder_mut.get_path();
                         } else {
-                            return Err(format!("Coder for crate {} is not a LibCoder.", build_object_name));
+                            return Err(format!("Coder for crate {} is not a LibCoder.", build_object_name).into());
                         }
                     },
-                    None => return Err(format!("Coder for crate {} not found.", build_object_name)),
+                    None => return Err(format!("Coder for crate {} not found.", build_object_name).into()),
                 }
             },
             Err(_) => {
                 match self.check_var(build_object_name, &"application".to_string()) {
                     Ok(_) => {
-                        let main_coder: MainCoder;
                         match self.coder_map.get_mut(&build_object_name.clone()) {
                             Some(some_coder) => {
                                 if let Some(main_coder_mut) = some_coder.as_any_mut().downcast_mut::<MainCoder>() {
                                     build_path = main_coder_mut.get_path();
                                 } else {
-                                    return Err(format!("Coder for crate {} is not a MainCoder.", build_object_name));
+                                    return Err(format!("Coder for crate {} is not a MainCoder.", build_object_name).into());
                                 }
                             },
-                            None => return Err(format!("Coder for crate {} not found.", build_object_name)),
+                            None => return Err(format!("Coder for crate {} not found.", build_object_name).into()),
                         }
                     },
-                    Err(_) => {return Err(format!("Build target {} is neither a crate nor an application.", build_object_name));},
+                    Err(_) => {return Err(format!("Build target {} is neither a crate nor an application.", build_object_name).into());},
                 }
             },
         }
         
+        self.ensure_valid(build_object_name)?;
         let build_type: String;
         if let Some(build_type_value) = tokens.get(2) {
             build_type = build_type_value.to_string();
@@ -579,82 +1500,1530 @@ impl Parser {
             build_type = "debug".to_string();
         }
         
-        self.cargo_if.cargo_build(build_path.clone(), build_type.clone())?;
-        Ok(())
+        let mut report = self.cargo_if.cargo_build(build_path.clone(), build_type.clone())?;
+        self.locate_diagnostics(build_object_name, &mut report.diagnostics);
+        let payload = serde_json::to_value(&report).map_err(|e| format!("Error serializing build report: {}", e))?;
+        if !report.success {
+            let mut error = ParserError::new(ErrorCode::BuildFailed, format!("Build of {} failed with {} error(s).", build_object_name, report.error_count()));
+            error.payload = payload;
+            return Err(error);
+        }
+        Ok(payload)
+    }
+    // Type-checks the generated code of a crate or an application, test harnesses included.
+    fn parse_check(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let check_object_name = tokens.get(1).ok_or_else(|| "Missing crate or application name".to_string())?;
+        if self.check_var(check_object_name, &"crate".to_string()).is_err() {
+            self.check_var(check_object_name, &"application".to_string())?;
+        }
+        self.ensure_valid(check_object_name)?;
+        let check_path = self.project_path(check_object_name)?;
+        let mut report = self.cargo_if.cargo_check(check_path)?;
+        self.locate_diagnostics(check_object_name, &mut report.diagnostics);
+        let payload = serde_json::to_value(&report).map_err(|e| format!("Error serializing check report: {}", e))?;
+        if !report.success {
+            let mut error = ParserError::new(ErrorCode::BuildFailed, format!("Check of {} failed with {} error(s).", check_object_name, report.error_count()));
+            error.payload = payload;
+            return Err(error);
+        }
+        Ok(payload)
+    }
+    fn parse_test(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let test_object_name = tokens.get(1).ok_or_else(|| "Missing crate name".to_string())?;
+        self.check_var(test_object_name, &"crate".to_string())?;
+        self.ensure_valid(test_object_name)?;
+        let test_path = self.project_path(test_object_name)?;
+        let mut report = self.cargo_if.cargo_test(test_path)?;
+        self.locate_diagnostics(test_object_name, &mut report.diagnostics);
+        let payload = serde_json::to_value(&report).map_err(|e| format!("Error serializing test report: {}", e))?;
+        if !report.success {
+            let mut error = ParserError::new(ErrorCode::TestFailed, format!("Tests of {} failed: {} passed, {} failed.", test_object_name, report.passed, report.failed));
+            error.payload = payload;
+            return Err(error);
+        }
+        Ok(payload)
+    }
+    fn parse_install(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let crate_name = tokens.get(1).ok_or_else(|| "Missing crate name".to_string())?;
+        self.check_var(crate_name, &"crate".to_string())?;
+        let build_type = tokens.get(2).cloned().unwrap_or("debug".to_string());
+        if build_type != "debug" && build_type != "release" {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown build type {}, expected debug or release.", build_type)));
+        }
+        self.ensure_valid(crate_name)?;
+        let crate_path = self.project_path(crate_name)?;
+        let mut report = self.cargo_if.cargo_build_cdylib(crate_path.clone(), build_type)?;
+        self.locate_diagnostics(crate_name, &mut report.diagnostics);
+        let mut payload = json!({ "build": report });
+        if !report.success {
+            let mut error = ParserError::new(ErrorCode::BuildFailed, format!("Build of {} failed with {} error(s).", crate_name, report.error_count()));
+            error.payload = payload;
+            return Err(error);
+        }
+        // Dependencies such as proc-macro crates are dylibs too: only the cdylib of the crate itself is installed.
+        let artifact = report.artifacts.iter()
+            .filter(|a| a.kinds.iter().any(|kind| kind == "cdylib"))
+            .filter(|a| a.package_path().map_or(false, |package_path| Parser::same_file(&package_path, &crate_path)))
+            .flat_map(|a| a.filenames.iter())
+            .find(|f| is_library_file(Path::new(f)))
+            .ok_or_else(|| format!("Build of {} produced no cdylib of the crate.", crate_name))?;
+        let artifact_path = Path::new(artifact);
+        let mut library_manager = LibraryManager::get().lock().unwrap();
+        let libraries_path = library_manager.get_libraries_path().clone();
+        if libraries_path.is_empty() {
+            return Err("Dynamic libraries path is not configured.".to_string().into());
+        }
+        std::fs::create_dir_all(&libraries_path).map_err(|e| format!("Error creating {}: {}", libraries_path, e))?;
+        let installed_path = Path::new(&libraries_path).join(artifact_path.file_name().unwrap());
+        std::fs::copy(artifact_path, &installed_path).map_err(|e| format!("Error copying {} to {:?}: {}", artifact, installed_path, e))?;
+        let event = library_manager.load_file(&installed_path)?;
+        payload["installed"] = json!(installed_path.display().to_string());
+        payload["module"] = json!(event);
+        Ok(payload)
+    }
+    fn split_list(list: &String) -> Vec<String> {
+        list.split(",")
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+    fn parse_dependency_spec(tokens: &[String]) -> Result<DependencySpec, ParserError> {
+        let mut spec = DependencySpec::default();
+        let mut index = 0;
+        while index < tokens.len() {
+            let keyword = tokens[index].as_str();
+            if keyword == "optional" {
+                spec.optional = true;
+                index += 1;
+                continue;
+            }
+            let option_value = tokens.get(index + 1)
+                .ok_or_else(|| ParserError::new(ErrorCode::InvalidArguments, format!("Missing value for dependency option {}.", keyword)))?;
+            match keyword {
+                "version" => spec.version = Some(option_value.clone()),
+                "path" => spec.path = Some(option_value.clone()),
+                "features" => spec.features = Parser::split_list(option_value),
+                _ => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown dependency option {}, expected version, path, features or optional.", keyword))),
+            }
+            index += 2;
+        }
+        Ok(spec)
+    }
+    // manifest <project> [show]
+    // manifest <project> crate_type <type,...>
+    // manifest <project> profile <profile> <key> <value> | remove_profile <profile> [key]
+    // manifest <project> feature <feature> [<enabled,...>] | remove_feature <feature>
+    // manifest <project> dependency <name> [version <v>] [path <p>] [features <f,...>] [optional] | remove_dependency <name>
+    fn parse_manifest(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let project_name = tokens.get(1).ok_or_else(|| "Missing project name".to_string())?;
+        if project_name.contains('.') {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("{} is not a crate or an application.", project_name)));
+        }
+        self.get_object(project_name)?;
+        let coder_name = Parser::manifest_coder_name(project_name);
+        let action = tokens.get(2).cloned().unwrap_or("show".to_string());
+        if action == "show" {
+            let coder = self.get_coder_ref::<ManifestCoder>(&coder_name)?;
+            return Ok(serde_json::to_value(coder).map_err(|e| format!("Error serializing manifest: {}", e))?);
+        }
+        let mut coder: ManifestCoder = self.get_coder::<ManifestCoder>(coder_name.clone())?.clone();
+        let name = tokens.get(3).ok_or_else(|| ParserError::new(ErrorCode::InvalidArguments, format!("Missing argument for manifest {}.", action)))?;
+        match action.as_str() {
+            "crate_type" => {
+                coder.set_crate_types(Parser::split_list(name))
+                    .map_err(|e| ParserError::new(ErrorCode::InvalidArguments, e))?;
+            },
+            "profile" => {
+                let key = tokens.get(4).ok_or_else(|| ParserError::new(ErrorCode::InvalidArguments, "Missing profile setting name.".to_string()))?;
+                let setting = tokens.get(5).ok_or_else(|| ParserError::new(ErrorCode::InvalidArguments, "Missing profile setting value.".to_string()))?;
+                coder.set_profile_setting(name.clone(), key.clone(), setting.clone());
+            },
+            "remove_profile" => {
+                coder.remove_profile_setting(name, tokens.get(4))
+                    .map_err(|e| ParserError::new(ErrorCode::NotFound, e))?;
+            },
+            "feature" => {
+                let enables = tokens.get(4).map(|list| Parser::split_list(list)).unwrap_or_default();
+                coder.set_feature(name.clone(), enables);
+            },
+            "remove_feature" => {
+                coder.remove_feature(name)
+                    .map_err(|e| ParserError::new(ErrorCode::NotFound, e))?;
+            },
+            "dependency" => {
+                let spec = Parser::parse_dependency_spec(&tokens[4..])?;
+                coder.set_dependency(name.clone(), spec)
+                    .map_err(|e| ParserError::new(ErrorCode::InvalidArguments, e))?;
+            },
+            "remove_dependency" => {
+                coder.remove_dependency(name)
+                    .map_err(|e| ParserError::new(ErrorCode::NotFound, e))?;
+            },
+            _ => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown manifest action {}.", action))),
+        }
+        coder.generate()?;
+        self.coder_map.insert(coder_name, Box::new(coder));
+        Ok(Value::Null)
+    }
+    // module <crate> [show]
+    // module <crate> name|description|authors|version|release_date <value>
+    // module <crate> dependencies [<module>[@<version requirement>] ...]
+    // module <crate> provides [<name> ...]
+    fn parse_module(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let crate_name = tokens.get(1).ok_or_else(|| "Missing crate name".to_string())?;
+        self.check_var(crate_name, &"crate".to_string())?;
+        let field = tokens.get(2).cloned().unwrap_or("show".to_string());
+        if field == "show" {
+            let coder = self.get_coder_ref::<LibCoder>(crate_name)?;
+            return Ok(serde_json::to_value(coder.get_module_struct()).map_err(|e| format!("Error serializing module metadata: {}", e))?);
+        }
+        let mut coder: LibCoder = self.get_coder::<LibCoder>(crate_name.clone())?.clone();
+        let values = tokens[3..].to_vec();
+        let single_value = || -> Result<String, ParserError> {
+            match values.len() {
+                1 => Ok(values[0].clone()),
+                _ => Err(ParserError::new(ErrorCode::InvalidArguments, format!("Module {} expects exactly one value.", field))),
+            }
+        };
+        let result = match field.as_str() {
+            "name" => coder.set_module_name(single_value()?),
+            "description" => coder.set_module_description(single_value()?),
+            "authors" => coder.set_module_authors(single_value()?),
+            "version" => coder.set_module_version(single_value()?),
+            "release_date" => coder.set_module_release_date(single_value()?),
+            "dependencies" => coder.set_module_dependencies(values.clone()),
+            "provides" => coder.set_module_provides(values.clone()),
+            _ => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown module field {}.", field))),
+        };
+        result.map_err(|e| ParserError::new(ErrorCode::InvalidArguments, e))?;
+        coder.generate()?;
+        self.coder_map.insert(crate_name.clone(), Box::new(coder));
+        Ok(Value::Null)
+    }
+    fn parse_libraries(&mut self, _tokens: &Vec<String>) -> ParserFunctionReturn {
+        let library_manager = LibraryManager::get().lock().unwrap();
+        Ok(json!({
+            "libraries": library_manager.list_libraries(),
+            "issues": library_manager.get_dependency_issues(),
+            "events": library_manager.get_events(),
+            "watch_error": library_manager.get_watch_error(),
+        }))
     }
+    fn parse_reload(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let mut library_manager = LibraryManager::get().lock().unwrap();
+        let events = match tokens.get(1) {
+            Some(module_name) => vec![library_manager.reload(module_name)?],
+            None => library_manager.refresh()?,
+        };
+        Ok(serde_json::to_value(&events).map_err(|e| format!("Error serializing library events: {}", e))?)
+    }
+    fn parse_unload(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let module_name = tokens.get(1).ok_or_else(|| "Missing module name".to_string())?;
+        let event = LibraryManager::get().lock().unwrap().unload(module_name)?;
+        Ok(serde_json::to_value(&event).map_err(|e| format!("Error serializing library event: {}", e))?)
+    }
+    fn parse_deps(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let crate_name = tokens.get(1).ok_or_else(|| "Missing crate name".to_string())?;
+        self.check_var(crate_name, &"crate".to_string())?;
+        let module = self.get_coder_ref::<LibCoder>(crate_name)?.get_module_struct().clone();
+        let graph = LibraryManager::get().lock().unwrap().dependency_graph(&module);
+        Ok(serde_json::to_value(&graph).map_err(|e| format!("Error serializing dependency graph: {}", e))?)
+    }
+    fn parse_workspace(&mut self, _tokens: &Vec<String>) -> ParserFunctionReturn {
+        if self.workspace_dir.is_empty() {
+            return Err("No workspace is open.".to_string().into());
+        }
+        Ok(json!({
+            "path": Workspace::file_path(&self.workspace_dir),
+            "projects": self.workspace.projects,
+            "settings": self.workspace.settings,
+        }))
+    }
+    fn save_project_info(&self, project_name: &String) -> Result<(), String> {
+        let object = self.projects_map.get(project_name)
+            .and_then(|object_map| object_map.get(project_name))
+            .ok_or_else(|| format!("Project {} not found.", project_name))?;
+        let kind: String = object.object_category.into();
+        ProjectInfo::new(project_name.clone(), kind).save(&self.project_path(project_name)?)
+    }
+    // Differences between the memory map, the coder state and the generated files of an imported project.
+    fn import_differences(object_map: &HashMap<String, MemoryObject>, coders: &mut Vec<(String, Box<dyn Coder>)>) -> Result<Vec<String>, String> {
+        let mut differences: Vec<String> = Vec::new();
+        let find_coder = |coders: &Vec<(String, Box<dyn Coder>)>, coder_name: &String| coders.iter().position(|(name, _)| name == coder_name);
+        for (object_name, object) in object_map.iter() {
+            let split_name = object_name.split(".").collect::<Vec<&str>>();
+            let local_name = split_name.last().unwrap().to_string();
+            let known = match object.object_category {
+                ObjectCategory::StreamProcBlock => {
+                    find_coder(coders, &split_name[0].to_string())
+                        .and_then(|index| coders[index].1.as_any().downcast_ref::<LibCoder>())
+                        .map_or(false, |lib_coder| lib_coder.get_modules().contains(&local_name))
+                        && find_coder(coders, object_name).is_some()
+                },
+                ObjectCategory::Input | ObjectCategory::Output | ObjectCategory::State | ObjectCategory::Static | ObjectCategory::Parameter => {
+                    match find_coder(coders, &object.parent).and_then(|index| coders[index].1.as_any().downcast_ref::<ProcessorCoder>()) {
+                        Some(processor_coder) => match object.object_category {
+                            ObjectCategory::Input => processor_coder.get_inputs().contains_key(&local_name),
+                            ObjectCategory::Output => processor_coder.get_outputs().contains_key(&local_name),
+                            ObjectCategory::State => processor_coder.get_states().contains_key(&local_name),
+                            ObjectCategory::Static => processor_coder.get_statics().contains_key(&local_name),
+                            _ => processor_coder.get_parameters().contains_key(&local_name),
+                        },
+                        None => false,
+                    }
+                },
+                ObjectCategory::Task | ObjectCategory::StreamProc => {
+                    match find_coder(coders, &split_name[0].to_string()).and_then(|index| coders[index].1.as_any().downcast_ref::<MainCoder>()) {
+                        Some(main_coder) => match object.object_category {
+                            ObjectCategory::Task => main_coder.get_task_processors().contains_key(object_name),
+                            _ => main_coder.get_task_processors().get(&object.parent)
+                                .map_or(false, |task| task.stream_processors.contains(&local_name)),
+                        },
+                        None => false,
+                    }
+                },
+                _ => true,
+            };
+            if !known {
+                differences.push(format!("{} is in the memory map but not in the coder state.", object_name));
+            }
+        }
+        for (coder_name, coder) in coders.iter() {
+            let prefix = coder_name.clone();
+            let mut coder_objects: Vec<String> = Vec::new();
+            if let Some(lib_coder) = coder.as_any().downcast_ref::<LibCoder>() {
+                coder_objects.extend(lib_coder.get_modules().iter().map(|module| format!("{}.{}", prefix, module)));
+            } else if let Some(processor_coder) = coder.as_any().downcast_ref::<ProcessorCoder>() {
+                coder_objects.extend(processor_coder.get_inputs().keys().map(|name| format!("{}.{}", prefix, name)));
+                coder_objects.extend(processor_coder.get_outputs().keys().map(|name| format!("{}.{}", prefix, name)));
+                coder_objects.extend(processor_coder.get_states().keys().map(|name| format!("{}.{}", prefix, name)));
+                coder_objects.extend(processor_coder.get_statics().keys().map(|name| format!("{}.{}", prefix, name)));
+                coder_objects.extend(processor_coder.get_parameters().keys().map(|name| format!("{}.{}", prefix, name)));
+            } else if let Some(main_coder) = coder.as_any().downcast_ref::<MainCoder>() {
+                for (task_name, task) in main_coder.get_task_processors().iter() {
+                    coder_objects.push(task_name.clone());
+                    coder_objects.extend(task.stream_processors.iter().map(|name| format!("{}.{}", task_name, name)));
+                }
+            }
+            for object_name in coder_objects.iter() {
+                if !object_map.contains_key(object_name) {
+                    differences.push(format!("{} is in the coder state but not in the memory map.", object_name));
+                }
+            }
+        }
+        for (coder_name, coder) in coders.iter_mut() {
+            let file_path = coder.get_file_path();
+            match std::fs::read_to_string(&file_path) {
+                Ok(content) => {
+                    if coder.render()? != content {
+                        differences.push(format!("{} differs from the code generated for {}.", file_path, coder_name));
+                    }
+                },
+                Err(_) => differences.push(format!("{} is missing.", file_path)),
+            }
+        }
+        differences.sort();
+        Ok(differences)
+    }
+    // import <folder> [as <name>]
+    // import <archive.tar.gz> path <folder> [as <name>]
     pub fn parse_import(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
         let import_path = tokens.get(1).ok_or_else(|| "Missing import path".to_string())?;
-        let canonical_path = std::fs::canonicalize(&import_path).map_err(|_| "Import path does not exist.".to_string())?;
+        let mut new_name: Option<String> = None;
+        let mut destination: Option<String> = None;
+        let mut index = 2;
+        while index < tokens.len() {
+            let option_value = tokens.get(index + 1)
+                .ok_or_else(|| ParserError::new(ErrorCode::InvalidArguments, format!("Missing value for import option {}.", tokens[index])))?;
+            match tokens[index].as_str() {
+                "as" => new_name = Some(option_value.clone()),
+                "path" => destination = Some(option_value.clone()),
+                _ => return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Unknown import option {}, expected as or path.", tokens[index]))),
+            }
+            index += 2;
+        }
+        if let Some(name) = &new_name {
+            if name.is_empty() || name.contains('.') || name.contains(char::is_whitespace) {
+                return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Invalid project name {}.", name)));
+            }
+            if self.projects_map.contains_key(name) {
+                return Err(ParserError::new(ErrorCode::AlreadyExists, format!("Project {} already exists.", name)));
+            }
+        }
+        if !is_archive(import_path) {
+            if destination.is_some() {
+                return Err(ParserError::new(ErrorCode::InvalidArguments, "The path option only applies to archives.".to_string()));
+            }
+            return self.import_folder(import_path, new_name);
+        }
+        let destination = destination
+            .ok_or_else(|| ParserError::new(ErrorCode::InvalidArguments, "Importing an archive needs a destination: import <archive> path <folder>.".to_string()))?;
+        let folder = archive_folder(import_path)?;
+        let project_name = new_name.clone().unwrap_or(folder.clone());
+        if self.projects_map.contains_key(&project_name) {
+            return Err(ParserError::new(ErrorCode::AlreadyExists, format!("Project {} already exists, import it with `as <name>`.", project_name)));
+        }
+        self.track_new_folder(&format!("{}/{}", destination, folder));
+        let project_folder = extract_archive(import_path, &destination)?;
+        // An archive that cannot be imported does not leave its unpacked folder behind.
+        self.import_folder(&project_folder, new_name).map_err(|e| match std::fs::remove_dir_all(&project_folder) {
+            Ok(_) => e,
+            Err(remove_error) => ParserError { message: format!("{} Error removing {}: {}", e.message, project_folder, remove_error), ..e },
+        })
+    }
+    fn import_folder(&mut self, project_folder: &String, new_name: Option<String>) -> ParserFunctionReturn {
+        let canonical_path = std::fs::canonicalize(&project_folder)
+            .map_err(|_| ParserError::new(ErrorCode::NotFound, "Import path does not exist.".to_string()))?;
         let canonical_path_str = canonical_path.to_str().unwrap().to_string();
+        let project_name = match new_name {
+            Some(name) => name,
+            None => canonical_path.file_name()
+                .and_then(|f| f.to_str())
+                .ok_or_else(|| "Could not determine project name from import path.".to_string())?
+                .to_string(),
+        };
+        if self.projects_map.contains_key(&project_name) {
+            return Err(ParserError::new(ErrorCode::AlreadyExists, format!("Project {} already exists, import it with `as <name>`.", project_name)));
+        }
+        for other_project in self.projects_map.keys() {
+            if self.project_path(other_project).map_or(false, |other_path| Parser::same_file(&other_path, &canonical_path_str)) {
+                return Err(ParserError::new(ErrorCode::AlreadyExists, format!("{} is already imported as {}.", canonical_path_str, other_project)));
+            }
+        }
+
+        // Older files are migrated in memory by the loaders and only rewritten once the import succeeded.
+        let migrations = Parser::project_migrations(&canonical_path_str)?;
         let memory_map_file = format!("{}/.project/memory_map.json", canonical_path_str);
-        let json_string = std::fs::read_to_string(&memory_map_file).map_err(|e| format!("Error reading import file: {}", e))?;
-        let object_map: HashMap<String, MemoryObject> = serde_json::from_str(&json_string).map_err(|e| format!("Error deserializing import file: {}", e))?;
-        let project_name = Path::new(&canonical_path_str)
-            .parent()
-            .and_then(|p| p.parent())
-            .and_then(|p| p.file_name())
-            .and_then(|f| f.to_str())
-            .ok_or_else(|| "Could not determine project name from import path.".to_string())?
-            .to_string();
+        let mut object_map: HashMap<String, MemoryObject> = schema::read_versioned(&memory_map_file, schema::MEMORY_MAP)?;
+        let stored_name = object_map.iter()
+            .find(|(_, object)| object.parent.is_empty())
+            .map(|(object_name, _)| object_name.clone())
+            .ok_or_else(|| format!("{} has no crate or application object.", memory_map_file))?;
+
+        let mut coders: Vec<(String, Box<dyn Coder>)> = Vec::new();
         let main_coder_import_path = format!("{}/.project/main_coder.json", canonical_path_str);
         let lib_coder_import_path = format!("{}/.project/lib_coder.json", canonical_path_str);
         if std::path::Path::new(&main_coder_import_path).exists() {
-            let main_coder = MainCoder::load(main_coder_import_path.clone())?;
-            self.coder_map.insert(project_name.clone(), Box::new(main_coder));
+            let mut main_coder = MainCoder::load(main_coder_import_path.clone())?;
+            main_coder.rename_object(&stored_name, &project_name);
+            coders.push((project_name.clone(), Box::new(main_coder)));
         } else if std::path::Path::new(&lib_coder_import_path).exists() {
             let lib_coder = LibCoder::load(lib_coder_import_path.clone())?;
-            let modules = lib_coder.get_modules();
-            for module in modules.iter() {
+            for module in lib_coder.get_modules().iter() {
                 let processor_coder_import_path = format!("{}/.project/{}.json", canonical_path_str, module);
-                if std::path::Path::new(&processor_coder_import_path).exists() {
-                    let processor_coder = ProcessorCoder::load(processor_coder_import_path.clone())?;
-                    let processor_coder_name = format!("{}.{}", project_name.clone(), module);
-                    self.coder_map.insert(processor_coder_name, Box::new(processor_coder));
-                } else {
-                    // Erase added processor coders
-                    for mod_ in modules.iter() {
-                        let processor_coder_name = format!("{}.{}", project_name.clone(), mod_);
-                        self.coder_map.remove(&processor_coder_name);
-                    }
-                    return Err(format!("Processor coder file {} not found during import.", processor_coder_import_path));
+                if !std::path::Path::new(&processor_coder_import_path).exists() {
+                    return Err(format!("Processor coder file {} not found during import.", processor_coder_import_path).into());
                 }
+                let processor_coder = ProcessorCoder::load(processor_coder_import_path.clone())?;
+                coders.push((format!("{}.{}", project_name, module), Box::new(processor_coder)));
+            }
+            coders.push((project_name.clone(), Box::new(lib_coder)));
+        } else {
+            return Err(format!("No coder state found in {}/.project.", canonical_path_str).into());
+        }
+        let manifest_import_path = format!("{}/.project/manifest.json", canonical_path_str);
+        let mut manifest_coder = if std::path::Path::new(&manifest_import_path).exists() {
+            ManifestCoder::load(manifest_import_path.clone())?
+        } else {
+            ManifestCoder::from_manifest(canonical_path_str.clone())?
+        };
+
+        // The project may have been moved or unpacked elsewhere: point every coder to the folder it now lives in.
+        let relocated = !Parser::same_file(&manifest_coder.get_path(), &canonical_path_str)
+            || coders.iter().any(|(_, coder)| !Parser::same_file(&coder.get_path(), &canonical_path_str));
+        manifest_coder.relocate(canonical_path_str.clone());
+        for (_, coder) in coders.iter_mut() {
+            coder.relocate(canonical_path_str.clone());
+        }
+        let mut rebased: Vec<String> = Vec::new();
+        if !self.library_path.is_empty() {
+            for (dependency, dependency_path) in self.cargo_if.library_dependencies() {
+                let spec = match manifest_coder.get_dependencies().get(&dependency) {
+                    // Relative paths in Cargo.toml are relative to the crate folder.
+                    Some(spec) if spec.path.as_ref().map_or(false, |path| {
+                        let path = Path::new(&canonical_path_str).join(path).display().to_string();
+                        !Parser::same_file(&path, &dependency_path)
+                    }) => spec.clone(),
+                    _ => continue,
+                };
+                manifest_coder.set_dependency(dependency.clone(), DependencySpec { path: Some(dependency_path), ..spec })?;
+                rebased.push(dependency);
             }
-            self.coder_map.insert(project_name.clone(), Box::new(lib_coder.clone()));
+        }
+        coders.push((Parser::manifest_coder_name(&project_name), Box::new(manifest_coder)));
+
+        if stored_name != project_name {
+            object_map = object_map.into_iter()
+                .map(|(object_name, mut object)| {
+                    object.parent = rename_prefix(&object.parent, &stored_name, &project_name);
+                    (rename_prefix(&object_name, &stored_name, &project_name), object)
+                })
+                .collect();
+        }
+        if let Some(project_object) = object_map.get_mut(&project_name) {
+            project_object.object_type = canonical_path_str.clone();
+        }
+        // Stream processors migrated from memory maps before version 2 take their block type from the application.
+        let main_coder = coders.iter()
+            .find(|(coder_name, _)| *coder_name == project_name)
+            .and_then(|(_, coder)| coder.as_any().downcast_ref::<MainCoder>());
+        if let Some(main_coder) = main_coder {
+            for (object_name, object) in object_map.iter_mut() {
+                if object.object_category == ObjectCategory::StreamProc && object.object_type.is_empty() {
+                    object.object_type = main_coder.get_stream_processors().get(object_name).cloned().unwrap_or_default();
+                }
+            }
+        }
+        let mut read_back: Vec<String> = Vec::new();
+        let mut unreadable: Vec<String> = Vec::new();
+        for (_, coder) in coders.iter_mut() {
+            // A file with broken markers is reported instead of blocking the import.
+            match coder.sync_from_file() {
+                Ok(sections) => read_back.extend(sections.iter().map(|section| format!("{}: {}", coder.get_file_path(), section))),
+                Err(e) => unreadable.push(e),
+            }
+        }
+        let mut differences = Parser::import_differences(&object_map, &mut coders)?;
+        differences.extend(unreadable);
+        if !rebased.is_empty() {
+            // Cargo.toml is rewritten with the rebased paths below.
+            let manifest_file = format!("{}/Cargo.toml", canonical_path_str);
+            differences.retain(|difference| !difference.starts_with(&manifest_file));
+        }
+
+        let migrated = Parser::write_project_migrations(&canonical_path_str, &migrations)?;
+        let manifest_name = Parser::manifest_coder_name(&project_name);
+        if !rebased.is_empty() {
+            if let Some((_, manifest_coder)) = coders.iter_mut().find(|(coder_name, _)| *coder_name == manifest_name) {
+                manifest_coder.generate()?;
+            }
+        }
+        for (coder_name, coder) in coders.into_iter() {
+            self.coder_map.insert(coder_name, coder);
         }
         self.projects_map.insert(project_name.clone(), object_map);
-        Ok(())
+        self.save_memory_map(&project_name)?;
+        if relocated || stored_name != project_name {
+            // Journal snapshots refer to the former location and names.
+            self.rewrite_journal(&project_name, Journal::default())?;
+        }
+        self.save_project_info(&project_name)?;
+        self.register_project(&canonical_path_str)?;
+        Ok(json!({
+            "project": project_name,
+            "path": canonical_path_str,
+            "renamed_from": if stored_name != project_name { Some(stored_name) } else { None },
+            "relocated": relocated,
+            "rebased_dependencies": rebased,
+            "migrated": migrated,
+            "read_back": read_back,
+            "differences": differences,
+        }))
     }
-    pub fn parse_command(&mut self, command_string: String) -> ParserFunctionReturn {
-        let commands: Vec<String> = command_string
-            .split(';')
-            .map(|s| s.trim().to_string())
-            .collect();
-        for cmd in commands.iter() {
-            let mut tokens: Vec<String> = cmd
-                .split(' ')
-                .map(|s| s.trim().to_string())
-                .collect();
-            if tokens.get(0).is_none() {
-                return Err("Invalid command format.".to_string());
+    // Files saved by older versions, migrated to the current schema. Block states are the modules
+    // listed by the library, other files of the folder are left alone.
+    fn project_migrations(project_path: &String) -> Result<Vec<(String, &'static str, u32, Value)>, ParserError> {
+        let mut files: Vec<(String, &'static str)> = vec![
+            ("project.json".to_string(), schema::PROJECT),
+            ("memory_map.json".to_string(), schema::MEMORY_MAP),
+            ("main_coder.json".to_string(), schema::MAIN_CODER),
+            ("lib_coder.json".to_string(), schema::LIB_CODER),
+            ("manifest.json".to_string(), schema::MANIFEST),
+        ];
+        let mut migrations: Vec<(String, &'static str, u32, Value)> = Vec::new();
+        let mut index = 0;
+        while index < files.len() {
+            let (file_name, kind) = files[index].clone();
+            index += 1;
+            let file_path = format!("{}/.project/{}", project_path, file_name);
+            if !Path::new(&file_path).exists() {
+                continue;
             }
-            while tokens.get(0) == Some(&"".to_string()) {
-                // Remove empty strings at the start
-                let _ = tokens.remove(0);
+            let version = schema::file_version(&file_path, kind)?;
+            if version > schema::SCHEMA_VERSION {
+                return Err(ParserError::new(ErrorCode::UnsupportedVersion, format!("{} uses schema version {}, this server supports up to {}.",
+                    file_path, version, schema::SCHEMA_VERSION)));
             }
-            if tokens.is_empty() {
-                return Err("Invalid command format.".to_string());
+            if kind == schema::LIB_CODER {
+                let lib_coder = LibCoder::load(file_path.clone())?;
+                files.extend(lib_coder.get_modules().iter().map(|module| (format!("{}.json", module), schema::PROCESSOR_CODER)));
             }
-            let key_command = tokens[0].clone();
-            let parser_function: ParserFunction;
-            if let Some((_, key_value)) = self.commands_fn.get_key_value(&key_command) {
-                parser_function = *key_value;
-            } else {
-                return Err(format!("Unknown command: {}", key_command));
+            if version < schema::SCHEMA_VERSION {
+                let data: Value = schema::read_versioned(&file_path, kind)?;
+                migrations.push((file_name, kind, version, data));
             }
-            parser_function(self, &tokens)?;            
         }
-        Ok(())
+        Ok(migrations)
+    }
+    fn write_project_migrations(project_path: &String, migrations: &Vec<(String, &'static str, u32, Value)>) -> Result<Vec<Value>, String> {
+        let mut migrated: Vec<Value> = Vec::new();
+        for (file_name, kind, version, data) in migrations.iter() {
+            schema::write_versioned(&format!("{}/.project/{}", project_path, file_name), kind, data)?;
+            migrated.push(json!({ "file": file_name, "from_version": version, "to_version": schema::SCHEMA_VERSION }));
+        }
+        Ok(migrated)
+    }
+    fn parse_export(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let project_name = tokens.get(1).ok_or_else(|| "Missing project name".to_string())?;
+        let archive = tokens.get(2).ok_or_else(|| "Missing archive path".to_string())?;
+        if project_name.contains('.') {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("{} is not a crate or an application.", project_name)));
+        }
+        self.get_object(project_name)?;
+        if !is_archive(archive) {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, format!("Archive {} must end with .tar.gz or .tgz.", archive)));
+        }
+        let project_path = self.project_path(project_name)?;
+        self.save_memory_map(project_name)?;
+        self.save_project_info(project_name)?;
+        export_archive(&project_path, archive)?;
+        Ok(json!({ "project": project_name, "archive": archive }))
+    }
+    fn object_to_json(object_name: &String, object: &MemoryObject) -> Value {
+        json!({
+            "name": object_name,
+            "parent": object.parent,
+            "category": <ObjectCategory as Into<String>>::into(object.object_category),
+            "type": object.object_type,
+            "value": object.object_value,
+            "limits": object.object_limits,
+        })
+    }
+    fn sorted_children<'a>(object_map: &'a HashMap<String, MemoryObject>, parent: &String) -> Vec<(&'a String, &'a MemoryObject)> {
+        let mut children = object_map.iter()
+            .filter(|(k, v)| v.parent == *parent && *k != parent)
+            .collect::<Vec<(&String, &MemoryObject)>>();
+        children.sort_by(|a, b| a.0.cmp(b.0));
+        children
+    }
+    fn object_tree(object_map: &HashMap<String, MemoryObject>, object_name: &String) -> Value {
+        let mut node = match object_map.get(object_name) {
+            Some(object) => Parser::object_to_json(object_name, object),
+            None => json!({ "name": object_name }),
+        };
+        let children = Parser::sorted_children(object_map, object_name)
+            .into_iter()
+            .map(|(child_name, _)| Parser::object_tree(object_map, child_name))
+            .collect::<Vec<Value>>();
+        node["children"] = Value::Array(children);
+        node
+    }
+    fn parse_list(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        match tokens.get(1) {
+            None => {
+                let mut project_names = self.projects_map.keys().collect::<Vec<&String>>();
+                project_names.sort();
+                let projects = project_names.into_iter()
+                    .map(|project_name| {
+                        match self.projects_map[project_name].get(project_name) {
+                            Some(object) => Parser::object_to_json(project_name, object),
+                            None => json!({ "name": project_name }),
+                        }
+                    })
+                    .collect::<Vec<Value>>();
+                Ok(Value::Array(projects))
+            },
+            Some(object_name) => {
+                self.get_object(object_name)?;
+                let split_name = object_name.split(".").collect::<Vec<&str>>();
+                let object_map = &self.projects_map[split_name[0]];
+                let children = Parser::sorted_children(object_map, object_name)
+                    .into_iter()
+                    .map(|(child_name, child)| Parser::object_to_json(child_name, child))
+                    .collect::<Vec<Value>>();
+                Ok(Value::Array(children))
+            },
+        }
+    }
+    fn parse_show(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let object_name = tokens.get(1).ok_or_else(|| "Missing object name".to_string())?;
+        let object = self.get_object(object_name)?;
+        let mut result = Parser::object_to_json(object_name, object);
+        let split_name = object_name.split(".").collect::<Vec<&str>>();
+        match object.object_category {
+            ObjectCategory::Crate => {
+                let lib_coder = self.get_coder_ref::<LibCoder>(object_name)?;
+                result["modules"] = json!(lib_coder.get_modules());
+            },
+            ObjectCategory::StreamProcBlock => {
+                let coder = self.get_coder_ref::<ProcessorCoder>(object_name)?;
+                result["inputs"] = json!(coder.get_inputs());
+                result["outputs"] = json!(coder.get_outputs());
+                result["states"] = json!(coder.get_states());
+                result["statics"] = json!(coder.get_statics());
+                result["parameters"] = json!(coder.get_parameters());
+            },
+            ObjectCategory::Application => {
+                let main_coder = self.get_coder_ref::<MainCoder>(object_name)?;
+                result["tasks"] = json!(main_coder.get_task_processors());
+                result["stream_processors"] = json!(main_coder.get_stream_processors());
+                result["connections"] = json!(main_coder.get_connections());
+                result["settings"] = json!(main_coder.get_settings());
+            },
+            ObjectCategory::Task => {
+                let main_coder = self.get_coder_ref::<MainCoder>(&split_name[0].to_string())?;
+                result["stream_processors"] = json!(main_coder.get_task_processors().get(object_name).map(|task| task.stream_processors.clone()).unwrap_or_default());
+            },
+            ObjectCategory::StreamProc => {
+                let main_coder = self.get_coder_ref::<MainCoder>(&split_name[0].to_string())?;
+                let connections = main_coder.get_connections().iter()
+                    .filter(|c| c.from_processor == *object_name || c.to_processor == *object_name)
+                    .collect::<Vec<_>>();
+                let settings = main_coder.get_settings().iter()
+                    .filter(|s| s.processor_name == *object_name)
+                    .collect::<Vec<_>>();
+                result["connections"] = json!(connections);
+                result["settings"] = json!(settings);
+            },
+            _ => {},
+        }
+        Ok(result)
+    }
+    fn parse_tree(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        match tokens.get(1) {
+            None => {
+                let mut project_names = self.projects_map.keys().collect::<Vec<&String>>();
+                project_names.sort();
+                let trees = project_names.into_iter()
+                    .map(|project_name| Parser::object_tree(&self.projects_map[project_name], project_name))
+                    .collect::<Vec<Value>>();
+                Ok(Value::Array(trees))
+            },
+            Some(object_name) => {
+                self.get_object(object_name)?;
+                let split_name = object_name.split(".").collect::<Vec<&str>>();
+                Ok(Parser::object_tree(&self.projects_map[split_name[0]], object_name))
+            },
+        }
+    }
+    fn parse_undo(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let project_name = tokens.get(1).ok_or_else(|| "Missing project name".to_string())?;
+        self.get_object(project_name)?;
+        // The entry stays in the journal until its snapshot has been restored.
+        let entry = self.journal_mut(project_name)?.undo.last().cloned()
+            .ok_or_else(|| format!("Nothing to undo in project {}.", project_name))?;
+        let current = self.take_snapshot(project_name)?;
+        let previous = entry.delta.apply(current.clone());
+        let redo_entry = JournalEntry { command: entry.command.clone(), delta: ProjectDelta::between(&current, &previous) };
+        self.restore_snapshot(project_name, previous)?;
+        self.journal_mut(project_name)?.undone(redo_entry);
+        self.save_journal(project_name)?;
+        Ok(json!({ "undone": entry.command }))
+    }
+    fn parse_redo(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let project_name = tokens.get(1).ok_or_else(|| "Missing project name".to_string())?;
+        self.get_object(project_name)?;
+        let entry = self.journal_mut(project_name)?.redo.last().cloned()
+            .ok_or_else(|| format!("Nothing to redo in project {}.", project_name))?;
+        let current = self.take_snapshot(project_name)?;
+        let next = entry.delta.apply(current.clone());
+        let undo_entry = JournalEntry { command: entry.command.clone(), delta: ProjectDelta::between(&current, &next) };
+        self.restore_snapshot(project_name, next)?;
+        self.journal_mut(project_name)?.redone(undo_entry);
+        self.save_journal(project_name)?;
+        Ok(json!({ "redone": entry.command }))
+    }
+    fn parse_begin(&mut self, _tokens: &Vec<String>) -> ParserFunctionReturn {
+        if self.transaction.as_ref().map_or(false, |t| t.explicit) {
+            return Err("A transaction is already active.".to_string().into());
+        }
+        // Commands already run in the current batch are kept: the transaction starts here.
+        self.transaction = Some(Transaction::new(true, self.projects_map.keys().cloned().collect()));
+        Ok(Value::Null)
+    }
+    fn parse_commit(&mut self, _tokens: &Vec<String>) -> ParserFunctionReturn {
+        if !self.transaction.as_ref().map_or(false, |t| t.explicit) {
+            return Err("No active transaction to commit.".to_string().into());
+        }
+        self.transaction = None;
+        Ok(Value::Null)
+    }
+    fn parse_rollback(&mut self, _tokens: &Vec<String>) -> ParserFunctionReturn {
+        if !self.transaction.as_ref().map_or(false, |t| t.explicit) {
+            return Err("No active transaction to roll back.".to_string().into());
+        }
+        Ok(self.rollback_transaction()?)
+    }
+    pub fn execute_command(&mut self, tokens: &Vec<String>) -> ParserFunctionReturn {
+        let key_command = tokens.get(0).ok_or_else(|| ParserError::new(ErrorCode::InvalidArguments, "Invalid command format.".to_string()))?;
+        let parser_function: ParserFunction;
+        if let Some((_, key_value)) = self.commands_fn.get_key_value(key_command) {
+            parser_function = *key_value;
+        } else {
+            return Err(ParserError::new(ErrorCode::UnknownCommand, format!("Unknown command: {}", key_command)));
+        }
+        if let Some(project_name) = self.command_project(tokens) {
+            // Dry runs and model queries only report: they neither read edits back nor save the coders.
+            if !Parser::is_dry_run(tokens) && !Parser::is_model_query(tokens) {
+                self.sync_project_sources(&project_name)?;
+            }
+            self.track_project(&project_name)?;
+        }
+        let journaled_project = self.journaled_project(tokens);
+        let snapshot = match &journaled_project {
+            Some(project_name) => Some(self.take_snapshot(project_name)?),
+            None => None,
+        };
+        let payload = parser_function(self, tokens)?;
+        if let (Some(project_name), Some(snapshot)) = (journaled_project, snapshot) {
+            let after = self.take_snapshot(&project_name)?;
+            self.journal_mut(&project_name)?.record(tokens.join(" "), &snapshot, &after);
+            self.save_journal(&project_name)?;
+        }
+        Ok(payload)
+    }
+    // Runs a batch of commands atomically: on failure, every project touched since the batch
+    // (or the explicit transaction) started is restored before the error is returned.
+    pub fn run_batch(&mut self, commands: &Vec<Vec<String>>) -> ParserFunctionReturn {
+        let in_transaction = self.transaction.as_ref().map_or(false, |t| t.explicit);
+        if (in_transaction || commands.len() > 1) && commands.iter().any(|tokens| Parser::is_project_deletion(tokens)) {
+            return Err(ParserError::new(ErrorCode::InvalidArguments, "Projects can only be deleted by a single command outside a transaction.".to_string()));
+        }
+        let mut payloads: Vec<Value> = Vec::new();
+        for tokens in commands.iter() {
+            if self.transaction.is_none() {
+                self.transaction = Some(Transaction::new(false, self.projects_map.keys().cloned().collect()));
+            }
+            match self.execute_command(tokens) {
+                Ok(payload) => payloads.push(payload),
+                Err(mut e) => {
+                    match self.rollback_transaction() {
+                        Ok(_) => e.message = format!("{} Changes rolled back.", e.message),
+                        Err(rollback_error) => e.message = format!("{} Rollback failed: {}", e.message, rollback_error),
+                    }
+                    return Err(e);
+                },
+            }
+        }
+        if !self.transaction.as_ref().map_or(false, |t| t.explicit) {
+            self.transaction = None;
+        }
+        Ok(Value::Array(payloads))
+    }
+    pub fn parse_command(&mut self, command_string: String) -> ParserFunctionReturn {
+        let commands = tokenize(&command_string)
+            .map_err(|e| ParserError::new(ErrorCode::SyntaxError, e))?;
+        self.run_batch(&commands)
     }
 }
 
 static PARSER: OnceLock<Mutex<Parser>> = OnceLock::new();
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{ConnectionSpec, SettingSpec};
+
+    fn copy_dir(from: &Path, to: &Path) {
+        std::fs::create_dir_all(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir(&entry.path(), &to.join(entry.file_name()));
+            } else {
+                std::fs::copy(entry.path(), to.join(entry.file_name())).unwrap();
+            }
+        }
+    }
+
+    // Copy of a project saved by an older version, in a folder of its own for each test.
+    fn legacy_project(project: &str, test_name: &str) -> String {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/coder/tests/fixtures/legacy").join(project);
+        let root = std::env::temp_dir().join(format!("kappa_{}_{}", test_name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        copy_dir(&fixture, &root.join(project));
+        root.join(project).to_string_lossy().to_string()
+    }
+
+    fn project_file(project_path: &String, file_name: &str) -> String {
+        std::fs::read_to_string(format!("{}/.project/{}", project_path, file_name)).unwrap()
+    }
+
+    fn import(parser: &mut Parser, project_path: &String) -> ParserFunctionReturn {
+        parser.parse_import(&vec!["import".to_string(), project_path.clone()])
+    }
+
+    fn command(command: &str) -> Vec<String> {
+        crate::lexer::tokenize(command).unwrap().remove(0)
+    }
+
+    fn names(objects: &Value) -> Vec<&str> {
+        objects.as_array().unwrap().iter().map(|o| o["name"].as_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn import_rewrites_legacy_files_in_current_schema() {
+        let project_path = legacy_project("dsp", "import_migration");
+        let notes = project_file(&project_path, "notes.json");
+        let mut parser = Parser::new();
+        let result = import(&mut parser, &project_path).unwrap();
+        let mut migrated = result["migrated"].as_array().unwrap().iter()
+            .map(|m| m["file"].as_str().unwrap().to_string())
+            .collect::<Vec<String>>();
+        migrated.sort();
+        assert_eq!(migrated, vec!["Gain.json", "lib_coder.json", "manifest.json", "memory_map.json", "project.json"]);
+        for (file_name, kind) in [("Gain.json", schema::PROCESSOR_CODER), ("lib_coder.json", schema::LIB_CODER), ("manifest.json", schema::MANIFEST),
+            ("memory_map.json", schema::MEMORY_MAP), ("project.json", schema::PROJECT)] {
+            let file_path = format!("{}/.project/{}", project_path, file_name);
+            assert_eq!(schema::file_version(&file_path, kind).unwrap(), schema::SCHEMA_VERSION, "{}", file_name);
+        }
+        // Files that are not block states of the library are not project files.
+        assert_eq!(project_file(&project_path, "notes.json"), notes);
+        assert_eq!(parser.get_coder::<LibCoder>("dsp".to_string()).unwrap().get_module_struct().name, "dsp");
+    }
+
+    #[test]
+    fn failed_import_leaves_legacy_files_untouched() {
+        let project_path = legacy_project("dsp", "import_failure");
+        std::fs::remove_file(format!("{}/.project/Gain.json", project_path)).unwrap();
+        let stored = ["lib_coder.json", "manifest.json", "memory_map.json", "project.json"].map(|file_name| project_file(&project_path, file_name));
+        let mut parser = Parser::new();
+        let error = import(&mut parser, &project_path).unwrap_err();
+        assert!(error.message.contains("Gain.json not found"), "{}", error.message);
+        assert_eq!(["lib_coder.json", "manifest.json", "memory_map.json", "project.json"].map(|file_name| project_file(&project_path, file_name)), stored);
+        assert!(!parser.projects_map.contains_key("dsp"));
+    }
+
+    #[test]
+    fn import_refuses_newer_schema_without_changes() {
+        let project_path = legacy_project("dsp", "import_newer");
+        let project_info = json!({ "schema_version": schema::SCHEMA_VERSION + 1, "kind": schema::PROJECT, "data": { "name": "dsp", "kind": "crate" } });
+        std::fs::write(format!("{}/.project/project.json", project_path), project_info.to_string()).unwrap();
+        let lib_coder = project_file(&project_path, "lib_coder.json");
+        let mut parser = Parser::new();
+        let error = import(&mut parser, &project_path).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::UnsupportedVersion));
+        assert_eq!(project_file(&project_path, "lib_coder.json"), lib_coder);
+    }
+
+    #[test]
+    fn rollback_removes_folders_created_by_the_transaction() {
+        let root = std::env::temp_dir().join(format!("kappa_rollback_{}", std::process::id()));
+        let existing = root.join("existing").to_string_lossy().to_string();
+        let created = root.join("created").to_string_lossy().to_string();
+        std::fs::create_dir_all(&existing).unwrap();
+        let mut parser = Parser::new();
+        parser.transaction = Some(Transaction::new(false, Vec::new()));
+        parser.track_new_folder(&existing);
+        parser.track_new_folder(&created);
+        std::fs::create_dir_all(format!("{}/src", created)).unwrap();
+        let report = parser.rollback_transaction().unwrap();
+        assert_eq!(report["removed_folders"], json!([created]));
+        assert!(!Path::new(&created).exists());
+        assert!(Path::new(&existing).exists());
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn undo_keeps_entry_when_restore_fails() {
+        let project_path = legacy_project("dsp", "undo_restore");
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let project_name = "dsp".to_string();
+        let snapshot = parser.take_snapshot(&project_name).unwrap();
+        let mut changed = snapshot.clone();
+        changed.memory_map.get_mut("dsp.Gain").unwrap().object_value = "Changed".to_string();
+        parser.journal_mut(&project_name).unwrap().record("create input dsp.Gain.z type f32".to_string(), &snapshot, &changed);
+        // The fixture has neither src nor Cargo.toml, so the generated files cannot be written.
+        let undo = vec!["undo".to_string(), project_name.clone()];
+        assert!(parser.parse_undo(&undo).is_err());
+        assert_eq!(parser.journal_mut(&project_name).unwrap().undo.len(), 1);
+        std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+        std::fs::write(format!("{}/Cargo.toml", project_path), "[package]\nname = \"dsp\"\nversion = \"0.1.0\"\n").unwrap();
+        parser.parse_undo(&undo).unwrap();
+        let journal = parser.journal_mut(&project_name).unwrap();
+        assert_eq!((journal.undo.len(), journal.redo.len()), (0, 1));
+    }
+
+    #[test]
+    fn rejected_archive_import_removes_unpacked_folder() {
+        let project_path = legacy_project("dsp", "archive_newer");
+        let project_info = json!({ "schema_version": schema::SCHEMA_VERSION + 1, "kind": schema::PROJECT, "data": { "name": "dsp", "kind": "crate" } });
+        std::fs::write(format!("{}/.project/project.json", project_path), project_info.to_string()).unwrap();
+        let archive = format!("{}.tar.gz", project_path);
+        export_archive(&project_path, &archive).unwrap();
+        let destination = format!("{}/unpacked", Path::new(&project_path).parent().unwrap().display());
+        let mut parser = Parser::new();
+        let error = parser.parse_import(&vec!["import".to_string(), archive.clone(), "path".to_string(), destination.clone()]).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::UnsupportedVersion));
+        assert!(!Path::new(&format!("{}/dsp", destination)).exists());
+    }
+
+    #[test]
+    fn connect_rejects_second_source_for_an_input() {
+        let project_path = legacy_project("app", "connect_input");
+        std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let connect = |source: &str, target: &str| ["connect", source, target, "type", "f32"].map(|t| t.to_string()).to_vec();
+        parser.parse_connect(&connect("app.main.gain.out", "app.main.gain.in")).unwrap();
+        let error = parser.parse_connect(&connect("app.main.gain.other", "app.main.gain.in")).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::AlreadyExists));
+        assert!(error.message.contains("app.main.gain.out"), "{}", error.message);
+        assert_eq!(parser.get_coder::<MainCoder>("app".to_string()).unwrap().get_connections().len(), 1);
+    }
+
+    #[test]
+    fn import_takes_stream_processor_types_from_the_application() {
+        let project_path = legacy_project("app", "import_stream_proc");
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let stream_proc = parser.get_object(&"app.main.gain".to_string()).unwrap();
+        assert_eq!((stream_proc.parent.as_str(), stream_proc.object_type.as_str()), ("app.main", "dsp.Gain"));
+        let memory_map: Value = serde_json::from_str(&project_file(&project_path, "memory_map.json")).unwrap();
+        assert_eq!(memory_map["data"]["app.main.gain"]["object_type"], "dsp.Gain");
+    }
+
+    #[test]
+    fn apply_updates_description_and_refuses_path_for_existing_application() {
+        let project_path = legacy_project("app", "apply_description");
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let mut pipeline = Pipeline::from_application(&"app".to_string(), &"Gain chain".to_string(), parser.get_coder_ref::<MainCoder>(&"app".to_string()).unwrap());
+        let pipeline_file = format!("{}/pipeline.toml", project_path);
+        pipeline.save(&pipeline_file).unwrap();
+        let apply = vec!["apply".to_string(), pipeline_file.clone()];
+        let result = parser.parse_apply(&apply).unwrap();
+        assert_eq!(result["changes"], json!([]));
+        assert_eq!(result["description_changed"], true);
+        assert_eq!(parser.get_object(&"app".to_string()).unwrap().object_value, "Gain chain");
+        assert_eq!(parser.parse_apply(&apply).unwrap()["description_changed"], false);
+
+        pipeline.description = "Other".to_string();
+        pipeline.save(&pipeline_file).unwrap();
+        let error = parser.parse_apply(&vec!["apply".to_string(), pipeline_file, "path".to_string(), "/tmp".to_string()]).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::InvalidArguments));
+        assert_eq!(parser.get_object(&"app".to_string()).unwrap().object_value, "Gain chain");
+    }
+
+    #[test]
+    fn block_deletion_fails_when_its_files_cannot_be_removed() {
+        let project_path = legacy_project("dsp", "delete_block");
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let save_path = format!("{}/.project/Gain.json", project_path);
+        std::fs::remove_file(&save_path).unwrap();
+        std::fs::create_dir(&save_path).unwrap();
+        let error = parser.parse_delete(&vec!["delete".to_string(), "dsp.Gain".to_string()]).unwrap_err();
+        assert!(error.message.contains("Error removing"), "{}", error.message);
+        assert!(parser.get_coder::<ProcessorCoder>("dsp.Gain".to_string()).is_ok());
+    }
+
+    #[test]
+    fn invalid_crate_metadata_is_refused_before_cargo_runs() {
+        let root = std::env::temp_dir().join(format!("kappa_crate_metadata_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        let folder = root.to_string_lossy().to_string();
+        let mut parser = Parser::new();
+        let create = ["create", "crate", "dsp", "path", folder.as_str(), "metadata", "Signal\0blocks"].map(|t| t.to_string()).to_vec();
+        let error = parser.create_crate(&create).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::InvalidArguments));
+        assert!(!root.join("dsp").exists());
+        assert!(!parser.projects_map.contains_key("dsp"));
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    // The `dsp` crate and the `app` application of the legacy fixtures, imported side by side.
+    fn imported_projects(test_name: &str) -> Parser {
+        let mut parser = Parser::new();
+        for project in ["dsp", "app"] {
+            import(&mut parser, &legacy_project(project, &format!("{}_{}", test_name, project))).unwrap();
+        }
+        parser
+    }
+
+    #[test]
+    fn list_returns_projects_or_the_children_of_an_object() {
+        let mut parser = imported_projects("list");
+        let projects = parser.parse_list(&command("list")).unwrap();
+        assert_eq!(names(&projects), vec!["app", "dsp"]);
+        assert_eq!((projects[0]["category"].as_str(), projects[1]["value"].as_str()), (Some("application"), Some("Signal blocks")));
+        assert_eq!(names(&parser.parse_list(&command("list dsp.Gain")).unwrap()), vec!["dsp.Gain.gain", "dsp.Gain.x", "dsp.Gain.y"]);
+        let processors = parser.parse_list(&command("list app.main")).unwrap();
+        assert_eq!(names(&processors), vec!["app.main.gain"]);
+        assert_eq!(processors[0]["type"], "dsp.Gain");
+        assert!(matches!(parser.parse_list(&command("list dsp.Mixer")).unwrap_err().code, ErrorCode::NotFound));
+    }
+
+    #[test]
+    fn show_adds_the_coder_state_of_the_object() {
+        let mut parser = imported_projects("show");
+        let crate_object = parser.parse_show(&command("show dsp")).unwrap();
+        assert_eq!(crate_object["modules"], json!(["Gain"]));
+        let block = parser.parse_show(&command("show dsp.Gain")).unwrap();
+        assert_eq!((&block["inputs"], &block["outputs"]), (&json!({ "x": "f32" }), &json!({ "y": "f32" })));
+        assert_eq!(block["parameters"]["gain"]["limits"], json!({ "min": "0.0", "max": "10.0" }));
+        let task = parser.parse_show(&command("show app.main")).unwrap();
+        assert_eq!(task["stream_processors"], json!(["gain"]));
+        let processor = parser.parse_show(&command("show app.main.gain")).unwrap();
+        assert_eq!((processor["parent"].as_str(), processor["type"].as_str()), (Some("app.main"), Some("dsp.Gain")));
+        assert_eq!(processor["settings"][0]["settable_name"], "app.main.gain.gain");
+        let parameter = parser.parse_show(&command("show dsp.Gain.gain")).unwrap();
+        assert_eq!((parameter["category"].as_str(), parameter["limits"].as_str()), (Some("parameter"), Some("0.0,10.0")));
+    }
+
+    #[test]
+    fn tree_nests_objects_under_their_parent() {
+        let mut parser = imported_projects("tree");
+        let tree = parser.parse_tree(&command("tree app")).unwrap();
+        assert_eq!(tree["name"], "app");
+        assert_eq!(names(&tree["children"]), vec!["app.main"]);
+        assert_eq!(names(&tree["children"][0]["children"]), vec!["app.main.gain"]);
+        assert_eq!(tree["children"][0]["children"][0]["children"], json!([]));
+        let trees = parser.parse_tree(&command("tree")).unwrap();
+        assert_eq!(names(&trees), vec!["app", "dsp"]);
+        assert_eq!(names(&trees[1]["children"][0]["children"]), vec!["dsp.Gain.gain", "dsp.Gain.x", "dsp.Gain.y"]);
+    }
+
+    #[test]
+    fn diagnostics_are_located_in_generated_sections() {
+        let project_path = legacy_project("dsp", "locate_diagnostics");
+        std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let block = parser.get_coder::<ProcessorCoder>("dsp.Gain".to_string()).unwrap();
+        block.generate().unwrap();
+        let span = block.get_sections().iter().find(|span| span.end_line > span.start_line).unwrap().clone();
+        let diagnostic = |file: String, line: usize| BuildDiagnostic {
+            level: "error".to_string(), message: "mismatched types".to_string(), code: None, file: Some(file), line: Some(line),
+            column: Some(1), rendered: None, object: None, section: None,
+        };
+        let mut diagnostics = vec![diagnostic(block.get_file_path(), span.end_line), diagnostic(format!("{}/src/other.rs", project_path), 1)];
+        parser.locate_diagnostics(&"dsp".to_string(), &mut diagnostics);
+        assert_eq!((diagnostics[0].object.as_deref(), diagnostics[0].section.as_deref()), (Some("dsp.Gain"), Some(span.section.as_str())));
+        assert_eq!((diagnostics[1].object.as_deref(), diagnostics[1].section.as_deref()), (None, None));
+    }
+
+    #[test]
+    fn journal_appends_what_each_command_changed() {
+        let project_path = legacy_project("dsp", "journal_append");
+        std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+        std::fs::write(format!("{}/Cargo.toml", project_path), "[package]\nname = \"dsp\"\nversion = \"0.1.0\"\n").unwrap();
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let journal_file = format!("{}/.project/journal.jsonl", project_path);
+        parser.parse_command("create input dsp.Gain.z type f32".to_string()).unwrap();
+        let first = std::fs::read_to_string(&journal_file).unwrap();
+        parser.parse_command("create input dsp.Gain.w type f32".to_string()).unwrap();
+        let records = std::fs::read_to_string(&journal_file).unwrap();
+        assert!(records.starts_with(&first));
+        assert_eq!(records.lines().count(), first.lines().count() + 1);
+
+        let entry = parser.journal_mut(&"dsp".to_string()).unwrap().undo[1].clone();
+        assert_eq!(entry.delta.coders.keys().collect::<Vec<_>>(), vec!["dsp.Gain"]);
+        assert_eq!(entry.delta.memory_map.keys().collect::<Vec<_>>(), vec!["dsp.Gain.w"]);
+        assert!(entry.delta.memory_map["dsp.Gain.w"].is_none());
+
+        // A journal read back from the file undoes like the one in memory.
+        parser.journals.clear();
+        parser.parse_command("undo dsp".to_string()).unwrap();
+        assert!(parser.get_object(&"dsp.Gain.w".to_string()).is_err());
+        assert!(parser.get_object(&"dsp.Gain.z".to_string()).is_ok());
+        assert_eq!(std::fs::read_to_string(&journal_file).unwrap().lines().count(), first.lines().count() + 2);
+        parser.journals.clear();
+        parser.parse_command("redo dsp".to_string()).unwrap();
+        assert!(parser.get_object(&"dsp.Gain.w".to_string()).is_ok());
+    }
+
+    #[test]
+    fn model_queries_leave_edited_sources_alone() {
+        let project_path = legacy_project("dsp", "query_sync");
+        std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let block = parser.get_coder::<ProcessorCoder>("dsp.Gain".to_string()).unwrap();
+        block.generate().unwrap();
+        let file_path = block.get_file_path();
+        let marker = format!("{} ProcessBody", coder::coder::REGION_BEGIN);
+        let edited = std::fs::read_to_string(&file_path).unwrap().lines()
+            .map(|line| if line.trim() == marker { format!("{}\n        let edited = true;", line) } else { line.to_string() })
+            .collect::<Vec<String>>().join("\n");
+        std::fs::write(&file_path, edited).unwrap();
+        parser.parse_command("manifest dsp show; manifest dsp; module dsp show".to_string()).unwrap();
+        assert_eq!(parser.sync_project_sources(&"dsp".to_string()).unwrap(), vec![format!("{}: ProcessBody", file_path)]);
+    }
+
+    #[test]
+    fn import_detects_name_clashes() {
+        let project_path = legacy_project("dsp", "import_clash");
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let error = import(&mut parser, &project_path).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::AlreadyExists));
+        assert!(error.message.contains("Project dsp already exists, import it with `as <name>`"), "{}", error.message);
+        let error = parser.parse_import(&command(&format!("import {} as copy", project_path))).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::AlreadyExists));
+        assert!(error.message.ends_with("is already imported as dsp."), "{}", error.message);
+
+        // Another folder of the same name is imported under a new name.
+        let other_path = legacy_project("dsp", "import_clash_other");
+        let error = parser.parse_import(&command(&format!("import {} as dsp", other_path))).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::AlreadyExists));
+        assert!(matches!(parser.parse_import(&command(&format!("import {} as a.b", other_path))).unwrap_err().code, ErrorCode::InvalidArguments));
+        let report = parser.parse_import(&command(&format!("import {} as copy", other_path))).unwrap();
+        assert_eq!((report["project"].as_str(), report["renamed_from"].as_str()), (Some("copy"), Some("dsp")));
+        assert_eq!(parser.get_object(&"copy.Gain.gain".to_string()).unwrap().parent, "copy.Gain");
+        assert!(parser.get_coder::<ProcessorCoder>("copy.Gain".to_string()).is_ok());
+        assert!(parser.get_coder::<ProcessorCoder>("dsp.Gain".to_string()).is_ok());
+    }
+
+    #[test]
+    fn import_reports_differences_between_memory_map_coders_and_files() {
+        let project_path = legacy_project("dsp", "import_differences");
+        let memory_map_file = format!("{}/.project/memory_map.json", project_path);
+        let mut memory_map: Value = serde_json::from_str(&project_file(&project_path, "memory_map.json")).unwrap();
+        let objects = memory_map.as_object_mut().unwrap();
+        objects.remove("dsp.Gain.gain");
+        objects.insert("dsp.Gain.extra".to_string(), json!({ "parent": "dsp.Gain", "object_category": "Input", "object_type": "f32", "object_value": "", "object_limits": "" }));
+        std::fs::write(&memory_map_file, memory_map.to_string()).unwrap();
+        let mut parser = Parser::new();
+        let report = import(&mut parser, &project_path).unwrap();
+        let canonical_path = report["path"].as_str().unwrap();
+        assert_eq!(report["differences"], json!([
+            format!("{}/Cargo.toml is missing.", canonical_path),
+            format!("{}/src/gain.rs is missing.", canonical_path),
+            format!("{}/src/lib.rs is missing.", canonical_path),
+            "dsp.Gain.extra is in the memory map but not in the coder state.",
+            "dsp.Gain.gain is in the coder state but not in the memory map.",
+        ]));
+    }
+
+    #[test]
+    fn exported_archive_imports_elsewhere() {
+        let project_path = legacy_project("dsp", "export_archive");
+        std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        let archive = format!("{}.tar.gz", project_path);
+        assert!(matches!(parser.parse_export(&command(&format!("export dsp {}.zip", project_path))).unwrap_err().code, ErrorCode::InvalidArguments));
+        parser.parse_export(&command(&format!("export dsp {}", archive))).unwrap();
+        let destination = format!("{}/unpacked", Path::new(&project_path).parent().unwrap().display());
+        std::fs::create_dir_all(&destination).unwrap();
+        let report = parser.parse_import(&command(&format!("import {} path {} as copy", archive, destination))).unwrap();
+        assert_eq!(report["relocated"], true);
+        assert!(Parser::same_file(&report["path"].as_str().unwrap().to_string(), &format!("{}/dsp", destination)));
+        let copy = parser.get_coder::<ProcessorCoder>("copy.Gain".to_string()).unwrap();
+        assert!(Parser::same_file(&copy.get_file_path(), &format!("{}/dsp/src/gain.rs", destination)));
+    }
+
+    #[test]
+    fn saved_project_files_carry_the_current_schema() {
+        let project_path = legacy_project("dsp", "schema_saved");
+        std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        parser.parse_command("create input dsp.Gain.z type f32".to_string()).unwrap();
+        let envelope: Value = serde_json::from_str(&project_file(&project_path, "Gain.json")).unwrap();
+        assert_eq!((envelope["schema_version"].as_u64(), envelope["kind"].as_str()), (Some(schema::SCHEMA_VERSION as u64), Some(schema::PROCESSOR_CODER)));
+        assert!(envelope["data"]["inputs"]["z"].is_string());
+
+        // Files already in the current schema are read as they are.
+        let mut parser = Parser::new();
+        let report = import(&mut parser, &project_path).unwrap();
+        assert_eq!(report["migrated"], json!([]));
+        assert!(parser.get_object(&"dsp.Gain.z".to_string()).is_ok());
+        assert!(parser.get_coder::<ProcessorCoder>("dsp.Gain".to_string()).unwrap().get_inputs().contains_key("z"));
+    }
+
+    #[test]
+    fn legacy_application_is_migrated_on_import() {
+        let project_path = legacy_project("app", "schema_application");
+        let mut parser = Parser::new();
+        let report = import(&mut parser, &project_path).unwrap();
+        let mut migrated = report["migrated"].as_array().unwrap().iter()
+            .map(|m| (m["file"].as_str().unwrap(), m["from_version"].as_u64().unwrap(), m["to_version"].as_u64().unwrap()))
+            .collect::<Vec<_>>();
+        migrated.sort();
+        let current = schema::SCHEMA_VERSION as u64;
+        assert_eq!(migrated, vec![("main_coder.json", 0, current), ("manifest.json", 0, current), ("memory_map.json", 0, current)]);
+        for (file_name, kind) in [("main_coder.json", schema::MAIN_CODER), ("manifest.json", schema::MANIFEST), ("memory_map.json", schema::MEMORY_MAP), ("project.json", schema::PROJECT)] {
+            assert_eq!(schema::file_version(&format!("{}/.project/{}", project_path, file_name), kind).unwrap(), schema::SCHEMA_VERSION, "{}", file_name);
+        }
+        assert_eq!(parser.get_coder::<MainCoder>("app".to_string()).unwrap().get_stream_processors()["app.main.gain"], "dsp.Gain");
+    }
+
+    // Imported dsp crate with the files generation needs, so that rollbacks can regenerate it.
+    fn generated_dsp(test_name: &str) -> (Parser, String) {
+        let project_path = legacy_project("dsp", test_name);
+        std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+        std::fs::write(format!("{}/Cargo.toml", project_path), "[package]\nname = \"dsp\"\nversion = \"0.1.0\"\n").unwrap();
+        let mut parser = Parser::new();
+        import(&mut parser, &project_path).unwrap();
+        (parser, project_path)
+    }
+
+    #[test]
+    fn failing_batch_restores_the_project() {
+        let (mut parser, project_path) = generated_dsp("batch_rollback");
+        let error = parser.parse_command("create input dsp.Gain.z type f32; create input dsp.Missing.w type f32".to_string()).unwrap_err();
+        assert!(error.message.ends_with("Changes rolled back."), "{}", error.message);
+        assert!(parser.get_object(&"dsp.Gain.z".to_string()).is_err());
+        assert!(!parser.get_coder::<ProcessorCoder>("dsp.Gain".to_string()).unwrap().get_inputs().contains_key("z"));
+        let block_state: Value = serde_json::from_str(&project_file(&project_path, "Gain.json")).unwrap();
+        assert_eq!(block_state["data"]["inputs"], json!({ "x": "f32" }));
+        assert!(!project_file(&project_path, "memory_map.json").contains("dsp.Gain.z"));
+        assert!(parser.transaction.is_none());
+    }
+
+    #[test]
+    fn explicit_transactions_commit_or_roll_back() {
+        let (mut parser, _) = generated_dsp("explicit_transaction");
+        assert!(parser.parse_command("commit".to_string()).unwrap_err().message.contains("No active transaction"));
+        parser.parse_command("begin; create input dsp.Gain.z type f32".to_string()).unwrap();
+        parser.parse_command("create input dsp.Gain.w type f32".to_string()).unwrap();
+        let report = parser.parse_command("rollback".to_string()).unwrap();
+        assert_eq!(report[0]["restored"], json!(["dsp"]));
+        assert!(parser.get_object(&"dsp.Gain.z".to_string()).is_err());
+        assert!(parser.get_object(&"dsp.Gain.w".to_string()).is_err());
+
+        parser.parse_command("begin; create input dsp.Gain.z type f32; commit".to_string()).unwrap();
+        assert!(parser.get_object(&"dsp.Gain.z".to_string()).is_ok());
+        assert!(parser.parse_command("rollback".to_string()).is_err());
+
+        // A failing command rolls back everything since `begin`.
+        parser.parse_command("begin; create input dsp.Gain.w type f32".to_string()).unwrap();
+        let error = parser.parse_command("begin".to_string()).unwrap_err();
+        assert!(error.message.contains("already active") && error.message.ends_with("Changes rolled back."), "{}", error.message);
+        assert!(parser.get_object(&"dsp.Gain.w".to_string()).is_err());
+        assert!(parser.get_object(&"dsp.Gain.z".to_string()).is_ok());
+        assert!(parser.transaction.is_none());
+        assert!(matches!(parser.parse_command("begin; delete dsp".to_string()).unwrap_err().code, ErrorCode::InvalidArguments));
+    }
+
+    #[test]
+    fn connections_are_checked_against_the_block_ports() {
+        let mut parser = Parser::new();
+        for project in ["dsp", "app"] {
+            let project_path = legacy_project(project, &format!("connect_types_{}", project));
+            std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+            import(&mut parser, &project_path).unwrap();
+        }
+        parser.parse_command("create output dsp.Gain.level type f64".to_string()).unwrap();
+        let connect = |parser: &mut Parser, line: &str| parser.parse_connect(&command(line));
+
+        let error = connect(&mut parser, "connect app.main.gain.level app.main.gain.x").unwrap_err();
+        assert!(matches!(error.code, ErrorCode::TypeMismatch));
+        assert_eq!(error.message, "Cannot connect app.main.gain.level (f64) to app.main.gain.x (f32).");
+        let error = connect(&mut parser, "connect app.main.gain.y app.main.gain.x type f64").unwrap_err();
+        assert_eq!((error.code, error.message.as_str()), (ErrorCode::TypeMismatch, "The connection carries f32, not f64."));
+        let error = connect(&mut parser, "connect app.main.gain.z app.main.gain.x").unwrap_err();
+        assert_eq!((error.code, error.message.as_str()), (ErrorCode::NotFound, "Block Gain of app.main.gain has no output z."));
+        let error = connect(&mut parser, "connect app.main.gain.y app.main.gain.gain").unwrap_err();
+        assert_eq!((error.code, error.message.as_str()), (ErrorCode::NotFound, "Block Gain of app.main.gain has no input gain."));
+        assert!(matches!(connect(&mut parser, "connect app.main.gain.y dsp.main.gain.x").unwrap_err().code, ErrorCode::InvalidArguments));
+        assert!(connect(&mut parser, "connect app.main.other.y app.main.gain.x").is_err());
+
+        connect(&mut parser, "connect app.main.gain.y app.main.gain.x").unwrap();
+        assert_eq!(parser.parse_connections(&command("connections app")).unwrap(), json!([{ "from": "app.main.gain.y", "to": "app.main.gain.x", "type": "f32" }]));
+        parser.parse_disconnect(&command("disconnect app.main.gain.y app.main.gain.x")).unwrap();
+        let error = parser.parse_disconnect(&command("disconnect app.main.gain.y app.main.gain.x")).unwrap_err();
+        assert_eq!((error.code, error.message.as_str()), (ErrorCode::NotFound, "app.main.gain.y is not connected to app.main.gain.x."));
+        assert_eq!(parser.parse_connections(&command("connections app")).unwrap(), json!([]));
+    }
+
+    #[test]
+    fn applied_pipeline_changes_settings_and_connections() {
+        let mut parser = Parser::new();
+        let mut paths: Vec<String> = Vec::new();
+        for project in ["dsp", "app"] {
+            let project_path = legacy_project(project, &format!("apply_changes_{}", project));
+            std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+            std::fs::write(format!("{}/Cargo.toml", project_path), format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", project)).unwrap();
+            import(&mut parser, &project_path).unwrap();
+            paths.push(project_path);
+        }
+        let dumped = parser.parse_dump(&command("dump app")).unwrap();
+        let mut pipeline: Pipeline = toml::from_str(dumped["pipeline"].as_str().unwrap()).unwrap();
+        assert_eq!(pipeline.tasks["main"].processors["gain"].block_type, "dsp.Gain");
+
+        let pipeline_file = format!("{}/pipeline.toml", paths[1]);
+        pipeline.tasks.get_mut("main").unwrap().processors.get_mut("gain").unwrap().parameters
+            .insert("gain".to_string(), SettingSpec::Value(toml::Value::Float(3.0)));
+        pipeline.connections.push(ConnectionSpec { from: "main.gain.y".to_string(), to: "main.gain.x".to_string(), data_type: String::new() });
+        pipeline.save(&pipeline_file).unwrap();
+        let result = parser.parse_command(format!("apply {}", pipeline_file)).unwrap();
+        assert_eq!(result[0]["changes"], json!(["set parameter app.main.gain.gain 3.0", "connect app.main.gain.y app.main.gain.x"]));
+        let main_coder = parser.get_coder_ref::<MainCoder>(&"app".to_string()).unwrap();
+        assert_eq!(main_coder.get_settings().iter().map(|s| s.value.as_str()).collect::<Vec<_>>(), vec!["3.0"]);
+        assert_eq!(main_coder.get_connections()[0].data_type, "f32");
+
+        // The pipeline is applied, dumping and applying it again changes nothing.
+        parser.parse_dump(&command(&format!("dump app {}", pipeline_file))).unwrap();
+        assert_eq!(parser.parse_command(format!("apply {}", pipeline_file)).unwrap()[0]["changes"], json!([]));
+        // One apply is one journal entry.
+        parser.parse_command("undo app; undo app".to_string()).unwrap();
+        let main_coder = parser.get_coder_ref::<MainCoder>(&"app".to_string()).unwrap();
+        assert_eq!(main_coder.get_settings().iter().map(|s| s.value.as_str()).collect::<Vec<_>>(), vec!["2.0"]);
+        assert!(main_coder.get_connections().is_empty());
+    }
+
+    #[test]
+    fn block_deletion_cascades_to_files_and_applications() {
+        let mut parser = Parser::new();
+        for project in ["dsp", "app"] {
+            let project_path = legacy_project(project, &format!("delete_cascade_{}", project));
+            std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+            std::fs::write(format!("{}/Cargo.toml", project_path), format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", project)).unwrap();
+            import(&mut parser, &project_path).unwrap();
+        }
+        parser.parse_command("connect app.main.gain.y app.main.gain.x".to_string()).unwrap();
+        let block = parser.get_coder::<ProcessorCoder>("dsp.Gain".to_string()).unwrap();
+        block.generate().unwrap();
+        let files = vec![block.get_file_path(), block.get_save_path()];
+
+        // A dry run lists what goes away and changes nothing.
+        let plan = parser.parse_command("delete dsp.Gain.x dry_run".to_string()).unwrap()[0].clone();
+        assert_eq!((plan["objects"].clone(), plan["connections"].clone()), (json!(["dsp.Gain.x"]), json!(["app.main.gain.y -> app.main.gain.x"])));
+        let plan = parser.parse_command("delete dsp.Gain dry_run".to_string()).unwrap()[0].clone();
+        assert_eq!(plan["dry_run"], true);
+        assert_eq!(plan["objects"], json!(["dsp.Gain", "dsp.Gain.gain", "dsp.Gain.x", "dsp.Gain.y"]));
+        assert_eq!(plan["files"], json!(files));
+        assert_eq!((plan["modules"].clone(), plan["processors"].clone()), (json!(["Gain"]), json!(["app.main.gain"])));
+        assert_eq!(plan["connections"], json!(["app.main.gain.y -> app.main.gain.x"]));
+        assert_eq!(plan["settings"].as_array().unwrap().len(), 1);
+        assert!(files.iter().all(|file| Path::new(file).exists()));
+        assert_eq!(parser.get_coder_ref::<MainCoder>(&"app".to_string()).unwrap().get_connections().len(), 1);
+
+        parser.parse_command("delete dsp.Gain".to_string()).unwrap();
+        assert!(files.iter().all(|file| !Path::new(file).exists()));
+        assert!(parser.get_object(&"dsp.Gain".to_string()).is_err());
+        assert!(parser.get_coder_ref::<LibCoder>(&"dsp".to_string()).unwrap().get_modules().is_empty());
+        assert!(parser.get_object(&"app.main.gain".to_string()).is_err());
+        let main_coder = parser.get_coder_ref::<MainCoder>(&"app".to_string()).unwrap();
+        assert!(main_coder.get_stream_processors().is_empty() && main_coder.get_connections().is_empty() && main_coder.get_settings().is_empty());
+
+        // The application has its own journal entry for its part of the deletion.
+        parser.parse_command("undo app".to_string()).unwrap();
+        assert!(parser.get_object(&"app.main.gain".to_string()).is_ok());
+        assert_eq!(parser.get_coder_ref::<MainCoder>(&"app".to_string()).unwrap().get_connections().len(), 1);
+    }
+
+    #[test]
+    fn module_metadata_is_validated_and_generated() {
+        let (mut parser, _) = generated_dsp("module_metadata");
+        parser.parse_command("module dsp name signal-blocks; module dsp version 1.4.2; module dsp description 'Gain and filters'".to_string()).unwrap();
+        parser.parse_command("module dsp dependencies utils@^0.2 filters; module dsp provides gain".to_string()).unwrap();
+        let module = parser.parse_command("module dsp".to_string()).unwrap()[0].clone();
+        assert_eq!((module["name"].as_str(), module["description"].as_str()), (Some("signal-blocks"), Some("Gain and filters")));
+        assert_eq!(module["version"], json!({ "major": 1, "minor": 4, "build": 2 }));
+        assert_eq!((module["dependencies"].clone(), module["provides"].clone()), (json!(["utils@^0.2", "filters"]), json!(["gain"])));
+        let lib_rs = std::fs::read_to_string(parser.get_coder_ref::<LibCoder>(&"dsp".to_string()).unwrap().get_file_path()).unwrap();
+        for literal in ["name: b\"signal-blocks\\0\"", "b\"utils@^0.2\\0\"", "b\"filters\\0\"", "FfiStringArray<1> = FfiStringArray([\n    b\"gain\\0\""] {
+            assert!(lib_rs.contains(literal), "{} not in {}", literal, lib_rs);
+        }
+
+        for invalid in ["module dsp version 1.2", "module dsp version 1.0.0-beta", "module dsp name 'bad name'", "module dsp description a b",
+            "module dsp dependencies signal-blocks", "module dsp dependencies utils utils@1", "module dsp provides gain gain", "module dsp licence MIT"] {
+            let error = parser.parse_command(invalid.to_string()).unwrap_err();
+            assert!(matches!(error.code, ErrorCode::InvalidArguments), "{}: {}", invalid, error.message);
+        }
+        assert_eq!(parser.get_coder_ref::<LibCoder>(&"dsp".to_string()).unwrap().get_module_struct().version.minor, 4);
+        parser.parse_command("undo dsp".to_string()).unwrap();
+        assert!(parser.get_coder_ref::<LibCoder>(&"dsp".to_string()).unwrap().get_module_struct().provides.is_empty());
+    }
+
+    #[test]
+    fn queries_report_the_model_without_journaling() {
+        let mut parser = Parser::new();
+        for project in ["dsp", "app"] {
+            let project_path = legacy_project(project, &format!("queries_{}", project));
+            std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+            import(&mut parser, &project_path).unwrap();
+        }
+        parser.parse_command("connect app.main.gain.y app.main.gain.x".to_string()).unwrap();
+        let undo_entries = parser.journal_mut(&"app".to_string()).unwrap().undo.len();
+        let results = parser.parse_command("list; list app; show app; show app.main.gain; tree app".to_string()).unwrap();
+        assert_eq!(names(&results[1]), vec!["app.main"]);
+        assert_eq!(results[2]["stream_processors"], json!({ "app.main.gain": "dsp.Gain" }));
+        assert_eq!(results[2]["connections"][0]["to_input"], "app.main.gain.x");
+        assert_eq!(results[3]["connections"].as_array().unwrap().len(), 1);
+        assert_eq!(parser.journal_mut(&"app".to_string()).unwrap().undo.len(), undo_entries);
+        for query in ["show app.main.other", "tree dsp.Mixer", "list other"] {
+            assert!(matches!(parser.parse_command(query.to_string()).unwrap_err().code, ErrorCode::NotFound), "{}", query);
+        }
+    }
+
+    // Imports dsp and app with source folders and manifests, and connects the gain processor to itself.
+    fn connected_projects(test_name: &str) -> (Parser, String, String) {
+        let mut parser = Parser::new();
+        let mut paths: Vec<String> = Vec::new();
+        for project in ["dsp", "app"] {
+            let project_path = legacy_project(project, &format!("{}_{}", test_name, project));
+            std::fs::create_dir_all(format!("{}/src", project_path)).unwrap();
+            std::fs::write(format!("{}/Cargo.toml", project_path), format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", project)).unwrap();
+            import(&mut parser, &project_path).unwrap();
+            paths.push(project_path);
+        }
+        parser.parse_command("connect app.main.gain.y app.main.gain.x".to_string()).unwrap();
+        (parser, paths[0].clone(), paths[1].clone())
+    }
+
+    fn connection_ends(parser: &mut Parser) -> Vec<(String, String)> {
+        let main_coder = parser.get_coder::<MainCoder>("app".to_string()).unwrap();
+        main_coder.get_connections().iter().map(|c| (c.from_output.clone(), c.to_input.clone())).collect()
+    }
+
+    fn setting_names(parser: &mut Parser) -> Vec<(String, String)> {
+        let main_coder = parser.get_coder::<MainCoder>("app".to_string()).unwrap();
+        main_coder.get_settings().iter().map(|s| (s.processor_name.clone(), s.settable_name.clone())).collect()
+    }
+
+    #[test]
+    fn renamed_block_follows_into_library_and_applications() {
+        let (mut parser, dsp_path, app_path) = connected_projects("rename_block");
+        let renamed = parser.parse_command("rename dsp.Gain Amp".to_string()).unwrap()[0].clone();
+        assert_eq!(renamed["updated"], json!(["app"]));
+        assert!(parser.get_object(&"dsp.Gain".to_string()).is_err());
+        assert_eq!(parser.get_object(&"dsp.Amp.gain".to_string()).unwrap().parent, "dsp.Amp");
+        assert!(std::path::Path::new(&format!("{}/.project/Amp.json", dsp_path)).exists());
+        assert!(!std::path::Path::new(&format!("{}/.project/Gain.json", dsp_path)).exists());
+        assert_eq!(parser.get_coder::<LibCoder>("dsp".to_string()).unwrap().get_modules(), &vec!["Amp".to_string()]);
+        assert_eq!(parser.get_object(&"app.main.gain".to_string()).unwrap().object_type, "dsp.Amp");
+        assert_eq!(parser.get_coder::<MainCoder>("app".to_string()).unwrap().get_stream_processors()["app.main.gain"], "dsp.Amp");
+        assert!(project_file(&app_path, "memory_map.json").contains("dsp.Amp"));
+        // Connections and settings name processors, not blocks, so they stay as they were.
+        assert_eq!(connection_ends(&mut parser), vec![("app.main.gain.y".to_string(), "app.main.gain.x".to_string())]);
+        assert_eq!(setting_names(&mut parser), vec![("app.main.gain".to_string(), "app.main.gain.gain".to_string())]);
+        // The application records its own part of the rename.
+        parser.parse_command("undo app".to_string()).unwrap();
+        assert_eq!(parser.get_object(&"app.main.gain".to_string()).unwrap().object_type, "dsp.Gain");
+    }
+
+    #[test]
+    fn renamed_members_follow_in_connections_and_settings() {
+        let (mut parser, _, _) = connected_projects("rename_member");
+        let renamed = parser.parse_command("rename dsp.Gain.x in; rename dsp.Gain.gain level".to_string()).unwrap();
+        assert_eq!(renamed[0]["updated"], json!(["app"]));
+        assert!(parser.get_object(&"dsp.Gain.in".to_string()).is_ok());
+        assert!(parser.get_coder::<ProcessorCoder>("dsp.Gain".to_string()).unwrap().get_parameters().contains_key("level"));
+        assert_eq!(connection_ends(&mut parser), vec![("app.main.gain.y".to_string(), "app.main.gain.in".to_string())]);
+        assert_eq!(setting_names(&mut parser), vec![("app.main.gain".to_string(), "app.main.gain.level".to_string())]);
+        let error = parser.parse_command("rename dsp.Gain.y in".to_string()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::AlreadyExists);
+        let error = parser.parse_command("move dsp.Gain.y dsp.Other.y".to_string()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidArguments);
+        let error = parser.parse_command("rename dsp.Gain.y out.put".to_string()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidArguments);
+    }
+
+    #[test]
+    fn processors_move_between_tasks() {
+        let (mut parser, _, app_path) = connected_projects("move_processor");
+        parser.parse_command("create task app.audio; move app.main.gain app.audio.gain".to_string()).unwrap();
+        let main_coder = parser.get_coder::<MainCoder>("app".to_string()).unwrap();
+        assert!(main_coder.get_task_processors()["app.main"].stream_processors.is_empty());
+        assert_eq!(main_coder.get_task_processors()["app.audio"].stream_processors, vec!["gain".to_string()]);
+        assert_eq!(parser.get_object(&"app.audio.gain".to_string()).unwrap().parent, "app.audio");
+        assert_eq!(connection_ends(&mut parser), vec![("app.audio.gain.y".to_string(), "app.audio.gain.x".to_string())]);
+        assert_eq!(setting_names(&mut parser), vec![("app.audio.gain".to_string(), "app.audio.gain.gain".to_string())]);
+        // Renaming the task carries its processors along.
+        parser.parse_command("rename app.audio mixer".to_string()).unwrap();
+        assert!(parser.get_object(&"app.audio.gain".to_string()).is_err());
+        assert_eq!(parser.get_object(&"app.mixer.gain".to_string()).unwrap().parent, "app.mixer");
+        assert_eq!(setting_names(&mut parser), vec![("app.mixer.gain".to_string(), "app.mixer.gain.gain".to_string())]);
+        assert!(project_file(&app_path, "memory_map.json").contains("app.mixer.gain"));
+        let error = parser.parse_command("move app.mixer.gain app.missing.gain".to_string()).unwrap_err();
+        assert_eq!(error.code, ErrorCode::NotFound);
+        assert!(parser.get_object(&"app.mixer.gain".to_string()).is_ok());
+    }
+}
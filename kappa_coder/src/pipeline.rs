@@ -0,0 +1,341 @@
+use std::collections::BTreeMap;
+use serde::{Serialize, Deserialize};
+use coder::main_coder::MainCoder;
+
+// Declarative description of an application. Names are relative to the application: processors
+// are `<task>.<processor>` and ports `<task>.<processor>.<port>`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Pipeline {
+    pub application: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default)]
+    pub tasks: BTreeMap<String, TaskSpec>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connections: Vec<ConnectionSpec>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct TaskSpec {
+    #[serde(default)]
+    pub processors: BTreeMap<String, ProcessorSpec>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProcessorSpec {
+    #[serde(rename = "type")]
+    pub block_type: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, SettingSpec>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub statics: BTreeMap<String, SettingSpec>,
+}
+
+// `gain = 2.0` takes the type from the block, `gain = { value = 2.0, type = "f32" }` names it.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SettingSpec {
+    Typed {
+        value: toml::Value,
+        #[serde(rename = "type")]
+        data_type: String,
+    },
+    Value(toml::Value),
+}
+
+impl SettingSpec {
+    fn value(&self) -> Result<String, String> {
+        let value = match self {
+            SettingSpec::Typed { value, .. } | SettingSpec::Value(value) => value,
+        };
+        match value {
+            toml::Value::String(s) => Ok(s.clone()),
+            toml::Value::Integer(i) => Ok(i.to_string()),
+            // `{:?}` keeps `2.0` a float, `to_string` would give `2`.
+            toml::Value::Float(f) => Ok(format!("{:?}", f)),
+            toml::Value::Boolean(b) => Ok(b.to_string()),
+            _ => Err(format!("Setting value {} must be a string, a number or a bool.", value)),
+        }
+    }
+
+    fn data_type(&self) -> Option<&String> {
+        match self {
+            SettingSpec::Typed { data_type, .. } => Some(data_type),
+            SettingSpec::Value(_) => None,
+        }
+    }
+
+    // Values of the model are stored as written, they are dumped as TOML values of their type.
+    fn from_setting(value: &String, data_type: &String) -> Self {
+        let typed_value = match data_type.as_str() {
+            "bool" => value.parse::<bool>().ok().map(toml::Value::Boolean),
+            "f32" | "f64" => value.parse::<f64>().ok().map(toml::Value::Float),
+            "String" | "&str" | "&'static str" | "char" => None,
+            _ => value.parse::<i64>().ok().map(toml::Value::Integer),
+        };
+        SettingSpec::Typed {
+            value: typed_value.unwrap_or_else(|| toml::Value::String(value.clone())),
+            data_type: data_type.clone(),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionSpec {
+    pub from: String,
+    pub to: String,
+    #[serde(rename = "type", default, skip_serializing_if = "String::is_empty")]
+    pub data_type: String,
+}
+
+impl ConnectionSpec {
+    // A connection without a type takes the type of its ports, so it matches any typed one.
+    fn matches(&self, other: &ConnectionSpec) -> bool {
+        self.from == other.from && self.to == other.to
+            && (self.data_type.is_empty() || other.data_type.is_empty() || self.data_type == other.data_type)
+    }
+
+    fn processors(&self) -> [&str; 2] {
+        [port_processor(&self.from), port_processor(&self.to)]
+    }
+}
+
+fn port_processor(port: &String) -> &str {
+    port.rsplit_once('.').map(|(proc_name, _)| proc_name).unwrap_or_default()
+}
+
+fn settables(processor: &ProcessorSpec) -> [(&str, &BTreeMap<String, SettingSpec>); 2] {
+    [("parameter", &processor.parameters), ("static", &processor.statics)]
+}
+
+impl Pipeline {
+    pub fn load(path: &String) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Error reading pipeline file {}: {}", path, e))?;
+        let pipeline: Pipeline = toml::from_str(&text).map_err(|e| format!("Error parsing pipeline file {}: {}", path, e))?;
+        if pipeline.application.is_empty() {
+            return Err(format!("Pipeline file {} names no application.", path));
+        }
+        Ok(pipeline)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, String> {
+        toml::to_string_pretty(self).map_err(|e| format!("Error serializing pipeline {}: {}", self.application, e))
+    }
+
+    pub fn save(&self, path: &String) -> Result<(), String> {
+        std::fs::write(path, self.to_toml_string()?).map_err(|e| format!("Error writing pipeline file {}: {}", path, e))
+    }
+
+    pub fn from_application(application: &String, description: &String, main_coder: &MainCoder) -> Self {
+        let prefix = format!("{}.", application);
+        let local = |object_name: &String| object_name.strip_prefix(&prefix).unwrap_or(object_name).to_string();
+        let mut tasks: BTreeMap<String, TaskSpec> = BTreeMap::new();
+        for (task_name, task) in main_coder.get_task_processors().iter() {
+            let mut processors: BTreeMap<String, ProcessorSpec> = BTreeMap::new();
+            for proc_name in task.stream_processors.iter() {
+                let block_type = main_coder.get_stream_processors().get(&format!("{}.{}", task_name, proc_name)).cloned().unwrap_or_default();
+                processors.insert(proc_name.clone(), ProcessorSpec {
+                    block_type,
+                    parameters: BTreeMap::new(),
+                    statics: BTreeMap::new(),
+                });
+            }
+            tasks.insert(local(task_name), TaskSpec { processors });
+        }
+        for setting in main_coder.get_settings().iter() {
+            let proc_name = local(&setting.processor_name);
+            let processor = proc_name.split_once('.')
+                .and_then(|(task_name, proc_name)| tasks.get_mut(task_name)?.processors.get_mut(proc_name));
+            if let Some(processor) = processor {
+                let settables = if setting.settable_type == "parameter" { &mut processor.parameters } else { &mut processor.statics };
+                let settable_name = setting.settable_name.rsplit('.').next().unwrap_or_default().to_string();
+                settables.insert(settable_name, SettingSpec::from_setting(&setting.value, &setting.data_type));
+            }
+        }
+        let mut connections = main_coder.get_connections().iter()
+            .map(|c| ConnectionSpec { from: local(&c.from_output), to: local(&c.to_input), data_type: c.data_type.clone() })
+            .collect::<Vec<ConnectionSpec>>();
+        connections.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+        Pipeline {
+            application: application.clone(),
+            description: description.clone(),
+            tasks,
+            connections,
+        }
+    }
+
+    // Commands turning the application modelled by `main_coder` into this pipeline. Removals come
+    // first, so that a processor whose type changed can be created again under the same name.
+    pub fn changes(&self, main_coder: &MainCoder) -> Result<Vec<Vec<String>>, String> {
+        let full = |name: &str| format!("{}.{}", self.application, name);
+        let current = Pipeline::from_application(&self.application, &self.description, main_coder);
+        let mut removals: Vec<Vec<String>> = Vec::new();
+        let mut deletions: Vec<Vec<String>> = Vec::new();
+        let mut additions: Vec<Vec<String>> = Vec::new();
+
+        // Processors deleted here lose their settings and connections with them.
+        let mut replaced: Vec<String> = Vec::new();
+        for (task_name, task) in current.tasks.iter() {
+            let wanted_task = match self.tasks.get(task_name) {
+                Some(wanted_task) => wanted_task,
+                None => {
+                    deletions.push(vec!["delete".to_string(), full(task_name)]);
+                    replaced.extend(task.processors.keys().map(|proc_name| format!("{}.{}", task_name, proc_name)));
+                    continue;
+                },
+            };
+            for (proc_name, processor) in task.processors.iter() {
+                let local_name = format!("{}.{}", task_name, proc_name);
+                let wanted = match wanted_task.processors.get(proc_name).filter(|w| w.block_type == processor.block_type) {
+                    Some(wanted) => wanted,
+                    None => {
+                        deletions.push(vec!["delete".to_string(), full(&local_name)]);
+                        replaced.push(local_name);
+                        continue;
+                    },
+                };
+                for ((category, current_settables), (_, wanted_settables)) in settables(processor).into_iter().zip(settables(wanted)) {
+                    for settable_name in current_settables.keys().filter(|name| !wanted_settables.contains_key(*name)) {
+                        removals.push(vec!["unset".to_string(), category.to_string(), full(&format!("{}.{}", local_name, settable_name))]);
+                    }
+                }
+            }
+        }
+        let kept_connections = current.connections.iter()
+            .filter(|c| !c.processors().iter().any(|proc_name| replaced.iter().any(|r| r == proc_name)))
+            .collect::<Vec<&ConnectionSpec>>();
+        for connection in kept_connections.iter().filter(|c| !self.connections.iter().any(|w| w.matches(c))) {
+            removals.push(vec!["disconnect".to_string(), full(&connection.from), full(&connection.to)]);
+        }
+
+        for (task_name, task) in self.tasks.iter() {
+            let current_task = current.tasks.get(task_name);
+            if current_task.is_none() {
+                additions.push(vec!["create".to_string(), "task".to_string(), full(task_name)]);
+            }
+            for (proc_name, processor) in task.processors.iter() {
+                let local_name = format!("{}.{}", task_name, proc_name);
+                let current_processor = current_task
+                    .and_then(|t| t.processors.get(proc_name))
+                    .filter(|c| c.block_type == processor.block_type);
+                if current_processor.is_none() {
+                    additions.push(vec!["create".to_string(), "stream_proc".to_string(), full(&local_name), "type".to_string(), processor.block_type.clone()]);
+                }
+                for (category, wanted_settables) in settables(processor) {
+                    for (settable_name, setting) in wanted_settables.iter() {
+                        let value = setting.value()?;
+                        let current_setting = current_processor
+                            .map(|c| if category == "parameter" { &c.parameters } else { &c.statics })
+                            .and_then(|c| c.get(settable_name));
+                        let unchanged = match current_setting {
+                            Some(current_setting) => {
+                                let current_type = current_setting.data_type().cloned().unwrap_or_default();
+                                setting.data_type().map_or(true, |t| *t == current_type)
+                                    && SettingSpec::from_setting(&value, &current_type).value()? == current_setting.value()?
+                            },
+                            None => false,
+                        };
+                        if unchanged {
+                            continue;
+                        }
+                        let mut command = vec!["set".to_string(), category.to_string(), full(&format!("{}.{}", local_name, settable_name)), value];
+                        if let Some(data_type) = setting.data_type() {
+                            command.extend(["type".to_string(), data_type.clone()]);
+                        }
+                        additions.push(command);
+                    }
+                }
+            }
+        }
+        for connection in self.connections.iter().filter(|w| !kept_connections.iter().any(|c| w.matches(c))) {
+            let mut command = vec!["connect".to_string(), full(&connection.from), full(&connection.to)];
+            if !connection.data_type.is_empty() {
+                command.extend(["type".to_string(), connection.data_type.clone()]);
+            }
+            additions.push(command);
+        }
+
+        removals.extend(deletions);
+        removals.extend(additions);
+        Ok(removals)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `app` runs a source feeding a gain in its `main` task.
+    fn application() -> MainCoder {
+        let mut main_coder = MainCoder::new("/tmp/app".to_string());
+        main_coder.add_task_processor("app.main".to_string());
+        main_coder.add_stream_processor("app.main.source".to_string(), "dsp.Source".to_string()).unwrap();
+        main_coder.add_stream_processor("app.main.gain".to_string(), "dsp.Gain".to_string()).unwrap();
+        main_coder.add_setting_value("app.main.gain".to_string(), "parameter".to_string(), "app.main.gain.gain".to_string(), "2.0".to_string(), "f32".to_string());
+        main_coder.add_setting_value("app.main.gain".to_string(), "static".to_string(), "app.main.gain.label".to_string(), "main".to_string(), "String".to_string());
+        main_coder.add_connection("app.main.source".to_string(), "app.main.source.y".to_string(), "app.main.gain".to_string(), "app.main.gain.x".to_string(), "f32".to_string());
+        main_coder
+    }
+
+    fn dumped(main_coder: &MainCoder) -> Pipeline {
+        let text = Pipeline::from_application(&"app".to_string(), &String::new(), main_coder).to_toml_string().unwrap();
+        toml::from_str(&text).unwrap()
+    }
+
+    fn commands(changes: Vec<Vec<String>>) -> Vec<String> {
+        changes.iter().map(|c| c.join(" ")).collect()
+    }
+
+    #[test]
+    fn dumped_pipeline_applies_without_changes() {
+        let main_coder = application();
+        let pipeline = dumped(&main_coder);
+        assert!(pipeline.to_toml_string().unwrap().contains("value = 2.0"));
+        assert!(pipeline.changes(&main_coder).unwrap().is_empty());
+    }
+
+    #[test]
+    fn changed_block_type_deletes_and_creates_the_processor() {
+        let main_coder = application();
+        let mut pipeline = dumped(&main_coder);
+        pipeline.tasks.get_mut("main").unwrap().processors.get_mut("gain").unwrap().block_type = "dsp.Attenuator".to_string();
+        assert_eq!(commands(pipeline.changes(&main_coder).unwrap()), vec![
+            "delete app.main.gain",
+            "create stream_proc app.main.gain type dsp.Attenuator",
+            "set parameter app.main.gain.gain 2.0 type f32",
+            "set static app.main.gain.label main type String",
+            "connect app.main.source.y app.main.gain.x type f32",
+        ]);
+    }
+
+    #[test]
+    fn removed_setting_is_unset() {
+        let main_coder = application();
+        let mut pipeline = dumped(&main_coder);
+        pipeline.tasks.get_mut("main").unwrap().processors.get_mut("gain").unwrap().parameters.clear();
+        assert_eq!(commands(pipeline.changes(&main_coder).unwrap()), vec!["unset parameter app.main.gain.gain"]);
+    }
+
+    #[test]
+    fn changed_float_value_keeps_its_fraction() {
+        let main_coder = application();
+        let mut pipeline = dumped(&main_coder);
+        pipeline.tasks.get_mut("main").unwrap().processors.get_mut("gain").unwrap().parameters
+            .insert("gain".to_string(), SettingSpec::Value(toml::Value::Float(3.0)));
+        assert_eq!(commands(pipeline.changes(&main_coder).unwrap()), vec!["set parameter app.main.gain.gain 3.0"]);
+    }
+
+    #[test]
+    fn retyped_connection_is_connected_again() {
+        let main_coder = application();
+        let mut pipeline = dumped(&main_coder);
+        pipeline.connections[0].data_type = "f64".to_string();
+        assert_eq!(commands(pipeline.changes(&main_coder).unwrap()), vec![
+            "disconnect app.main.source.y app.main.gain.x",
+            "connect app.main.source.y app.main.gain.x type f64",
+        ]);
+        // A connection without a type keeps the typed one.
+        pipeline.connections[0].data_type = String::new();
+        assert!(pipeline.changes(&main_coder).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+use std::process::Command;
+use serde::{Serialize, Deserialize};
+use coder::schema;
+
+// Description of a project folder, stored in `.project/project.json`. Its schema version is the one
+// of the envelope shared by all `.project` files.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProjectInfo {
+    pub name: String,
+    pub kind: String,
+}
+
+impl ProjectInfo {
+    pub fn new(name: String, kind: String) -> Self {
+        ProjectInfo {
+            name,
+            kind,
+        }
+    }
+
+    fn file_path(project_path: &String) -> String {
+        format!("{}/.project/project.json", project_path)
+    }
+
+    pub fn save(&self, project_path: &String) -> Result<(), String> {
+        schema::write_versioned(&ProjectInfo::file_path(project_path), schema::PROJECT, self)
+    }
+}
+
+pub fn is_archive(path: &String) -> bool {
+    path.ends_with(".tar.gz") || path.ends_with(".tgz")
+}
+
+// Packs the project folder with its sources and `.project/` state. Build output and the
+// undo journal, whose snapshots refer to the original location, are left out.
+pub fn export_archive(project_path: &String, archive: &String) -> Result<(), String> {
+    let project_dir = Path::new(project_path);
+    let parent = project_dir.parent().ok_or_else(|| format!("Project path {} has no parent folder.", project_path))?;
+    let folder = project_dir.file_name().and_then(|f| f.to_str()).ok_or_else(|| format!("Invalid project path {}.", project_path))?;
+    let output = Command::new("tar")
+        .arg("-czf").arg(archive)
+        .arg(format!("--exclude={}/target", folder))
+        .arg(format!("--exclude={}/.project/journal.jsonl", folder))
+        .arg("-C").arg(parent)
+        .arg(folder)
+        .output()
+        .map_err(|e| format!("Failed to run tar: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Error creating archive {}: {}", archive, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(())
+}
+
+// Name of the single project folder packed in an archive.
+pub fn archive_folder(archive: &String) -> Result<String, String> {
+    let listing = Command::new("tar")
+        .arg("-tzf").arg(archive)
+        .output()
+        .map_err(|e| format!("Failed to run tar: {}", e))?;
+    if !listing.status.success() {
+        return Err(format!("Error reading archive {}: {}", archive, String::from_utf8_lossy(&listing.stderr).trim()));
+    }
+    let listing = String::from_utf8_lossy(&listing.stdout).to_string();
+    let mut folders = listing.lines()
+        .filter_map(|entry| entry.trim_start_matches("./").split('/').next())
+        .filter(|folder| !folder.is_empty())
+        .collect::<Vec<&str>>();
+    folders.sort();
+    folders.dedup();
+    if folders.len() != 1 {
+        return Err(format!("Archive {} must contain exactly one project folder.", archive));
+    }
+    Ok(folders[0].to_string())
+}
+
+// Unpacks an exported archive into `destination` and returns the path of the project folder.
+pub fn extract_archive(archive: &String, destination: &String) -> Result<String, String> {
+    let project_path = format!("{}/{}", destination, archive_folder(archive)?);
+    if Path::new(&project_path).exists() {
+        return Err(format!("Folder {} already exists.", project_path));
+    }
+    std::fs::create_dir_all(destination).map_err(|e| format!("Error creating {}: {}", destination, e))?;
+    let output = Command::new("tar")
+        .arg("-xzf").arg(archive)
+        .arg("-C").arg(destination)
+        .output()
+        .map_err(|e| format!("Failed to run tar: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Error extracting archive {}: {}", archive, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(project_path)
+}
@@ -0,0 +1,181 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(u64),
+    Text(String),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Argument {
+    Bool(bool),
+    Integer(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl Argument {
+    pub fn to_token(&self) -> String {
+        match self {
+            Argument::Bool(value) => value.to_string(),
+            Argument::Integer(value) => value.to_string(),
+            // `{:?}` keeps the fractional part of whole floats, `1.0` must not reach the lexer as `1`.
+            Argument::Float(value) => format!("{:?}", value),
+            Argument::Text(value) => value.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Request {
+    pub version: u32,
+    pub id: RequestId,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<Argument>,
+}
+
+impl Request {
+    pub fn tokens(&self) -> Vec<String> {
+        let mut tokens: Vec<String> = vec![self.command.clone()];
+        tokens.extend(self.args.iter().map(|arg| arg.to_token()));
+        tokens
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Ok,
+    Error,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidRequest,
+    UnsupportedVersion,
+    UnknownCommand,
+    SyntaxError,
+    InvalidArguments,
+    NotFound,
+    AlreadyExists,
+    TypeMismatch,
+    BuildFailed,
+    TestFailed,
+    ValidationFailed,
+    CommandFailed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Response {
+    pub version: u32,
+    pub id: Option<RequestId>,
+    pub status: ResponseStatus,
+    pub error_code: Option<ErrorCode>,
+    pub message: Option<String>,
+    pub payload: Value,
+}
+
+impl Response {
+    pub fn ok(id: Option<RequestId>, payload: Value) -> Self {
+        Response {
+            version: PROTOCOL_VERSION,
+            id,
+            status: ResponseStatus::Ok,
+            error_code: None,
+            message: None,
+            payload,
+        }
+    }
+
+    pub fn error(id: Option<RequestId>, error_code: ErrorCode, message: String, payload: Value) -> Self {
+        Response {
+            version: PROTOCOL_VERSION,
+            id,
+            status: ResponseStatus::Error,
+            error_code: Some(error_code),
+            message: Some(message),
+            payload,
+        }
+    }
+
+    pub fn to_line(&self) -> String {
+        match serde_json::to_string(self) {
+            Ok(json_string) => format!("{}\n", json_string),
+            Err(e) => {
+                let fallback = Response::error(self.id.clone(), ErrorCode::CommandFailed, format!("Error serializing response: {}", e), Value::Null);
+                format!("{}\n", serde_json::to_string(&fallback).unwrap_or_default())
+            },
+        }
+    }
+}
+
+pub fn decode_request(message: &str) -> Result<Request, Response> {
+    let raw: Value = serde_json::from_str(message)
+        .map_err(|e| Response::error(None, ErrorCode::InvalidRequest, format!("Malformed JSON request: {}", e), Value::Null))?;
+    let id: Option<RequestId> = raw.get("id").and_then(|id| serde_json::from_value(id.clone()).ok());
+    let request: Request = serde_json::from_value(raw)
+        .map_err(|e| Response::error(id.clone(), ErrorCode::InvalidRequest, format!("Invalid request: {}", e), Value::Null))?;
+    if request.version != PROTOCOL_VERSION {
+        return Err(Response::error(
+            Some(request.id.clone()),
+            ErrorCode::UnsupportedVersion,
+            format!("Unsupported protocol version {}, expected {}.", request.version, PROTOCOL_VERSION),
+            Value::Null));
+    }
+    Ok(request)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn request_arguments_become_tokens() {
+        let request = decode_request(r#"{"version":1,"id":7,"command":"set","args":["parameter","app.main.gain.gain",1.0,2,true]}"#).unwrap();
+        assert_eq!(request.id, RequestId::Number(7));
+        assert_eq!(request.tokens(), vec!["set", "parameter", "app.main.gain.gain", "1.0", "2", "true"]);
+        let request = decode_request(r#"{"version":1,"id":"a","command":"list"}"#).unwrap();
+        assert_eq!((request.tokens(), request.id), (vec!["list".to_string()], RequestId::Text("a".to_string())));
+    }
+
+    #[test]
+    fn unsupported_version_is_rejected_with_the_request_id() {
+        let response = decode_request(r#"{"version":2,"id":"a","command":"list"}"#).unwrap_err();
+        assert_eq!(response.error_code, Some(ErrorCode::UnsupportedVersion));
+        assert_eq!(response.id, Some(RequestId::Text("a".to_string())));
+    }
+
+    #[test]
+    fn invalid_requests_echo_the_id_when_it_can_be_read() {
+        let response = decode_request(r#"{"version":1,"id":3}"#).unwrap_err();
+        assert_eq!((response.error_code, response.id), (Some(ErrorCode::InvalidRequest), Some(RequestId::Number(3))));
+        let response = decode_request("{").unwrap_err();
+        assert_eq!((response.error_code, response.id), (Some(ErrorCode::InvalidRequest), None));
+    }
+
+    #[test]
+    fn replies_are_single_json_lines() {
+        let line = Response::ok(Some(RequestId::Number(4)), json!({ "name": "a \"b\"\n" })).to_line();
+        assert!(line.ends_with('\n') && !line.trim_end().contains('\n'));
+        let reply: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(reply, json!({ "version": 1, "id": 4, "status": "ok", "error_code": null, "message": null, "payload": { "name": "a \"b\"\n" } }));
+    }
+
+    #[test]
+    fn error_codes_are_snake_case() {
+        let reply: Value = serde_json::from_str(&Response::error(None, ErrorCode::TypeMismatch, "x".to_string(), Value::Null).to_line()).unwrap();
+        assert_eq!((reply["status"].as_str(), reply["error_code"].as_str()), (Some("error"), Some("type_mismatch")));
+        for (code, name) in [(ErrorCode::InvalidRequest, "invalid_request"), (ErrorCode::UnsupportedVersion, "unsupported_version"),
+            (ErrorCode::UnknownCommand, "unknown_command"), (ErrorCode::SyntaxError, "syntax_error"), (ErrorCode::CommandFailed, "command_failed")] {
+            assert_eq!(serde_json::to_value(code).unwrap(), json!(name));
+        }
+    }
+}
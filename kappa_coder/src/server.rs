@@ -3,6 +3,11 @@ use crate::library_manager::LibraryManager;
 use processor_engine::stream_processor::{StreamProcessor, StreamBlock};
 use interfaces::tcp_interface::{TcpReceiver, TcpMessage};
 use crate::parser::Parser;
+use crate::protocol::{Response, decode_request};
+use serde_json::Value;
+
+const LIBRARY_WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+
 pub struct Server;
 
 impl Server {
@@ -11,9 +16,11 @@ impl Server {
         server_port: u16,
         dynamic_libraries: String,
         kappa_library: String,
+        workspace: String,
     ) -> Result<std::thread::JoinHandle<()>, String> {
         let mut server = Server;
         Parser::get().lock().unwrap().set_library_path(kappa_library.clone())?;
+        server.init_workspace(workspace)?;
         if server.init_library(dynamic_libraries).is_ok() {
             Ok(std::thread::spawn(move || {
                 server.run_server(server_port, server_addr).unwrap()
@@ -23,14 +30,38 @@ impl Server {
         }
     }
 
+    pub fn init_workspace(&mut self, workspace: String) -> Result<(), String> {
+        let report = Parser::get().lock().unwrap().open_workspace(workspace.clone())?;
+        println!("Workspace {} opened.", workspace);
+        for project in report["restored"].as_array().cloned().unwrap_or_default() {
+            println!("Restored project {}", project.as_str().unwrap_or_default());
+        }
+        for failure in report["failed"].as_array().cloned().unwrap_or_default() {
+            eprintln!("Failed to restore project {}: {}",
+                failure["path"].as_str().unwrap_or_default(),
+                failure["error"].as_str().unwrap_or_default());
+        }
+        Ok(())
+    }
+
     pub fn init_library(&mut self, dynamic_libraries: String) -> Result<(), String> {
         match LibraryManager::get().lock().unwrap().load_library(&dynamic_libraries) {
             Ok(_) => println!("Libraries loaded successfully from {}", dynamic_libraries),
             Err(e) => eprintln!("Error loading libraries: {}", e),
         }
+        Server::watch_libraries();
         Ok(())
     }
 
+    fn watch_libraries() {
+        std::thread::spawn(move || {
+            loop {
+                std::thread::sleep(LIBRARY_WATCH_INTERVAL);
+                LibraryManager::get().lock().unwrap().watch();
+            }
+        });
+    }
+
     pub fn run_server(&mut self, port: u16, address: String) -> Result<(), String>{
         let mut tcp_receiver = TcpReceiver::<String>::new("coder_server");
         tcp_receiver.set_statics_value::<u16>("port", port).unwrap();
@@ -56,33 +87,57 @@ impl Server {
         loop {
             let command = receiver.recv().unwrap();
             let id_stream = command.id_stream;
-            let mut command = command.message;
-            command = command
-            .chars()
-            .filter(|c| {
-                *c != '\n' && *c != '\r'
-            })
-            .collect();
-            print!("Received {}\n", command);
+            let command = command.message;
             let answer: TcpMessage<String>;
-            match Parser::get().lock().unwrap().parse_command(command.clone()) {
-                
-                Ok(_) => {
-                    answer = TcpMessage {
-                        id_stream,
-                        message: format!("Ok\n"),
-                    };
-                    println!("Processed command successfully.\n");
-                },
-                Err(e) => {
-                    answer = TcpMessage {
-                        id_stream,
-                        message: format!("Error: {}\n", e),
-                    };
-                    println!("Error processing command: {}\n", e);
-                }
+            if command.trim_start().starts_with('{') {
+                let response = Server::process_request(command.trim());
+                answer = TcpMessage {
+                    id_stream,
+                    message: response.to_line(),
+                };
+            } else {
+                answer = TcpMessage {
+                    id_stream,
+                    message: Server::process_legacy_command(command),
+                };
             }
             sender_tcp.send(answer).unwrap();  
         }
     }
+
+    fn process_request(message: &str) -> Response {
+        print!("Received request {}\n", message);
+        let request = match decode_request(message) {
+            Ok(request) => request,
+            Err(response) => {
+                println!("Rejected request: {}\n", response.message.clone().unwrap_or_default());
+                return response;
+            }
+        };
+        match Parser::get().lock().unwrap().run_batch(&vec![request.tokens()]) {
+            Ok(payloads) => {
+                println!("Processed request successfully.\n");
+                let payload = payloads.get(0).cloned().unwrap_or(Value::Null);
+                Response::ok(Some(request.id), payload)
+            },
+            Err(e) => {
+                println!("Error processing request: {}\n", e);
+                Response::error(Some(request.id), e.code, e.message, e.payload)
+            }
+        }
+    }
+
+    fn process_legacy_command(command: String) -> String {
+        print!("Received {}\n", command);
+        match Parser::get().lock().unwrap().parse_command(command.clone()) {
+            Ok(_) => {
+                println!("Processed command successfully.\n");
+                format!("Ok\n")
+            },
+            Err(e) => {
+                println!("Error processing command: {}\n", e);
+                format!("Error: {}\n", e)
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,287 @@
+use std::collections::{BTreeSet, HashMap};
+use serde::Serialize;
+use coder::main_coder::MainCoder;
+use coder::processor_coder::{ProcessorCoder, Typed, typed_literal};
+
+#[derive(Clone, Serialize)]
+pub struct ValidationIssue {
+    pub severity: String,
+    pub kind: String,
+    pub object: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn error(kind: &str, object: String, message: String) -> Self {
+        ValidationIssue { severity: "error".to_string(), kind: kind.to_string(), object, message }
+    }
+
+    fn warning(kind: &str, object: String, message: String) -> Self {
+        ValidationIssue { severity: "warning".to_string(), kind: kind.to_string(), object, message }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == "error"
+    }
+}
+
+fn sorted_typed(typed: &HashMap<String, Typed>) -> Vec<(&String, &Typed)> {
+    let mut typed = typed.iter().collect::<Vec<(&String, &Typed)>>();
+    typed.sort_by(|a, b| a.0.cmp(b.0));
+    typed
+}
+
+fn task_of(proc_name: &String) -> String {
+    proc_name.rsplit_once('.').map(|(task_name, _)| task_name.to_string()).unwrap_or_default()
+}
+
+// Defaults of the block declarations must be valid values of their type and within their limits.
+pub fn validate_block(block_name: &String, block: &ProcessorCoder) -> Vec<ValidationIssue> {
+    let mut issues: Vec<ValidationIssue> = Vec::new();
+    for settables in [block.get_states(), block.get_statics(), block.get_parameters()] {
+        for (name, typed) in sorted_typed(settables) {
+            if let Err(e) = typed_literal(&typed.data_type, &typed.default, typed.limits.as_ref()) {
+                issues.push(ValidationIssue::error("invalid_default", format!("{}.{}", block_name, name),
+                    format!("Default of {} {}.{}: {}", typed.category, block_name, name, e)));
+            }
+        }
+    }
+    issues
+}
+
+// `blocks` maps the stream processors to their block when it is part of the workspace, `stream_procs`
+// lists the stream processors of the memory map.
+pub fn validate_application(main_coder: &MainCoder, blocks: &HashMap<String, &ProcessorCoder>, stream_procs: &Vec<String>) -> Vec<ValidationIssue> {
+    let mut issues: Vec<ValidationIssue> = Vec::new();
+    let connections = main_coder.get_connections();
+    let mut proc_names = main_coder.get_stream_processors().keys().cloned().collect::<Vec<String>>();
+    proc_names.sort();
+
+    let mut assigned: BTreeSet<String> = BTreeSet::new();
+    let mut tasks = main_coder.get_task_processors().iter().collect::<Vec<_>>();
+    tasks.sort_by(|a, b| a.0.cmp(b.0));
+    for (task_name, task) in tasks.into_iter() {
+        if task.stream_processors.is_empty() {
+            issues.push(ValidationIssue::warning("empty_task", task_name.clone(), format!("Task {} runs no stream processor.", task_name)));
+        }
+        assigned.extend(task.stream_processors.iter().map(|name| format!("{}.{}", task_name, name)));
+    }
+    let mut all_procs = stream_procs.iter().chain(proc_names.iter()).cloned().collect::<BTreeSet<String>>();
+    all_procs.extend(assigned.iter().cloned());
+    for proc_name in all_procs.iter() {
+        if !assigned.contains(proc_name) {
+            issues.push(ValidationIssue::error("unassigned_processor", proc_name.clone(), format!("Stream processor {} is not run by any task.", proc_name)));
+        } else if !main_coder.get_stream_processors().contains_key(proc_name) {
+            issues.push(ValidationIssue::error("unknown_processor_type", proc_name.clone(), format!("Stream processor {} has no block type.", proc_name)));
+        }
+    }
+
+    for connection in connections.iter() {
+        let output_name = connection.from_output.rsplit('.').next().unwrap_or_default().to_string();
+        let input_name = connection.to_input.rsplit('.').next().unwrap_or_default().to_string();
+        if !all_procs.contains(&connection.from_processor) || !all_procs.contains(&connection.to_processor) {
+            issues.push(ValidationIssue::error("dangling_connection", connection.from_output.clone(),
+                format!("Connection {} -> {} refers to a missing stream processor.", connection.from_output, connection.to_input)));
+            continue;
+        }
+        if let Some(block) = blocks.get(&connection.from_processor) {
+            match block.get_outputs().get(&output_name) {
+                None => issues.push(ValidationIssue::error("unknown_port", connection.from_output.clone(),
+                    format!("Block {} has no output {}.", block.get_processor_name(), output_name))),
+                Some(data_type) if !connection.data_type.is_empty() && *data_type != connection.data_type => issues.push(ValidationIssue::error("type_mismatch",
+                    connection.from_output.clone(), format!("Output {} is {}, the connection carries {}.", connection.from_output, data_type, connection.data_type))),
+                _ => {},
+            }
+        }
+        if let Some(block) = blocks.get(&connection.to_processor) {
+            match block.get_inputs().get(&input_name) {
+                None => issues.push(ValidationIssue::error("unknown_port", connection.to_input.clone(),
+                    format!("Block {} has no input {}.", block.get_processor_name(), input_name))),
+                Some(data_type) if !connection.data_type.is_empty() && *data_type != connection.data_type => issues.push(ValidationIssue::error("type_mismatch",
+                    connection.to_input.clone(), format!("Input {} is {}, the connection carries {}.", connection.to_input, data_type, connection.data_type))),
+                _ => {},
+            }
+        }
+    }
+
+    for proc_name in proc_names.iter() {
+        let block = match blocks.get(proc_name) {
+            Some(block) => block,
+            None => continue,
+        };
+        let mut inputs = block.get_inputs().keys().collect::<Vec<&String>>();
+        inputs.sort();
+        for input_name in inputs.into_iter() {
+            let port = format!("{}.{}", proc_name, input_name);
+            if !connections.iter().any(|c| c.to_input == port) {
+                issues.push(ValidationIssue::error("unconnected_input", port.clone(), format!("Input {} has no source.", port)));
+            }
+        }
+        let mut outputs = block.get_outputs().keys().collect::<Vec<&String>>();
+        outputs.sort();
+        for output_name in outputs.into_iter() {
+            let port = format!("{}.{}", proc_name, output_name);
+            if !connections.iter().any(|c| c.from_output == port) {
+                issues.push(ValidationIssue::warning("unconsumed_output", port.clone(), format!("Output {} has no consumer.", port)));
+            }
+        }
+        // Statics configure a processor before init() and have to be set for every instance.
+        for (static_name, _) in sorted_typed(block.get_statics()) {
+            let object_name = format!("{}.{}", proc_name, static_name);
+            if !main_coder.get_settings().iter().any(|s| s.settable_name == object_name && s.settable_type == "static") {
+                issues.push(ValidationIssue::error("static_without_value", object_name.clone(), format!("Static {} is never set.", object_name)));
+            }
+        }
+    }
+
+    for setting in main_coder.get_settings().iter() {
+        let block = match blocks.get(&setting.processor_name) {
+            Some(block) => block,
+            None => continue,
+        };
+        let settable_name = setting.settable_name.rsplit('.').next().unwrap_or_default().to_string();
+        let settables = if setting.settable_type == "parameter" { block.get_parameters() } else { block.get_statics() };
+        match settables.get(&settable_name) {
+            None => issues.push(ValidationIssue::error("unknown_setting", setting.settable_name.clone(),
+                format!("Block {} has no {} {}.", block.get_processor_name(), setting.settable_type, settable_name))),
+            Some(typed) if typed.data_type != setting.data_type => issues.push(ValidationIssue::error("type_mismatch", setting.settable_name.clone(),
+                format!("{} is declared as {}, the value was set as {}.", setting.settable_name, typed.data_type, setting.data_type))),
+            _ => {},
+        }
+    }
+
+    issues.extend(connection_cycles(&all_procs, main_coder));
+    issues
+}
+
+// A cycle between processors run by different tasks makes the tasks wait on each other.
+fn connection_cycles(proc_names: &BTreeSet<String>, main_coder: &MainCoder) -> Vec<ValidationIssue> {
+    let mut issues: Vec<ValidationIssue> = Vec::new();
+    let mut successors: HashMap<&String, Vec<&String>> = HashMap::new();
+    for connection in main_coder.get_connections().iter() {
+        successors.entry(&connection.from_processor).or_default().push(&connection.to_processor);
+    }
+    let reachable_from = |start: &String| -> BTreeSet<String> {
+        let mut reached: BTreeSet<String> = BTreeSet::new();
+        let mut pending: Vec<&String> = successors.get(start).cloned().unwrap_or_default();
+        while let Some(proc_name) = pending.pop() {
+            if reached.insert(proc_name.clone()) {
+                pending.extend(successors.get(proc_name).cloned().unwrap_or_default());
+            }
+        }
+        reached
+    };
+    let reachable: HashMap<&String, BTreeSet<String>> = proc_names.iter().map(|p| (p, reachable_from(p))).collect();
+    let mut reported: BTreeSet<String> = BTreeSet::new();
+    for proc_name in proc_names.iter() {
+        if !reachable[proc_name].contains(proc_name) || reported.contains(proc_name) {
+            continue;
+        }
+        let cycle = proc_names.iter()
+            .filter(|other| reachable[proc_name].contains(*other) && reachable[*other].contains(proc_name))
+            .cloned()
+            .collect::<Vec<String>>();
+        reported.extend(cycle.iter().cloned());
+        let cycle_tasks = cycle.iter().map(|p| task_of(p)).collect::<BTreeSet<String>>();
+        if cycle_tasks.len() > 1 {
+            issues.push(ValidationIssue::error("task_cycle", proc_name.clone(), format!("Stream processors {} form a cycle across tasks {}.",
+                cycle.join(", "), cycle_tasks.into_iter().collect::<Vec<String>>().join(", "))));
+        } else {
+            issues.push(ValidationIssue::warning("cycle", proc_name.clone(), format!("Stream processors {} form a cycle.", cycle.join(", "))));
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gain_block() -> ProcessorCoder {
+        let mut block = ProcessorCoder::new("/tmp/dsp".to_string(), "Gain".to_string());
+        block.add_typed(&"input".to_string(), &"x".to_string(), &"f32".to_string());
+        block.add_typed(&"output".to_string(), &"y".to_string(), &"f32".to_string());
+        block.add_settable(&"parameter".to_string(), &"gain".to_string(), &"f32".to_string(), &"1".to_string(), Some(&"0,10".to_string()));
+        block.add_settable(&"static".to_string(), &"label".to_string(), &"String".to_string(), &"gain".to_string(), None);
+        block
+    }
+
+    // Application `app` with the tasks `main` and `other`, running Gain processors.
+    fn application(processors: &[&str]) -> MainCoder {
+        let mut main_coder = MainCoder::new("/tmp/app".to_string());
+        main_coder.add_task_processor("app.main".to_string());
+        main_coder.add_task_processor("app.other".to_string());
+        for proc_name in processors {
+            main_coder.add_stream_processor(format!("app.{}", proc_name), "dsp.Gain".to_string()).unwrap();
+            main_coder.add_setting_value(format!("app.{}", proc_name), "static".to_string(), format!("app.{}.label", proc_name), "g".to_string(), "String".to_string());
+        }
+        main_coder
+    }
+
+    fn connect(main_coder: &mut MainCoder, from: &str, to: &str) {
+        main_coder.add_connection(format!("app.{}", from), format!("app.{}.y", from), format!("app.{}", to), format!("app.{}.x", to), "f32".to_string());
+    }
+
+    fn validate(main_coder: &MainCoder, stream_procs: &[&str]) -> Vec<(String, String, String)> {
+        let block = gain_block();
+        let blocks = main_coder.get_stream_processors().keys().map(|p| (p.clone(), &block)).collect::<HashMap<String, &ProcessorCoder>>();
+        let stream_procs = stream_procs.iter().map(|p| format!("app.{}", p)).collect();
+        validate_application(main_coder, &blocks, &stream_procs).into_iter().map(|i| (i.severity, i.kind, i.object)).collect()
+    }
+
+    fn issue(severity: &str, kind: &str, object: &str) -> (String, String, String) {
+        (severity.to_string(), kind.to_string(), object.to_string())
+    }
+
+    #[test]
+    fn unconnected_ports_are_reported() {
+        let mut main_coder = application(&["main.a", "other.b"]);
+        connect(&mut main_coder, "main.a", "other.b");
+        assert_eq!(validate(&main_coder, &[]), vec![
+            issue("error", "unconnected_input", "app.main.a.x"),
+            issue("warning", "unconsumed_output", "app.other.b.y"),
+        ]);
+    }
+
+    #[test]
+    fn unassigned_processors_and_empty_tasks_are_reported() {
+        let main_coder = application(&["main.a"]);
+        let issues = validate(&main_coder, &["main.a", "main.ghost"]);
+        assert!(issues.contains(&issue("warning", "empty_task", "app.other")));
+        assert!(issues.contains(&issue("error", "unassigned_processor", "app.main.ghost")));
+        assert!(!issues.iter().any(|(_, kind, object)| kind == "unassigned_processor" && object == "app.main.a"));
+    }
+
+    #[test]
+    fn statics_must_be_set_for_every_processor() {
+        let mut main_coder = application(&["main.a"]);
+        main_coder.remove_setting(&"static".to_string(), &"app.main.a.label".to_string());
+        assert!(validate(&main_coder, &[]).contains(&issue("error", "static_without_value", "app.main.a.label")));
+    }
+
+    #[test]
+    fn cycles_inside_a_task_are_warnings() {
+        let mut main_coder = application(&["main.a", "main.b"]);
+        connect(&mut main_coder, "main.a", "main.b");
+        connect(&mut main_coder, "main.b", "main.a");
+        assert_eq!(validate(&main_coder, &[]), vec![issue("warning", "empty_task", "app.other"), issue("warning", "cycle", "app.main.a")]);
+    }
+
+    #[test]
+    fn cycles_across_tasks_are_errors() {
+        let mut main_coder = application(&["main.a", "other.b"]);
+        connect(&mut main_coder, "main.a", "other.b");
+        connect(&mut main_coder, "other.b", "main.a");
+        assert_eq!(validate(&main_coder, &[]), vec![issue("error", "task_cycle", "app.main.a")]);
+    }
+
+    #[test]
+    fn defaults_outside_their_limits_are_errors() {
+        let mut block = gain_block();
+        assert!(validate_block(&"dsp.Gain".to_string(), &block).is_empty());
+        block.add_settable(&"parameter".to_string(), &"gain".to_string(), &"f32".to_string(), &"20".to_string(), Some(&"0,10".to_string()));
+        block.add_settable(&"state".to_string(), &"count".to_string(), &"u8".to_string(), &"-1".to_string(), None);
+        let issues = validate_block(&"dsp.Gain".to_string(), &block).into_iter().map(|i| (i.severity, i.kind, i.object)).collect::<Vec<_>>();
+        assert_eq!(issues, vec![issue("error", "invalid_default", "dsp.Gain.count"), issue("error", "invalid_default", "dsp.Gain.gain")]);
+    }
+}
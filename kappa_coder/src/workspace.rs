@@ -0,0 +1,63 @@
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceSettings {
+    pub address: Option<String>,
+    pub port: Option<u16>,
+    pub dynamic_libraries: Option<String>,
+    pub kappa_library: Option<String>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Workspace {
+    pub projects: Vec<String>,
+    #[serde(default)]
+    pub settings: WorkspaceSettings,
+}
+
+impl Workspace {
+    pub fn file_path(workspace_dir: &String) -> String {
+        format!("{}/workspace.json", workspace_dir)
+    }
+
+    pub fn load(workspace_dir: &String) -> Result<Self, String> {
+        let path = Workspace::file_path(workspace_dir);
+        if !Path::new(&path).exists() {
+            return Ok(Workspace::default());
+        }
+        let json_data = std::fs::read_to_string(&path).map_err(|e| format!("Error reading workspace file: {}", e))?;
+        serde_json::from_str(&json_data).map_err(|e| format!("Error deserializing workspace: {}", e))
+    }
+
+    pub fn save(&self, workspace_dir: &String) -> Result<(), String> {
+        std::fs::create_dir_all(workspace_dir).map_err(|e| format!("Error creating workspace folder {}: {}", workspace_dir, e))?;
+        let json_string = serde_json::to_string_pretty(self).map_err(|e| format!("Error serializing workspace: {}", e))?;
+        std::fs::write(Workspace::file_path(workspace_dir), json_string).map_err(|e| format!("Error writing workspace file: {}", e))?;
+        Ok(())
+    }
+
+    // Projects are stored by canonical path so that the same folder is never listed twice.
+    fn canonical(project_path: &String) -> String {
+        std::fs::canonicalize(project_path)
+            .ok()
+            .and_then(|p| p.to_str().map(|p| p.to_string()))
+            .unwrap_or(project_path.clone())
+    }
+
+    pub fn add_project(&mut self, project_path: &String) -> bool {
+        let project_path = Workspace::canonical(project_path);
+        if self.projects.contains(&project_path) {
+            return false;
+        }
+        self.projects.push(project_path);
+        true
+    }
+
+    pub fn remove_project(&mut self, project_path: &String) -> bool {
+        let project_path = Workspace::canonical(project_path);
+        let count = self.projects.len();
+        self.projects.retain(|p| *p != project_path);
+        self.projects.len() != count
+    }
+}